@@ -0,0 +1,287 @@
+//!
+//! Headless access to the pattern engine.
+//!
+//! `dazzlefruit-cli list` prints the built-in patterns, which are the only ones the CLI can load,
+//! `dazzlefruit-cli export <pattern> <output>` renders one to a GIF, PNG or CSV file,
+//! `dazzlefruit-cli run <pattern> --device <ip>` streams one to connected Picos until stopped,
+//! `dazzlefruit-cli map --view <angle>:<folder> <output>` builds a position map from photos, and
+//! `dazzlefruit-cli scan --device <ip> --capture <command> --view <angle> <output>` builds one by
+//! photographing each pixel as it is lit.
+//!
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tokio::sync::broadcast;
+
+use dazzlefruit_lib::camera_mapping::{build_position_map, detect_view, scan_view, CommandFrameSource, FolderFrameSource, DEFAULT_SETTLE_MS, DEFAULT_THRESHOLD};
+use dazzlefruit_lib::camera_mapping::triangulation::CameraView;
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::neopixel_controller::NeopixelController;
+use dazzlefruit_lib::pattern_builder::export::{export_to_file, ExportFormat, ExportLayout, ExportOptions};
+use dazzlefruit_lib::pattern_builder::offline_render::OfflineRenderer;
+use dazzlefruit_lib::pattern_builder::pattern::Pattern;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::import::Normalization;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+use dazzlefruit_lib::pico_connection::PicoConnection;
+use dazzlefruit_lib::test_patterns::test_patterns;
+
+#[derive(Parser)]
+#[command(about = "Headless access to the dazzlefruit pattern engine.")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the built-in patterns.
+    List,
+    /// Render a pattern to a file.
+    Export {
+        /// The name of the pattern to render.
+        pattern: String,
+        /// Where to write the render. The format is taken from the extension unless --format is given.
+        output: PathBuf,
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        #[arg(long, value_enum, default_value = "strip")]
+        layout: LayoutArg,
+        #[arg(long, default_value_t = 120)]
+        frames: usize,
+        #[arg(long, default_value_t = 30.0)]
+        fps: f64,
+        /// The value of t for the first frame.
+        #[arg(long, default_value_t = 0.0)]
+        start: f64,
+        #[command(flatten)]
+        layout_args: LayoutArgs,
+        /// The size of each drawn pixel, in image pixels.
+        #[arg(long, default_value_t = 8)]
+        pixel_size: u32,
+        /// The size of the longest side of a projected layout, in image pixels.
+        #[arg(long, default_value_t = 256)]
+        image_size: u32,
+    },
+    /// Stream a pattern to one or more Picos until interrupted.
+    Run {
+        /// The name of the built-in pattern to show.
+        pattern: String,
+        /// A Pico to stream to, as IP[:TCP_PORT[:UDP_PORT]]. Can be given more than once.
+        #[arg(long = "device", required = true)]
+        devices: Vec<String>,
+        #[command(flatten)]
+        layout_args: LayoutArgs,
+    },
+    /// Build a position map from photos of each pixel lit in turn.
+    Map {
+        /// Where to write the position map (JSON).
+        output: PathBuf,
+        /// A folder of photos as ANGLE:FOLDER, where ANGLE is how many degrees around the layout
+        /// the camera moved from the first view. Give this once per view.
+        #[arg(long = "view", required = true)]
+        views: Vec<String>,
+        #[arg(long, default_value_t = 150)]
+        pixels: usize,
+        /// The brightness above the background, from 0 to 1, that counts as a lit pixel.
+        #[arg(long, default_value_t = DEFAULT_THRESHOLD)]
+        threshold: f32,
+    },
+    /// Build a position map by lighting each pixel on a Pico in turn and photographing it.
+    Scan {
+        /// Where to write the position map (JSON).
+        output: PathBuf,
+        /// The Pico driving the layout, as IP[:TCP_PORT[:UDP_PORT]].
+        #[arg(long)]
+        device: String,
+        /// The program that takes a photo, with {} where it should write the PNG, such as
+        /// "fswebcam --no-banner --png 0 {}".
+        #[arg(long)]
+        capture: String,
+        /// How many degrees around the layout the camera is from the first view. Give this once
+        /// per view, in the order they are taken.
+        #[arg(long = "view", required = true, allow_negative_numbers = true)]
+        angles: Vec<f64>,
+        #[arg(long, default_value_t = 150)]
+        pixels: u16,
+        /// How long to wait after lighting each pixel before photographing it, in milliseconds.
+        #[arg(long, default_value_t = DEFAULT_SETTLE_MS)]
+        settle_ms: u64,
+        /// The brightness above the background, from 0 to 1, that counts as a lit pixel.
+        #[arg(long, default_value_t = DEFAULT_THRESHOLD)]
+        threshold: f32,
+    },
+}
+
+#[derive(Args)]
+struct LayoutArgs {
+    /// How many pixels to render. Defaults to the position map's count, or 150 without one.
+    #[arg(long)]
+    pixels: Option<usize>,
+    /// A position map to render with: JSON, CSV or an xLights model.
+    #[arg(long)]
+    positions: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum FormatArg { Gif, Png, Csv }
+
+#[derive(Copy, Clone, ValueEnum)]
+enum LayoutArg { Strip, Flat, Isometric }
+
+fn load_patterns(layout_args: LayoutArgs) -> Result<PatternBuilder, String> {
+    let mut pattern_builder = PatternBuilder::new(layout_args.pixels.unwrap_or(150), Arc::new(NullEventSink));
+    if let Some(path) = layout_args.positions {
+        pattern_builder.import_position_map(path, None, &Normalization::default())?;
+    }
+    if let Some(pixels) = layout_args.pixels {
+        pattern_builder.set_num_pixels(pixels)?;
+    }
+    for pattern in test_patterns(pattern_builder.pattern_context()) {
+        pattern_builder.load_pattern(pattern);
+    }
+    Ok(pattern_builder)
+}
+
+fn find_pattern<'a>(pattern_builder: &'a PatternBuilder, name: &str) -> Result<&'a Pattern, String> {
+    pattern_builder.patterns()
+        .find(|pattern| pattern.name().eq_ignore_ascii_case(name))
+        .ok_or(format!("Unknown pattern \"{}\". Use `list` to see the available patterns.", name))
+}
+
+fn parse_device(device: &str) -> Result<(String, u16, u16), String> {
+    let mut parts = device.split(':');
+    let ip = parts.next().unwrap_or_default().to_string();
+    let mut port = |default: u16| parts.next()
+        .map(|port| port.parse().map_err(|_| format!("Invalid port in device {}", device)))
+        .unwrap_or(Ok(default));
+    let tcp_port = port(4242)?;
+    let udp_port = port(4243)?;
+    Ok((ip, tcp_port, udp_port))
+}
+
+fn map_positions(output: PathBuf, views: Vec<String>, num_pixels: usize, threshold: f32) -> Result<(), String> {
+    let mut detections = vec![];
+    for view in views {
+        let (angle, dir) = view.split_once(':').ok_or(format!("Expected ANGLE:FOLDER, got {}", view))?;
+        let angle: f64 = angle.parse().map_err(|_| format!("Invalid angle in view {}", view))?;
+        detections.push(detect_view(&mut FolderFrameSource::new(dir), CameraView::orbit(angle), num_pixels, threshold)?);
+    }
+    write_position_map(&output, &build_position_map(&detections, num_pixels))
+}
+
+async fn scan_positions(output: PathBuf, device: String, capture: String, angles: Vec<f64>, num_pixels: u16, settle: Duration, threshold: f32) -> Result<(), String> {
+    let mut source = CommandFrameSource::new(&capture)?;
+    let (ip, tcp_port, udp_port) = parse_device(&device)?;
+    let connection = PicoConnection::new(ip, tcp_port, udp_port, Arc::new(NullEventSink)).await?;
+    // Only single frames are shown, so no pattern updates are needed.
+    let (_, pattern_updates) = broadcast::channel(1);
+    let controller = NeopixelController::new(connection, num_pixels, pattern_updates).await?;
+
+    let mut detections = vec![];
+    for (i, angle) in angles.into_iter().enumerate() {
+        if i > 0 {
+            println!("Move the camera to {} degrees, then press Enter.", angle);
+            std::io::stdin().read_line(&mut String::new()).map_err(|e| e.to_string())?;
+        }
+        println!("Scanning the view from {} degrees.", angle);
+        detections.push(scan_view(&controller, &mut source, CameraView::orbit(angle), settle, threshold).await?);
+    }
+    write_position_map(&output, &build_position_map(&detections, num_pixels as usize))
+}
+
+fn write_position_map(output: &Path, position_map: &PositionMap) -> Result<(), String> {
+    let mapped = position_map.iter().flatten().count();
+    let contents = serde_json::to_string(position_map).map_err(|e| e.to_string())?;
+    std::fs::write(output, contents).map_err(|e| e.to_string())?;
+    println!("Mapped {} of {} pixels to {}.", mapped, position_map.len(), output.display());
+    Ok(())
+}
+
+async fn run_pattern(pattern_builder: &PatternBuilder, pattern: &Pattern, devices: Vec<String>) -> Result<(), String> {
+    let num_pixels = pattern_builder.pattern_context().borrow().num_pixels();
+    let num_pixels = u16::try_from(num_pixels).map_err(|_| format!("The layout has {} pixels, more than a strip can have.", num_pixels))?;
+    let mut controllers = vec![];
+    for device in devices {
+        let (ip, tcp_port, udp_port) = parse_device(&device)?;
+        let controller = match PicoConnection::new(ip, tcp_port, udp_port, Arc::new(NullEventSink)).await {
+            Ok(connection) => NeopixelController::new(connection, num_pixels, pattern_builder.pattern_update_receiver()).await,
+            Err(e) => Err(e),
+        };
+        match controller {
+            Ok(controller) => {
+                controller.show_pattern_id(pattern.id()).await;
+                println!("Streaming {} to {}.", pattern.name(), device);
+                controllers.push(controller);
+            },
+            Err(e) => eprintln!("Failed to connect to {}: {}", device, e),
+        }
+    }
+    if controllers.is_empty() {
+        return Err(format!("No devices connected."));
+    }
+
+    tokio::signal::ctrl_c().await.map_err(|e| e.to_string())?;
+    for controller in controllers {
+        controller.show_none().await;
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    match cli.command {
+        Command::List => {
+            for pattern in load_patterns(LayoutArgs { pixels: Some(0), positions: None })?.patterns() {
+                println!("{}", pattern.name());
+            }
+            Ok(())
+        },
+        Command::Export { pattern, output, format, layout, frames, fps, start, layout_args, pixel_size, image_size } => {
+            let format = match format {
+                Some(FormatArg::Gif) => ExportFormat::Gif,
+                Some(FormatArg::Png) => ExportFormat::Png,
+                Some(FormatArg::Csv) => ExportFormat::Csv,
+                None => ExportFormat::from_path(&output)
+                    .ok_or(format!("Unknown export format for {}. Use --format.", output.display()))?,
+            };
+            let options = ExportOptions {
+                num_frames: frames,
+                fps,
+                start_t: start,
+                layout: match layout {
+                    LayoutArg::Strip => ExportLayout::Strip,
+                    LayoutArg::Flat => ExportLayout::Flat,
+                    LayoutArg::Isometric => ExportLayout::Isometric,
+                },
+                pixel_size,
+                image_size,
+            };
+            let pattern_builder = load_patterns(layout_args)?;
+            let pattern = find_pattern(&pattern_builder, &pattern)?;
+            export_to_file(&mut OfflineRenderer::new(pattern), &options, format, &output)?;
+            println!("Exported {} frames of {} to {}.", frames, pattern.name(), output.display());
+            Ok(())
+        },
+        Command::Run { pattern, devices, layout_args } => {
+            let mut pattern_builder = load_patterns(layout_args)?;
+            let pattern_id = find_pattern(&pattern_builder, &pattern)?.id();
+            pattern_builder.watch_pattern(pattern_id)?;
+            let pattern = find_pattern(&pattern_builder, &pattern)?;
+            dazzlefruit_lib::runtime::block_on(run_pattern(&pattern_builder, pattern, devices))
+        },
+        Command::Map { output, views, pixels, threshold } => map_positions(output, views, pixels, threshold),
+        Command::Scan { output, device, capture, angles, pixels, settle_ms, threshold } => {
+            dazzlefruit_lib::runtime::block_on(scan_positions(output, device, capture, angles, pixels, Duration::from_millis(settle_ms), threshold))
+        },
+    }
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
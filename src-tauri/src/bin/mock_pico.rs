@@ -0,0 +1,37 @@
+//!
+//! Runs a simulated Pico, so the app can be demoed and developed without a board.
+//!
+//! Usage: `mock_pico [ip] [tcp_port] [udp_port]` (defaults: `127.0.0.1 4242 4243`).
+//!
+
+use std::env;
+use std::time::Duration;
+
+use dazzlefruit_lib::pico_connection::mock_device::MockPicoDevice;
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let ip = args.first().map(String::as_str).unwrap_or("127.0.0.1");
+    let tcp_port = args.get(1).map(|port| port.parse().expect("Invalid TCP port.")).unwrap_or(4242);
+    let udp_port = args.get(2).map(|port| port.parse().expect("Invalid UDP port.")).unwrap_or(4243);
+
+    let device = MockPicoDevice::bind(ip, tcp_port, udp_port).await
+        .expect("Failed to bind mock device ports.");
+    println!("Mock Pico listening on {} (TCP {}, UDP {}).", ip, device.tcp_port(), device.udp_port());
+
+    let mut last_frame_count = 0;
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let log = device.log();
+        if log.neopixel_frames.len() != last_frame_count {
+            println!(
+                "{} pixels, {} frames received ({} since last update).",
+                log.neopixel_num_pixels.unwrap_or(0),
+                log.neopixel_frames.len(),
+                log.neopixel_frames.len().saturating_sub(last_frame_count),
+            );
+            last_frame_count = log.neopixel_frames.len();
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//!
+//! Working out where each pixel is by photographing it.
+//!
+//! Each pixel is lit on its own and photographed from a few directions around the layout. The
+//! brightest blob in each photo gives the pixel's position as seen from that direction, and the
+//! views are then combined into a 3D [`PositionMap`].
+//!
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use nalgebra_glm::DVec2;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use tauri::AppHandle;
+#[cfg(feature = "gui")]
+use crate::runtime::spawn_blocking;
+#[cfg(feature = "gui")]
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "gui")]
+use crate::{AppState, LockedAppState};
+use crate::camera_mapping::detection::{find_brightest_blob, GrayImage};
+use crate::camera_mapping::triangulation::{triangulate, CameraView};
+use crate::neopixel_controller::NeopixelController;
+#[cfg(feature = "gui")]
+use crate::neopixel_controller::switch_neopixel_pattern;
+#[cfg(feature = "gui")]
+use crate::neopixel_controller::transition::Transition;
+use crate::pattern_builder::component::frame::{ColorPixel, Frame, Pixel};
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+pub mod detection;
+pub mod triangulation;
+
+/// The brightness above the background a pixel must reach to count as seen.
+pub const DEFAULT_THRESHOLD: f32 = 0.2;
+/// How long to let the strip and camera settle after each change when scanning, in milliseconds.
+pub const DEFAULT_SETTLE_MS: u64 = 300;
+
+/// Somewhere to get photos of the layout from.
+pub trait FrameSource: Send {
+    ///
+    /// Captures the layout with only `lit_pixel` on, or with every pixel off for `None`.
+    /// Returns `Ok(None)` if there is no photo for it.
+    ///
+    fn capture(&mut self, lit_pixel: Option<usize>) -> Result<Option<GrayImage>, String>;
+}
+
+///
+/// Photos taken beforehand and saved to a folder as `<pixel index>.png`, with an optional
+/// `background.png` taken with every pixel off.
+///
+pub struct FolderFrameSource {
+    dir: PathBuf,
+}
+
+impl FolderFrameSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl FrameSource for FolderFrameSource {
+    fn capture(&mut self, lit_pixel: Option<usize>) -> Result<Option<GrayImage>, String> {
+        let path = match lit_pixel {
+            Some(index) => self.dir.join(format!("{}.png", index)),
+            None => self.dir.join("background.png"),
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+        GrayImage::load_png(path).map(Some)
+    }
+}
+
+///
+/// Takes photos by running a capture program, such as `fswebcam --no-banner --png 0 {}`. Any `{}`
+/// in the arguments is replaced with the path the program should write its PNG to.
+///
+pub struct CommandFrameSource {
+    program: String,
+    args: Vec<String>,
+    photo_path: PathBuf,
+}
+
+impl CommandFrameSource {
+    pub fn new(command: &str) -> Result<Self, String> {
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts.next().ok_or("The capture command is empty.")?;
+        let args: Vec<String> = parts.collect();
+        if !args.iter().any(|arg| arg.contains("{}")) {
+            return Err(format!("The capture command must contain {{}} where the photo should be written."));
+        }
+        Ok(Self {
+            program,
+            args,
+            photo_path: std::env::temp_dir().join(format!("dazzlefruit-capture-{}.png", rand::random::<u32>())),
+        })
+    }
+}
+
+impl FrameSource for CommandFrameSource {
+    fn capture(&mut self, _: Option<usize>) -> Result<Option<GrayImage>, String> {
+        let photo_path = self.photo_path.to_string_lossy();
+        let status = process::Command::new(&self.program)
+            .args(self.args.iter().map(|arg| arg.replace("{}", &photo_path)))
+            .status()
+            .map_err(|e| format!("Failed to run {}: {}", self.program, e))?;
+        if !status.success() {
+            return Err(format!("{} failed: {}", self.program, status));
+        }
+        let image = GrayImage::load_png(&self.photo_path);
+        let _ = fs::remove_file(&self.photo_path);
+        image.map(Some)
+    }
+}
+
+/// Where each pixel was seen from one direction, in image pixels from the centre of the photo.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ViewDetections {
+    pub view: CameraView,
+    #[serde(with = "points_serde")]
+    pub points: Vec<Option<DVec2>>,
+}
+
+/// Saves points as `[x, y]` pairs, like [`CameraView`]'s axes.
+mod points_serde {
+    use nalgebra_glm::DVec2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(points: &[Option<DVec2>], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        points.iter()
+            .map(|point| point.map(|point| [point.x, point.y]))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Option<DVec2>>, D::Error> where D: Deserializer<'de> {
+        Ok(Vec::<Option<[f64; 2]>>::deserialize(deserializer)?.into_iter()
+            .map(|point| point.map(DVec2::from))
+            .collect())
+    }
+}
+
+fn detect(source: &mut dyn FrameSource, lit_pixel: usize, background: Option<&GrayImage>, threshold: f32) -> Result<Option<DVec2>, String> {
+    Ok(source.capture(Some(lit_pixel))?
+        .and_then(|image| find_brightest_blob(&image, background, threshold)))
+}
+
+/// Finds each pixel in photos that have already been taken.
+pub fn detect_view(source: &mut dyn FrameSource, view: CameraView, num_pixels: usize, threshold: f32) -> Result<ViewDetections, String> {
+    let background = source.capture(None)?;
+    let points = (0..num_pixels)
+        .map(|i| detect(source, i, background.as_ref(), threshold))
+        .collect::<Result<_, String>>()?;
+    Ok(ViewDetections { view, points })
+}
+
+///
+/// Lights each pixel in turn on the strip and captures it from `source`, waiting `settle` after
+/// each change so the camera sees the new state. The strip is left dark afterwards.
+///
+/// The frames are shown over whatever the strip was showing, so a pattern selected on the
+/// controller should be switched off first to release it.
+///
+pub async fn scan_view(controller: &NeopixelController, source: &mut dyn FrameSource, view: CameraView, settle: Duration, threshold: f32) -> Result<ViewDetections, String> {
+    let num_pixels = controller.num_pixels() as usize;
+    controller.show_frame(Frame::empty(num_pixels)).await;
+    tokio::time::sleep(settle).await;
+    let background = source.capture(None)?;
+
+    let mut points = Vec::with_capacity(num_pixels);
+    for i in 0..num_pixels {
+        controller.show_frame(single_pixel_frame(num_pixels, Some(i))).await;
+        tokio::time::sleep(settle).await;
+        points.push(detect(source, i, background.as_ref(), threshold)?);
+    }
+    controller.show_frame(Frame::empty(num_pixels)).await;
+    Ok(ViewDetections { view, points })
+}
+
+/// A frame with only `lit_pixel` on, at full white.
+pub fn single_pixel_frame(num_pixels: usize, lit_pixel: Option<usize>) -> Frame<ColorPixel> {
+    (0..num_pixels)
+        .map(|i| if Some(i) == lit_pixel { ColorPixel::new(1.0, 1.0, 1.0, 1.0) } else { ColorPixel::empty() })
+        .collect()
+}
+
+///
+/// Combines the detections from every view into 3D positions. Pixels that weren't seen from
+/// enough directions to place them have no position.
+///
+pub fn build_position_map(views: &[ViewDetections], num_pixels: usize) -> PositionMap<'static> {
+    let positions = (0..num_pixels)
+        .map(|i| {
+            let observations: Vec<(CameraView, DVec2)> = views.iter()
+                .filter_map(|view| view.points.get(i).copied().flatten().map(|point| (view.view, point)))
+                .collect();
+            triangulate(&observations)
+        })
+        .collect();
+    PositionMap::new(positions)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FolderView {
+    pub path: String,
+    /// Degrees around the layout from the first view. See [`CameraView::orbit`].
+    pub angle: f64,
+}
+
+/// Lights a single pixel, or turns every pixel off, so it can be photographed by hand.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn light_mapping_pixel(pixel_index: Option<usize>, app_handle: AppHandle, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    let num_pixels = state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?.num_pixels() as usize;
+    if let Some(index) = pixel_index.filter(|index| *index >= num_pixels) {
+        return Err(format!("Pixel {} is out of range, there are {} pixels.", index, num_pixels));
+    }
+    state.playlist_player.stop();
+    switch_neopixel_pattern(&mut state, None, Transition::default(), app_handle).await?;
+    if let Some(controller) = &state.neopixel_controller {
+        controller.show_frame(single_pixel_frame(num_pixels, pixel_index)).await;
+    }
+    Ok(())
+}
+
+///
+/// Scans one view of the layout with a capture program, see [`CommandFrameSource`]. Returns the
+/// detections for [`map_positions_from_scans`] once every view has been scanned.
+///
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn scan_mapping_view(capture_command: String, angle: f64, settle_ms: Option<u64>, threshold: Option<f32>, app_handle: AppHandle, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let mut source = CommandFrameSource::new(&capture_command)?;
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?;
+    state.playlist_player.stop();
+    switch_neopixel_pattern(&mut state, None, Transition::default(), app_handle).await?;
+
+    let state: RwLockReadGuard<AppState> = state.downgrade();
+    let controller = state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?;
+    let settle = Duration::from_millis(settle_ms.unwrap_or(DEFAULT_SETTLE_MS));
+    let detections = scan_view(controller, &mut source, CameraView::orbit(angle), settle, threshold.unwrap_or(DEFAULT_THRESHOLD)).await?;
+    serde_json::to_string(&detections).map_err(|e| e.to_string())
+}
+
+/// Builds the position map from the views returned by [`scan_mapping_view`].
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn map_positions_from_scans(views: Vec<ViewDetections>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let num_pixels = tauri_state.0.read().await.pattern_builder.pattern_context().borrow().num_pixels();
+    let position_map = build_position_map(&views, num_pixels);
+
+    let json = serde_json::to_string(&position_map).map_err(|e| e.to_string())?;
+    let state = tauri_state.0.read().await;
+    state.pattern_builder.set_position_map(position_map);
+    state.check_pixel_count();
+    Ok(json)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn map_positions_from_folders(views: Vec<FolderView>, threshold: Option<f32>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let num_pixels = tauri_state.0.read().await.pattern_builder.pattern_context().borrow().num_pixels();
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let position_map = spawn_blocking(move || {
+        let detections = views.iter()
+            .map(|folder| detect_view(&mut FolderFrameSource::new(&folder.path), CameraView::orbit(folder.angle), num_pixels, threshold))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok::<_, String>(build_position_map(&detections, num_pixels))
+    }).await.map_err(|e| e.to_string())??;
+
+    let json = serde_json::to_string(&position_map).map_err(|e| e.to_string())?;
+    let state = tauri_state.0.read().await;
+    state.pattern_builder.set_position_map(position_map);
+    state.check_pixel_count();
+    Ok(json)
+}
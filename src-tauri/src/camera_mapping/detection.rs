@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use nalgebra_glm::DVec2;
+
+/// Pixels this close to the peak brightness, relative to it, count as part of the blob.
+const BLOB_FRACTION: f32 = 0.5;
+
+/// A greyscale photo with brightness values from 0 to 1.
+#[derive(Clone, Debug)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+}
+
+impl GrayImage {
+    pub fn new(width: usize, height: usize, pixels: Vec<f32>) -> Result<Self, String> {
+        if pixels.len() != width * height {
+            return Err(format!("A {}x{} image needs {} pixels, got {}.", width, height, width * height, pixels.len()));
+        }
+        Ok(Self { width, height, pixels })
+    }
+
+    /// Reads a PNG of any colour type, using its luminance as brightness.
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let bytes = &buffer[..info.buffer_size()];
+
+        let luminance = |r: u8, g: u8, b: u8| (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0;
+        let pixels = match info.color_type {
+            png::ColorType::Grayscale => bytes.iter().map(|v| *v as f32 / 255.0).collect(),
+            png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|c| c[0] as f32 / 255.0).collect(),
+            png::ColorType::Rgb => bytes.chunks_exact(3).map(|c| luminance(c[0], c[1], c[2])).collect(),
+            png::ColorType::Rgba => bytes.chunks_exact(4).map(|c| luminance(c[0], c[1], c[2])).collect(),
+            png::ColorType::Indexed => return Err(format!("Cannot read {}: unexpanded palette.", path.display())),
+        };
+        Self::new(info.width as usize, info.height as usize, pixels)
+    }
+
+    /// Writes the image as an 8-bit greyscale PNG.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let bytes: Vec<u8> = self.pixels.iter().map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8).collect();
+        encoder.write_header()
+            .and_then(|mut png_writer| png_writer.write_image_data(&bytes))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+///
+/// Finds the brightest spot in `image`, as the brightness-weighted centre of the blob around the
+/// brightest pixel. With a `background` of the scene with every pixel off, ambient light is
+/// subtracted first.
+///
+/// The result is in image pixels relative to the centre of the image, with y pointing up. If
+/// nothing is brighter than `threshold`, the pixel wasn't seen and `None` is returned.
+///
+pub fn find_brightest_blob(image: &GrayImage, background: Option<&GrayImage>, threshold: f32) -> Option<DVec2> {
+    let background = background.filter(|background| background.width == image.width && background.height == image.height);
+    let brightness: Vec<f32> = match background {
+        Some(background) => image.pixels.iter().zip(&background.pixels).map(|(v, b)| (v - b).max(0.0)).collect(),
+        None => image.pixels.clone(),
+    };
+    let (peak_index, peak) = brightness.iter().copied().enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    if peak <= 0.0 || peak < threshold {
+        return None;
+    }
+
+    // Flood fill out from the peak over everything bright enough to be part of the same blob.
+    let cutoff = peak * BLOB_FRACTION;
+    let mut visited = vec![false; brightness.len()];
+    let mut stack = vec![peak_index];
+    visited[peak_index] = true;
+    let (mut total, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+    while let Some(index) = stack.pop() {
+        let (x, y) = (index % image.width, index / image.width);
+        // Weighting by the brightness above the cutoff fades pixels out at the blob's edge, so where
+        // the edge happens to fall doesn't pull the centre around.
+        let weight = (brightness[index] - cutoff) as f64;
+        total += weight;
+        sum_x += weight * x as f64;
+        sum_y += weight * y as f64;
+
+        let neighbours = [
+            (x > 0).then(|| index - 1),
+            (x + 1 < image.width).then(|| index + 1),
+            (y > 0).then(|| index - image.width),
+            (y + 1 < image.height).then(|| index + image.width),
+        ];
+        for neighbour in neighbours.into_iter().flatten() {
+            if !visited[neighbour] && brightness[neighbour] >= cutoff {
+                visited[neighbour] = true;
+                stack.push(neighbour);
+            }
+        }
+    }
+    let centre = DVec2::new((image.width - 1) as f64 / 2.0, (image.height - 1) as f64 / 2.0);
+    Some(DVec2::new(sum_x / total - centre.x, centre.y - sum_y / total))
+}
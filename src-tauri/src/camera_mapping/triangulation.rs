@@ -0,0 +1,53 @@
+use nalgebra_glm::{DMat3, DVec2, DVec3};
+use serde::{Deserialize, Serialize};
+
+///
+/// Where a camera was looking from. Views are orthographic: a point's image coordinates are its
+/// distance along the `right` and `up` axes, in image pixels from the centre of the image. This
+/// suits photos taken from a distance, where perspective barely changes the layout.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraView {
+    pub right: [f64; 3],
+    pub up: [f64; 3],
+}
+
+impl CameraView {
+    ///
+    /// A camera level with the layout, having moved `angle` degrees around the vertical (Y) axis
+    /// from the front. Turning the layout by `-angle` in front of a fixed camera is equivalent.
+    ///
+    pub fn orbit(angle: f64) -> Self {
+        let angle = angle.to_radians();
+        Self {
+            right: [angle.cos(), 0.0, -angle.sin()],
+            up: [0.0, 1.0, 0.0],
+        }
+    }
+
+    pub fn project(&self, pos: DVec3) -> DVec2 {
+        DVec2::new(DVec3::from(self.right).dot(&pos), DVec3::from(self.up).dot(&pos))
+    }
+}
+
+///
+/// Finds the position that best matches where a pixel was seen from each view, by least
+/// squares. Returns `None` if the views don't pin the position down, like when it was only seen
+/// from one side.
+///
+pub fn triangulate(observations: &[(CameraView, DVec2)]) -> Option<DVec3> {
+    let mut normal = DMat3::zeros();
+    let mut target = DVec3::zeros();
+    for (view, seen) in observations {
+        for (axis, coord) in [(DVec3::from(view.right), seen.x), (DVec3::from(view.up), seen.y)] {
+            normal += axis * axis.transpose();
+            target += axis * coord;
+        }
+    }
+    // Nearly parallel views leave one direction unconstrained, which shows up as a tiny determinant.
+    let scale = normal.trace() / 3.0;
+    if scale <= 0.0 || normal.determinant() < 1e-3 * scale.powi(3) {
+        return None;
+    }
+    normal.try_inverse().map(|inverse| inverse * target)
+}
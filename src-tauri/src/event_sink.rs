@@ -0,0 +1,86 @@
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter};
+use crate::tauri_events::{ConnectionOpenPayload, DebugMessagePayload, NeopixelOutputPayload, PixelUpdatePayload};
+
+/// An event sent from the backend to whatever is presenting it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AppEvent {
+    PixelUpdate(PixelUpdatePayload),
+    NeopixelOutput(NeopixelOutputPayload),
+    DebugMessage(DebugMessagePayload),
+    ConnectionOpen(ConnectionOpenPayload),
+    ConnectionClose,
+}
+
+impl AppEvent {
+    /// The name the frontend listens for.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::PixelUpdate(_) => "pixel-update",
+            AppEvent::NeopixelOutput(_) => "neopixel-output",
+            AppEvent::DebugMessage(_) => "debug-println",
+            AppEvent::ConnectionOpen(_) => "connection-open",
+            AppEvent::ConnectionClose => "connection-close",
+        }
+    }
+}
+
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: AppEvent);
+}
+
+/// Sends events to the tauri frontend.
+#[cfg(feature = "gui")]
+pub struct TauriEventSink(pub AppHandle);
+
+#[cfg(feature = "gui")]
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: AppEvent) {
+        let name = event.name();
+        let result = match event {
+            AppEvent::PixelUpdate(payload) => self.0.emit(name, payload),
+            AppEvent::NeopixelOutput(payload) => self.0.emit(name, payload),
+            AppEvent::DebugMessage(payload) => self.0.emit(name, payload),
+            AppEvent::ConnectionOpen(payload) => self.0.emit(name, payload),
+            AppEvent::ConnectionClose => self.0.emit(name, ()),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to emit {} event: {}", name, e);
+        }
+    }
+}
+
+/// Keeps every event, so they can be inspected later.
+#[derive(Default)]
+pub struct MemoryEventSink {
+    events: Mutex<Vec<AppEvent>>,
+}
+
+impl MemoryEventSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<AppEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Removes and returns the events received so far.
+    pub fn take(&self) -> Vec<AppEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl EventSink for MemoryEventSink {
+    fn emit(&self, event: AppEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+/// Discards every event, for running without a frontend.
+pub struct NullEventSink;
+
+impl EventSink for NullEventSink {
+    fn emit(&self, _event: AppEvent) {}
+}
@@ -0,0 +1,139 @@
+#![feature(iter_next_chunk)]
+#![feature(associated_type_defaults)]
+#![feature(map_try_insert)]
+
+extern crate core;
+
+#[cfg(feature = "gui")]
+use std::sync::Arc;
+#[cfg(feature = "gui")]
+use tauri::Manager;
+#[cfg(feature = "gui")]
+use tokio::sync::RwLock;
+#[cfg(feature = "gui")]
+use crate::neopixel_controller::NeopixelController;
+#[cfg(feature = "gui")]
+use crate::playlist::PlaylistPlayer;
+#[cfg(feature = "gui")]
+use crate::pattern_builder::PatternBuilder;
+#[cfg(feature = "gui")]
+use crate::pico_connection::PicoConnectionHandle;
+#[cfg(feature = "gui")]
+use crate::event_sink::{AppEvent, EventSink, TauriEventSink};
+#[cfg(feature = "gui")]
+use crate::tauri_events::DebugMessagePayload;
+
+pub mod pico_connection;
+pub mod neopixel_controller;
+pub mod tauri_events;
+pub mod event_sink;
+pub mod pattern_builder;
+pub mod test_patterns;
+pub mod playlist;
+pub mod camera_mapping;
+pub mod runtime;
+
+/// The pixel count patterns render until a layout is loaded or a strip is set up.
+#[cfg(feature = "gui")]
+const DEFAULT_NUM_PIXELS: usize = 150;
+
+#[cfg(feature = "gui")]
+pub struct AppState {
+    connection: Option<PicoConnectionHandle>,
+    neopixel_controller: Option<NeopixelController>,
+    pattern_builder: PatternBuilder,
+    playlist_player: PlaylistPlayer,
+    event_sink: Arc<dyn EventSink>,
+}
+
+#[cfg(feature = "gui")]
+pub struct LockedAppState(pub RwLock<AppState>);
+
+#[cfg(feature = "gui")]
+impl AppState {
+    fn debug_println(&self, message: &str) {
+        self.event_sink.emit(AppEvent::DebugMessage(DebugMessagePayload{ message: message.parse().unwrap() }));
+    }
+
+    /// Warns if the layout patterns render for has a different number of pixels to the strip.
+    fn check_pixel_count(&self) {
+        let Some(controller) = &self.neopixel_controller else {
+            return;
+        };
+        let layout_pixels = self.pattern_builder.pattern_context().borrow().num_pixels();
+        if layout_pixels != controller.num_pixels() as usize {
+            let message = format!("The layout has {} pixels but the strip has {}, so frames will be padded or cut short.", layout_pixels, controller.num_pixels());
+            eprintln!("{}", message);
+            self.debug_println(&message);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_shell::init())
+        .setup(move |app| {
+            let event_sink: Arc<dyn EventSink> = Arc::new(TauriEventSink(app.handle().clone()));
+            let mut state = AppState {
+                connection: None,
+                neopixel_controller: None,
+                pattern_builder: PatternBuilder::new(DEFAULT_NUM_PIXELS, event_sink.clone()),
+                playlist_player: PlaylistPlayer::default(),
+                event_sink,
+            };
+            for pattern in test_patterns::test_patterns(state.pattern_builder.pattern_context()) {
+                state.pattern_builder.load_pattern(pattern);
+            }
+            app.manage(LockedAppState(RwLock::new(state)));
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            pico_connection::connect,
+            pico_connection::disconnect,
+            neopixel_controller::init_neopixel,
+            neopixel_controller::init_virtual_neopixel,
+            neopixel_controller::set_neopixel_pattern,
+            neopixel_controller::start_neopixel_recording,
+            neopixel_controller::stop_neopixel_recording,
+            neopixel_controller::play_neopixel_recording,
+            neopixel_controller::stop_neopixel_playback,
+            pattern_builder::view_open_patterns,
+            pattern_builder::view_pattern,
+            pattern_builder::update_property,
+            pattern_builder::reseed_pattern,
+            pattern_builder::pattern_playback,
+            pattern_builder::pattern_stats,
+            pattern_builder::watch_pattern,
+            pattern_builder::unwatch_pattern,
+            pattern_builder::set_pattern_fps,
+            pattern_builder::set_pattern_running,
+            pattern_builder::seek_pattern,
+            pattern_builder::step_pattern,
+            pattern_builder::reset_pattern_time,
+            pattern_builder::set_pattern_loop,
+            pattern_builder::position_map,
+            pattern_builder::load_position_map,
+            pattern_builder::import_position_map,
+            pattern_builder::save_position_map,
+            pattern_builder::generate_position_map,
+            pattern_builder::set_pixel_position,
+            pattern_builder::view_zones,
+            pattern_builder::set_zone,
+            pattern_builder::remove_zone,
+            pattern_builder::export_pattern,
+            playlist::view_playlist,
+            playlist::set_playlist,
+            playlist::start_playlist,
+            playlist::stop_playlist,
+            playlist::save_playlist,
+            playlist::load_playlist,
+            camera_mapping::light_mapping_pixel,
+            camera_mapping::scan_mapping_view,
+            camera_mapping::map_positions_from_scans,
+            camera_mapping::map_positions_from_folders,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
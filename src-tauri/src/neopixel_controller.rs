@@ -1,18 +1,50 @@
 use std::sync::Arc;
-use tokio::sync::{broadcast, Mutex, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{broadcast, Mutex};
+#[cfg(feature = "gui")]
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
 
-use tauri::async_runtime::{JoinHandle, spawn};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+use crate::runtime::{JoinHandle, spawn};
+#[cfg(feature = "gui")]
 use crate::{AppState, LockedAppState};
+#[cfg(feature = "gui")]
+use crate::event_sink::AppEvent;
+use crate::neopixel_controller::output_format::NeopixelOutputFormat;
+use crate::neopixel_controller::recording::{FrameRecorder, Recording};
+use crate::neopixel_controller::transition::{ActiveTransition, Transition};
 use crate::pattern_builder::component::frame::{ColorPixel, Frame};
 use crate::pattern_builder::component::RandId;
+use crate::pattern_builder::PatternBuilder;
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
 use crate::pico_connection::packet_types::{TcpPacketType, UdpPacketType};
 use crate::pico_connection::PicoConnectionHandle;
+#[cfg(feature = "gui")]
+use crate::tauri_events::NeopixelOutputPayload;
+
+pub mod output_format;
+pub mod recording;
+pub mod transition;
 
 #[derive(Clone)]
 struct NeopixelControllerData {
-    pico_connection: PicoConnectionHandle,
+    pico_connection: Option<PicoConnectionHandle>,
     selected_pattern_id: Arc<Mutex<Option<RandId>>>,
+    /// The pattern the strip goes back to once a recording or single frame stops being shown.
+    paused_pattern_id: Arc<std::sync::Mutex<Option<RandId>>>,
+    active_transition: Arc<Mutex<Option<ActiveTransition>>>,
+    transition_handle: Arc<std::sync::Mutex<Option<JoinHandle<()>>>>,
+    last_frame: Arc<std::sync::Mutex<Option<Frame<ColorPixel>>>>,
     num_pixels: u16,
+    /// Set while frames don't match the strip's length, so the mismatch is reported once.
+    size_mismatch: Arc<AtomicBool>,
+    format: NeopixelOutputFormat,
+    recorder: Arc<std::sync::Mutex<Option<FrameRecorder>>>,
+    playback_handle: Arc<std::sync::Mutex<Option<JoinHandle<()>>>>,
+    output_sender: broadcast::Sender<Vec<u8>>,
 }
 
 pub struct NeopixelController {
@@ -23,95 +55,438 @@ pub struct NeopixelController {
 impl Drop for NeopixelController {
     fn drop(&mut self) {
         self.listener_handle.abort();
+        self.data.stop_playback();
+        self.data.stop_transition();
     }
 }
 
 impl NeopixelControllerData {
 
-    async fn display(&self, mut pixel_data: Frame<ColorPixel>) {
-        pixel_data.resize_with_empty(self.num_pixels as usize);
-        let bytes = pixel_data.iter()
-            .flat_map(|color| {
-                let color_pre = color.premultiply();
-                [
-                    (color_pre.red * 255.0).round() as u8,
-                    (color_pre.green * 225.0).round() as u8,
-                    (color_pre.blue * 225.0).round() as u8,
-                ]
-            })
-            .collect::<Vec<u8>>();
-        self.pico_connection.send_udp(
-            UdpPacketType::Neopixel_Show,
-            &bytes
-        ).await;
+    async fn display(&self, pixel_data: Frame<ColorPixel>) {
+        let mismatched = pixel_data.len() != self.num_pixels as usize;
+        if self.size_mismatch.swap(mismatched, Ordering::Relaxed) != mismatched && mismatched {
+            eprintln!("Frames have {} pixels but the strip has {}, padding or cutting them short to fit.", pixel_data.len(), self.num_pixels);
+        }
+        let bytes = self.format.encode(&pixel_data, self.num_pixels as usize);
+        if let Some(recorder) = self.recorder.lock().unwrap().as_mut() {
+            if let Err(e) = recorder.record(&bytes) {
+                eprintln!("Failed to record neopixel frame: {}", e);
+            }
+        }
+        self.output(bytes).await;
+        *self.last_frame.lock().unwrap() = Some(pixel_data);
     }
 
-    pub async fn show_pattern_id(&self, pattern_id: RandId) {
-        *self.selected_pattern_id.lock().await = Some(pattern_id);
+    async fn output(&self, bytes: Vec<u8>) {
+        if let Some(pico_connection) = &self.pico_connection {
+            let _ = pico_connection.send_udp(UdpPacketType::Neopixel_Show, &bytes).await;
+        }
+        let _ = self.output_sender.send(bytes);
     }
 
-    pub async fn show_none(&self) {
-        *self.selected_pattern_id.lock().await = None;
-        self.display(Frame::empty(self.num_pixels as usize)).await;
+    /// Stops a recording playing. Returns whether one was.
+    fn stop_playback(&self) -> bool {
+        match self.playback_handle.lock().unwrap().take() {
+            Some(handle) => {
+                let was_playing = !handle.inner().is_finished();
+                handle.abort();
+                was_playing
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the selected pattern aside while something else is shown. The caller holds the lock.
+    fn pause_selected(&self, selected_pattern_id: &mut Option<RandId>) {
+        if let Some(pattern_id) = selected_pattern_id.take() {
+            *self.paused_pattern_id.lock().unwrap() = Some(pattern_id);
+        }
+    }
+
+    fn stop_transition(&self) {
+        if let Some(handle) = self.transition_handle.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+
+    pub async fn transition_to(&self, pattern_id: Option<RandId>, transition: Transition, position_map: &PositionMap<'_>) {
+        self.stop_playback();
+        self.stop_transition();
+        let mut selected_lock = self.selected_pattern_id.lock().await;
+        let previous_pattern_id = std::mem::replace(&mut *selected_lock, pattern_id)
+            .or(self.paused_pattern_id.lock().unwrap().take());
+        let mut transition_lock = self.active_transition.lock().await;
+        if transition.is_instant() || previous_pattern_id == pattern_id {
+            *transition_lock = None;
+            drop(transition_lock);
+            drop(selected_lock);
+            if pattern_id.is_none() {
+                self.display(Frame::empty(self.num_pixels as usize)).await;
+            }
+            return;
+        }
+
+        let from_frame = self.last_frame.lock().unwrap().clone();
+        let active = ActiveTransition::new(transition, previous_pattern_id, pattern_id, from_frame, position_map, self.num_pixels as usize);
+        let end = active.end();
+        *transition_lock = Some(active);
+        drop(transition_lock);
+        drop(selected_lock);
+
+        // Finish the transition on time even if neither pattern sends another frame.
+        let data = self.clone();
+        *self.transition_handle.lock().unwrap() = Some(spawn(async move {
+            tokio::time::sleep_until(end).await;
+            let _selected_lock = data.selected_pattern_id.lock().await;
+            if let Some(transition) = data.active_transition.lock().await.take() {
+                data.display(transition.render(data.num_pixels as usize)).await;
+            }
+        }));
     }
 }
 
 impl NeopixelController {
-    pub async fn new(pico_connection: PicoConnectionHandle, num_pixels: u16, mut pattern_update_receiver: broadcast::Receiver<(RandId, Frame<ColorPixel>)>) -> Result<Self, String> {
+    pub async fn new(pico_connection: PicoConnectionHandle, num_pixels: u16, pattern_update_receiver: broadcast::Receiver<(RandId, Frame<ColorPixel>)>) -> Result<Self, String> {
+        Self::new_with_format(pico_connection, num_pixels, NeopixelOutputFormat::default(), pattern_update_receiver).await
+    }
+
+    pub async fn new_with_format(pico_connection: PicoConnectionHandle, num_pixels: u16, format: NeopixelOutputFormat, pattern_update_receiver: broadcast::Receiver<(RandId, Frame<ColorPixel>)>) -> Result<Self, String> {
+        let controller = Self::create(Some(pico_connection), num_pixels, format, pattern_update_receiver);
+
+        let init_data = num_pixels.to_be_bytes();
+        match controller.data.pico_connection.as_ref().unwrap().send_tcp_await_response(TcpPacketType::Neopixel_Init, &init_data).await {
+            Ok(Ok(())) => {},
+            Ok(Err(msg)) => return Err(msg),
+            Err(e) => return Err(e.to_string()),
+        };
+        Ok(controller)
+    }
+
+    ///
+    /// Creates a controller with no strip attached. It encodes frames exactly as a real strip
+    /// would receive them, which can be observed with [`Self::output_receiver`] or recorded.
+    ///
+    pub fn new_virtual(num_pixels: u16, format: NeopixelOutputFormat, pattern_update_receiver: broadcast::Receiver<(RandId, Frame<ColorPixel>)>) -> Self {
+        Self::create(None, num_pixels, format, pattern_update_receiver)
+    }
+
+    fn create(pico_connection: Option<PicoConnectionHandle>, num_pixels: u16, format: NeopixelOutputFormat, mut pattern_update_receiver: broadcast::Receiver<(RandId, Frame<ColorPixel>)>) -> Self {
         let data = NeopixelControllerData{
             pico_connection,
             selected_pattern_id: Arc::new(Mutex::new(None)),
-            num_pixels
+            paused_pattern_id: Arc::new(std::sync::Mutex::new(None)),
+            active_transition: Arc::new(Mutex::new(None)),
+            transition_handle: Arc::new(std::sync::Mutex::new(None)),
+            last_frame: Arc::new(std::sync::Mutex::new(None)),
+            num_pixels,
+            size_mismatch: Arc::new(AtomicBool::new(false)),
+            format,
+            recorder: Arc::new(std::sync::Mutex::new(None)),
+            playback_handle: Arc::new(std::sync::Mutex::new(None)),
+            output_sender: broadcast::channel(10).0,
         };
-        let controller = Self {
+        Self {
             data: data.clone(),
             listener_handle: spawn(async move {
                 loop {
-                    let (pattern_id, frame) = pattern_update_receiver.recv().await.unwrap();
+                    let (pattern_id, frame) = match pattern_update_receiver.recv().await {
+                        Ok(update) => update,
+                        // Skipped frames are superseded by the next one anyway.
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    };
                     let selected_lock = data.selected_pattern_id.lock().await;
-                    if Some(pattern_id) == *selected_lock {
-                        data.display(frame).await;
+                    let mut transition_lock = data.active_transition.lock().await;
+                    match transition_lock.as_mut() {
+                        Some(transition) if transition.involves(pattern_id) => {
+                            transition.update(pattern_id, frame);
+                            let mixed = transition.render(data.num_pixels as usize);
+                            if transition.is_finished() {
+                                *transition_lock = None;
+                            }
+                            data.display(mixed).await;
+                        },
+                        Some(_) => {},
+                        None => if Some(pattern_id) == *selected_lock {
+                            data.display(frame).await;
+                        },
                     }
+                    drop(transition_lock);
                     drop(selected_lock);
                 }
             }),
-        };
+        }
+    }
 
-        let init_data = num_pixels.to_be_bytes();
-        match controller.data.pico_connection.send_tcp_await_response(TcpPacketType::Neopixel_Init, &init_data).await {
-            Ok(Ok(())) => {},
-            Ok(Err(msg)) => return Err(msg),
-            Err(e) => return Err(e.to_string()),
-        };
-        Ok(controller)
+    pub fn num_pixels(&self) -> u16 {
+        self.data.num_pixels
+    }
+
+    pub fn format(&self) -> NeopixelOutputFormat {
+        self.data.format
+    }
+
+    /// Receives the bytes of every frame sent to the strip.
+    pub fn output_receiver(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.data.output_sender.subscribe()
+    }
+
+    pub async fn show_pattern_id(&self, pattern_id: RandId) {
+        self.transition_to(Some(pattern_id), Transition::default(), &PositionMap::new(vec![])).await;
+    }
+
+    pub async fn show_none(&self) {
+        self.transition_to(None, Transition::default(), &PositionMap::new(vec![])).await;
+    }
+
+    ///
+    /// Shows a single frame until something else is shown, taking the strip away from its pattern
+    /// or recording. The pattern is paused rather than unwatched, and is still reported by
+    /// [`Self::selected_pattern_id`] so that whatever shows the next pattern can release it.
+    ///
+    pub async fn show_frame(&self, frame: Frame<ColorPixel>) {
+        self.data.stop_playback();
+        self.data.stop_transition();
+        let mut selected_lock = self.data.selected_pattern_id.lock().await;
+        self.data.pause_selected(&mut selected_lock);
+        *self.data.active_transition.lock().await = None;
+        self.data.display(frame).await;
+        drop(selected_lock);
     }
+
+    ///
+    /// Switches the strip to another pattern, or turns it off if `pattern_id` is `None`. While the
+    /// transition runs, frames from both patterns are mixed, so both must keep rendering until
+    /// `transition.duration` has passed.
+    ///
+    pub async fn transition_to(&self, pattern_id: Option<RandId>, transition: Transition, position_map: &PositionMap<'_>) {
+        self.data.transition_to(pattern_id, transition, position_map).await;
+    }
+
+    /// The pattern the strip belongs to, including one paused by a recording or single frame.
+    pub async fn selected_pattern_id(&self) -> Option<RandId> {
+        let selected = *self.data.selected_pattern_id.lock().await;
+        selected.or(*self.data.paused_pattern_id.lock().unwrap())
+    }
+
+    pub fn start_recording(&self, recorder: FrameRecorder) -> Result<(), String> {
+        let mut current_recorder = self.data.recorder.lock().unwrap();
+        if current_recorder.is_some() {
+            return Err(format!("Already recording."));
+        }
+        *current_recorder = Some(recorder);
+        Ok(())
+    }
+
+    pub fn stop_recording(&self) -> Result<(), String> {
+        self.data.recorder.lock().unwrap().take()
+            .ok_or("Not recording.")?
+            .finish()
+            .map_err(|e| e.to_string())
+    }
+
+    ///
+    /// Sends a recording's frames to the strip with their original timing. Pattern output is
+    /// paused until playback finishes or is stopped, and playback stops if another pattern is shown.
+    ///
+    pub async fn play_recording(&self, recording: Recording) -> Result<(), String> {
+        if recording.num_pixels != self.data.num_pixels {
+            return Err(format!("Recording is for {} pixels, but the strip has {}.", recording.num_pixels, self.data.num_pixels));
+        }
+        if recording.format != self.data.format {
+            return Err(format!("Recording was made with a different output format."));
+        }
+        self.data.stop_playback();
+        self.data.stop_transition();
+        *self.data.active_transition.lock().await = None;
+        self.data.pause_selected(&mut *self.data.selected_pattern_id.lock().await);
+        let data = self.data.clone();
+        *self.data.playback_handle.lock().unwrap() = Some(spawn(async move {
+            let start = Instant::now();
+            for frame in recording.frames {
+                tokio::time::sleep_until(start + frame.timestamp).await;
+                data.output(frame.bytes).await;
+            }
+            let mut selected_lock = data.selected_pattern_id.lock().await;
+            *selected_lock = data.paused_pattern_id.lock().unwrap().take();
+        }));
+        Ok(())
+    }
+
+    /// Drops the controller, unwatching the pattern it was showing so it stops rendering.
+    pub async fn release(self, pattern_builder: &mut PatternBuilder) {
+        if let Some(pattern_id) = self.selected_pattern_id().await {
+            let _ = pattern_builder.unwatch_pattern(pattern_id);
+        }
+    }
+
+    /// Stops a recording playing, and goes back to the pattern it interrupted.
+    pub async fn stop_playback(&self) {
+        let mut selected_lock = self.data.selected_pattern_id.lock().await;
+        if self.data.stop_playback() && selected_lock.is_none() {
+            *selected_lock = self.data.paused_pattern_id.lock().unwrap().take();
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn forward_output_to_frontend(state: &AppState, controller: &NeopixelController) {
+    let event_sink = state.event_sink.clone();
+    let format = controller.format();
+    let mut output_receiver = controller.output_receiver();
+    spawn(async move {
+        loop {
+            match output_receiver.recv().await {
+                Ok(bytes) => {
+                    event_sink.emit(AppEvent::NeopixelOutput(NeopixelOutputPayload { pixel_data: format.decode(&bytes) }));
+                },
+                Err(RecvError::Lagged(_)) => {},
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+/// Works out how many pixels a new strip has: the given count, or else the layout's count.
+#[cfg(feature = "gui")]
+fn strip_num_pixels(state: &AppState, num_pixels: Option<u16>) -> Result<u16, String> {
+    match num_pixels {
+        Some(num_pixels) => Ok(num_pixels),
+        None => {
+            let layout_pixels = state.pattern_builder.pattern_context().borrow().num_pixels();
+            u16::try_from(layout_pixels).map_err(|_| format!("The layout has {} pixels, more than a strip can have.", layout_pixels))
+        }
+    }
+}
+
+///
+/// Replaces the strip's controller. If the strip's size was given rather than taken from the
+/// layout, patterns are rendered at that size from now on.
+///
+#[cfg(feature = "gui")]
+async fn install_controller(state: &mut AppState, controller: NeopixelController, resize_layout: bool) -> Result<(), String> {
+    if resize_layout {
+        state.pattern_builder.set_num_pixels(controller.num_pixels() as usize)?;
+    }
+    release_controller(state).await;
+    forward_output_to_frontend(state, &controller);
+    state.neopixel_controller = Some(controller);
+    Ok(())
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
-pub async fn init_neopixel(num_pixels: u16, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+pub async fn init_neopixel(num_pixels: Option<u16>, format: Option<NeopixelOutputFormat>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    let connection = state.connection.clone().ok_or("Not connected to a pico.")?;
+    let strip_pixels = strip_num_pixels(&state, num_pixels)?;
 
-    state.neopixel_controller = Some(NeopixelController::new(
-        state.connection.clone().ok_or("Not connected to a pico.")?,
-        num_pixels,
+    let controller = NeopixelController::new_with_format(
+        connection,
+        strip_pixels,
+        format.unwrap_or_default(),
         state.pattern_builder.pattern_update_receiver()
-    ).await?);
-    Ok(())
+    ).await?;
+    install_controller(&mut state, controller, num_pixels.is_some()).await
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
-pub async fn set_neopixel_pattern(pattern_id: Option<RandId>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
-    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+pub async fn init_virtual_neopixel(num_pixels: Option<u16>, format: Option<NeopixelOutputFormat>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    let strip_pixels = strip_num_pixels(&state, num_pixels)?;
 
+    let controller = NeopixelController::new_virtual(
+        strip_pixels,
+        format.unwrap_or_default(),
+        state.pattern_builder.pattern_update_receiver()
+    );
+    install_controller(&mut state, controller, num_pixels.is_some()).await
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_neopixel_pattern(pattern_id: Option<RandId>, transition: Option<Transition>, app_handle: AppHandle, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+
+    // Choosing a pattern by hand takes over from the playlist.
+    state.playlist_player.stop();
+    switch_neopixel_pattern(&mut state, pattern_id, transition.unwrap_or_default(), app_handle).await
+}
+
+///
+/// Shows a pattern on the strip, keeping the old pattern watched until the transition has
+/// finished with it.
+///
+#[cfg(feature = "gui")]
+pub(crate) async fn switch_neopixel_pattern(state: &mut AppState, pattern_id: Option<RandId>, transition: Transition, app_handle: AppHandle) -> Result<(), String> {
+    transition.validate()?;
     let controller = state.neopixel_controller.as_ref().ok_or("Pico not connected!")?;
+    let previous_pattern_id = controller.selected_pattern_id().await;
     if let Some(pattern_id) = pattern_id {
-        if state.pattern_builder.pattern(pattern_id).is_none() {
-            return Err(format!("Pattern with id {} not found", pattern_id));
-        }
-        controller.data.show_pattern_id(pattern_id).await;
+        state.pattern_builder.watch_pattern(pattern_id)
+            .map_err(|_| format!("Pattern with id {} not found", pattern_id))?;
+    }
+    let pattern_context = state.pattern_builder.pattern_context().borrow().clone();
+    controller.transition_to(pattern_id, transition, pattern_context.position_map()).await;
+
+    let Some(previous_pattern_id) = previous_pattern_id else {
+        return Ok(());
+    };
+    if transition.is_instant() {
+        let _ = state.pattern_builder.unwatch_pattern(previous_pattern_id);
     } else {
-        controller.data.show_none().await;
+        // The old pattern keeps rendering until the transition has finished with it.
+        spawn(async move {
+            tokio::time::sleep(transition.duration()).await;
+            let tauri_state = app_handle.state::<LockedAppState>();
+            let _ = tauri_state.0.write().await.pattern_builder.unwatch_pattern(previous_pattern_id);
+        });
     }
     Ok(())
 }
 
+/// Stops the pattern builder rendering for a controller that is about to be replaced or dropped.
+#[cfg(feature = "gui")]
+pub(crate) async fn release_controller(state: &mut AppState) {
+    if let Some(controller) = state.neopixel_controller.take() {
+        controller.release(&mut state.pattern_builder).await;
+    }
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn start_neopixel_recording(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+
+    let controller = state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?;
+    controller.start_recording(FrameRecorder::create(path, controller.num_pixels(), controller.format())?)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn stop_neopixel_recording(tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+
+    state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?
+        .stop_recording()
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn play_neopixel_recording(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+
+    let controller = state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?;
+    controller.play_recording(Recording::load(path)?).await
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn stop_neopixel_playback(tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+
+    state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?
+        .stop_playback().await;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use crate::pattern_builder::component::frame::{ColorPixel, Frame, Pixel};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelOrder {
+    Rgb,
+    Rbg,
+    Grb,
+    Gbr,
+    Brg,
+    Bgr,
+}
+
+impl ChannelOrder {
+    /// For each byte sent to the strip, the index of the RGB component it holds.
+    fn component_indices(&self) -> [usize; 3] {
+        match self {
+            ChannelOrder::Rgb => [0, 1, 2],
+            ChannelOrder::Rbg => [0, 2, 1],
+            ChannelOrder::Grb => [1, 0, 2],
+            ChannelOrder::Gbr => [1, 2, 0],
+            ChannelOrder::Brg => [2, 0, 1],
+            ChannelOrder::Bgr => [2, 1, 0],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelOrder::Rgb => "RGB",
+            ChannelOrder::Rbg => "RBG",
+            ChannelOrder::Grb => "GRB",
+            ChannelOrder::Gbr => "GBR",
+            ChannelOrder::Brg => "BRG",
+            ChannelOrder::Bgr => "BGR",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            ChannelOrder::Rgb,
+            ChannelOrder::Rbg,
+            ChannelOrder::Grb,
+            ChannelOrder::Gbr,
+            ChannelOrder::Brg,
+            ChannelOrder::Bgr,
+        ].into_iter().find(|order| order.name().eq_ignore_ascii_case(name))
+    }
+}
+
+///
+/// How frames are turned into the bytes sent to a neopixel strip.
+///
+/// Pixels are premultiplied by their alpha, raised to the power of `gamma`, scaled to `0..=255`
+/// and written in `channel_order`.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NeopixelOutputFormat {
+    pub channel_order: ChannelOrder,
+    pub gamma: f64,
+}
+
+impl Default for NeopixelOutputFormat {
+    fn default() -> Self {
+        Self {
+            channel_order: ChannelOrder::Rgb,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl NeopixelOutputFormat {
+    pub const BYTES_PER_PIXEL: usize = 3;
+
+    /// Encodes a frame for a strip of `num_pixels`, padding with empty pixels or truncating as needed.
+    pub fn encode(&self, frame: &Frame<ColorPixel>, num_pixels: usize) -> Vec<u8> {
+        let component_indices = self.channel_order.component_indices();
+        frame.iter()
+            .cloned()
+            .chain(std::iter::repeat_with(ColorPixel::empty))
+            .take(num_pixels)
+            .flat_map(|color| {
+                let color_pre = color.premultiply();
+                let components = [color_pre.red, color_pre.green, color_pre.blue];
+                component_indices.map(|i| (components[i].clamp(0.0, 1.0).powf(self.gamma) * 255.0).round() as u8)
+            })
+            .collect()
+    }
+
+    /// Reorders encoded bytes back into `(r, g, b)` triples, exactly as the strip will receive them.
+    pub fn decode(&self, bytes: &[u8]) -> Vec<(u8, u8, u8)> {
+        let component_indices = self.channel_order.component_indices();
+        bytes.chunks_exact(Self::BYTES_PER_PIXEL)
+            .map(|pixel_bytes| {
+                let mut rgb = [0; 3];
+                for (byte, &component) in pixel_bytes.iter().zip(component_indices.iter()) {
+                    rgb[component] = *byte;
+                }
+                (rgb[0], rgb[1], rgb[2])
+            })
+            .collect()
+    }
+}
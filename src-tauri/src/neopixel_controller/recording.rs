@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::neopixel_controller::output_format::{ChannelOrder, NeopixelOutputFormat};
+
+const MAGIC: &[u8; 8] = b"DZFREC01";
+
+///
+/// A frame exactly as it was sent to the strip, and when it was sent relative to the start of
+/// the recording.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub timestamp: Duration,
+    pub bytes: Vec<u8>,
+}
+
+///
+/// A recording of neopixel output.
+///
+/// Stored as a header (`DZFREC01`, pixel count, channel order, gamma) followed by frames of
+/// `[timestamp in microseconds: u64][length: u32][bytes]`. All numbers are big-endian.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub num_pixels: u16,
+    pub format: NeopixelOutputFormat,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        Self::read_from(BufReader::new(file)).map_err(|e| format!("Invalid recording. ({})", e))
+    }
+
+    pub fn read_from(mut reader: impl Read) -> std::io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a neopixel recording."));
+        }
+        let mut num_pixels = [0; 2];
+        reader.read_exact(&mut num_pixels)?;
+        let mut channel_order = [0; 3];
+        reader.read_exact(&mut channel_order)?;
+        let channel_order = ChannelOrder::from_name(&String::from_utf8_lossy(&channel_order))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Unknown channel order."))?;
+        let mut gamma = [0; 8];
+        reader.read_exact(&mut gamma)?;
+
+        let mut frames = vec![];
+        loop {
+            let mut timestamp = [0; 8];
+            match reader.read_exact(&mut timestamp) {
+                Ok(()) => {},
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            let mut bytes = vec![0; u32::from_be_bytes(len) as usize];
+            reader.read_exact(&mut bytes)?;
+            frames.push(RecordedFrame {
+                timestamp: Duration::from_micros(u64::from_be_bytes(timestamp)),
+                bytes,
+            });
+        }
+
+        Ok(Self {
+            num_pixels: u16::from_be_bytes(num_pixels),
+            format: NeopixelOutputFormat {
+                channel_order,
+                gamma: f64::from_be_bytes(gamma),
+            },
+            frames,
+        })
+    }
+}
+
+///
+/// Writes a [`Recording`] as frames arrive, timestamping each relative to when the recorder was
+/// created.
+///
+pub struct FrameRecorder {
+    writer: Box<dyn Write + Send>,
+    started: Instant,
+}
+
+impl FrameRecorder {
+    pub fn create(path: impl AsRef<Path>, num_pixels: u16, format: NeopixelOutputFormat) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        Self::new(BufWriter::new(file), num_pixels, format).map_err(|e| e.to_string())
+    }
+
+    pub fn new(mut writer: impl Write + Send + 'static, num_pixels: u16, format: NeopixelOutputFormat) -> std::io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&num_pixels.to_be_bytes())?;
+        writer.write_all(format.channel_order.name().as_bytes())?;
+        writer.write_all(&format.gamma.to_be_bytes())?;
+        Ok(Self {
+            writer: Box::new(writer),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let timestamp = Instant::now().duration_since(self.started).as_micros() as u64;
+        self.writer.write_all(&timestamp.to_be_bytes())?;
+        self.writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        self.writer.write_all(bytes)
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,200 @@
+use std::iter::repeat_n;
+use std::time::Duration;
+use nalgebra_glm::DVec3;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use crate::pattern_builder::component::frame::{ColorPixel, Frame, Mix, Pixel};
+use crate::pattern_builder::component::RandId;
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+/// The fraction of a wipe's travel over which its edge fades from one pattern to the other.
+const WIPE_EDGE_WIDTH: f64 = 0.1;
+const BLACK: ColorPixel = ColorPixel::new(0.0, 0.0, 0.0, 1.0);
+/// The longest transition allowed, in seconds.
+const MAX_DURATION: f64 = 60.0 * 60.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn component(&self, pos: DVec3) -> f64 {
+        match self {
+            Axis::X => pos.x,
+            Axis::Y => pos.y,
+            Axis::Z => pos.z,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TransitionKind {
+    /// Switches to the new pattern immediately.
+    Cut,
+    /// Fades evenly from the old pattern to the new one.
+    Crossfade,
+    /// Sweeps the new pattern across the layout along one axis of the position map.
+    Wipe { axis: Axis },
+    /// Fades the old pattern out to black, then fades the new one in.
+    FadeThroughBlack,
+}
+
+///
+/// How the strip moves from one pattern to the next. Both patterns keep rendering for `duration`
+/// seconds while their frames are mixed.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: f64,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            kind: TransitionKind::Cut,
+            duration: 0.0,
+        }
+    }
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, duration: f64) -> Result<Self, String> {
+        let transition = Self { kind, duration };
+        transition.validate()?;
+        Ok(transition)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=MAX_DURATION).contains(&self.duration) {
+            return Err(format!("Transition duration must be between 0 and {} seconds, got {}.", MAX_DURATION, self.duration));
+        }
+        Ok(())
+    }
+
+    pub fn is_instant(&self) -> bool {
+        self.kind == TransitionKind::Cut || self.duration == 0.0
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.duration)
+    }
+
+    ///
+    /// Mixes `from` into `to`, where `progress` runs from 0 (only `from`) to 1 (only `to`).
+    ///
+    /// `wipe_coords` holds each pixel's position along the wipe axis, scaled to `0..=1`. Pixels
+    /// without one follow the overall progress.
+    ///
+    pub fn mix(&self, from: Frame<ColorPixel>, to: Frame<ColorPixel>, progress: f64, wipe_coords: &[Option<f64>]) -> Frame<ColorPixel> {
+        let progress = progress.clamp(0.0, 1.0);
+        match self.kind {
+            TransitionKind::Cut => if progress < 1.0 { from } else { to },
+            TransitionKind::Crossfade => from.mix(to, progress),
+            TransitionKind::Wipe { .. } => {
+                let front = progress * (1.0 + WIPE_EDGE_WIDTH);
+                let len = usize::max(from.len(), to.len());
+                let mut to = to.into_iter();
+                let mut from = from.into_iter();
+                (0..len)
+                    .map(|i| {
+                        let amount = match wipe_coords.get(i).copied().flatten() {
+                            Some(coord) => ((front - coord) / WIPE_EDGE_WIDTH).clamp(0.0, 1.0),
+                            None => progress,
+                        };
+                        let from = from.next().unwrap_or_else(ColorPixel::empty);
+                        from.mix(to.next().unwrap_or_else(ColorPixel::empty), amount)
+                    })
+                    .collect()
+            },
+            TransitionKind::FadeThroughBlack => {
+                let black: Frame<ColorPixel> = repeat_n(BLACK, usize::max(from.len(), to.len())).collect();
+                if progress < 0.5 {
+                    from.mix(black, progress * 2.0)
+                } else {
+                    black.mix(to, progress * 2.0 - 1.0)
+                }
+            },
+        }
+    }
+
+    fn wipe_coords(&self, position_map: &PositionMap, num_pixels: usize) -> Vec<Option<f64>> {
+        let TransitionKind::Wipe { axis } = self.kind else {
+            return vec![];
+        };
+        let coords: Vec<Option<f64>> = (0..num_pixels)
+            .map(|i| position_map.pos(i).map(|pos| axis.component(pos)))
+            .collect();
+        let (min, max) = coords.iter().flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), coord| (min.min(*coord), max.max(*coord)));
+        let range = max - min;
+        coords.into_iter()
+            .map(|coord| coord.map(|coord| if range > 0.0 { (coord - min) / range } else { 0.0 }))
+            .collect()
+    }
+}
+
+/// A transition in progress, holding the latest frame from each side.
+pub struct ActiveTransition {
+    transition: Transition,
+    from_id: Option<RandId>,
+    to_id: Option<RandId>,
+    from_frame: Option<Frame<ColorPixel>>,
+    to_frame: Option<Frame<ColorPixel>>,
+    wipe_coords: Vec<Option<f64>>,
+    start: Instant,
+}
+
+impl ActiveTransition {
+    ///
+    /// Starts a transition now. `from_frame` is what the strip currently shows, and stands in
+    /// for the old pattern until it sends a new frame.
+    ///
+    pub fn new(transition: Transition, from_id: Option<RandId>, to_id: Option<RandId>, from_frame: Option<Frame<ColorPixel>>, position_map: &PositionMap, num_pixels: usize) -> Self {
+        Self {
+            wipe_coords: transition.wipe_coords(position_map, num_pixels),
+            transition,
+            from_id,
+            to_id,
+            from_frame,
+            to_frame: None,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn involves(&self, pattern_id: RandId) -> bool {
+        Some(pattern_id) == self.from_id || Some(pattern_id) == self.to_id
+    }
+
+    pub fn update(&mut self, pattern_id: RandId, frame: Frame<ColorPixel>) {
+        if Some(pattern_id) == self.to_id {
+            self.to_frame = Some(frame);
+        } else if Some(pattern_id) == self.from_id {
+            self.from_frame = Some(frame);
+        }
+    }
+
+    pub fn end(&self) -> Instant {
+        self.start + self.transition.duration()
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.transition.duration == 0.0 {
+            1.0
+        } else {
+            (self.start.elapsed().as_secs_f64() / self.transition.duration).min(1.0)
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    pub fn render(&self, num_pixels: usize) -> Frame<ColorPixel> {
+        let side = |frame: &Option<Frame<ColorPixel>>| frame.clone().unwrap_or_else(|| Frame::empty(num_pixels));
+        self.transition.mix(side(&self.from_frame), side(&self.to_frame), self.progress(), &self.wipe_coords)
+    }
+}
@@ -2,13 +2,22 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use futures::StreamExt;
+use nalgebra_glm::DVec3;
+use serde::Deserialize;
+#[cfg(feature = "gui")]
 use serde::Serialize;
-use tauri::{AppHandle, Emitter, Manager};
-use tauri::async_runtime::{JoinHandle, spawn};
-use tokio::sync::{broadcast, RwLockReadGuard, RwLockWriteGuard, watch};
+use crate::runtime::{JoinHandle, spawn};
+#[cfg(feature = "gui")]
+use crate::runtime::spawn_blocking;
+use tokio::sync::{broadcast, watch};
+#[cfg(feature = "gui")]
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
 use tokio_stream::wrappers::WatchStream;
+#[cfg(feature = "gui")]
 use crate::{AppState, LockedAppState};
+use crate::event_sink::{AppEvent, EventSink};
 use component::RandId;
 use crate::pattern_builder::component::frame::{ColorPixel, Frame, ScalarPixel};
 use crate::pattern_builder::component::layer::io_type::DynTypeMapper;
@@ -16,13 +25,24 @@ use crate::pattern_builder::component::layer::Layer;
 use crate::pattern_builder::pattern::Pattern;
 use crate::pattern_builder::pattern_context::PatternContext;
 use crate::pattern_builder::pattern_context::position_map::PositionMap;
+use crate::pattern_builder::pattern_context::position_map::generator::LayoutGenerator;
+use crate::pattern_builder::pattern_context::position_map::import::{self, ImportFormat, Normalization};
+use crate::pattern_builder::pattern_context::zone::{PixelSelection, Zones};
 use crate::tauri_events::PixelUpdatePayload;
+#[cfg(feature = "gui")]
+use crate::pattern_builder::export::{ExportFormat, ExportOptions};
+#[cfg(feature = "gui")]
+use crate::pattern_builder::offline_render::OfflineRenderer;
+#[cfg(feature = "gui")]
+use crate::pattern_builder::pattern::LoopRange;
 
 pub mod library;
 pub mod component;
 pub mod math_functions;
 pub mod pattern_context;
 pub mod pattern;
+pub mod offline_render;
+pub mod export;
 
 mod standard_types {
     use crate::pattern_builder::component::layer::io_type::{DynTypeInfo};
@@ -47,56 +67,99 @@ pub struct PatternBuilder {
     open_patterns: HashMap<RandId, OpenPattern>,
     pattern_ordering: Vec<RandId>,
     pattern_context: watch::Sender<PatternContext<'static>>,
+    /// Whether the layout was loaded or edited, rather than being the default line of pixels.
+    custom_layout: AtomicBool,
     type_mapper: Arc<DynTypeMapper>,
     pattern_update_sender: broadcast::Sender<(RandId, Frame<ColorPixel>)>,
-    app_handle: AppHandle,
+    event_sink: Arc<dyn EventSink>,
 }
 
-impl PatternBuilder {
-    pub fn new(app_handle: AppHandle, num_pixels: usize) -> PatternBuilder {
+/// The type mappings for the types layers pass between each other.
+pub fn standard_type_mapper() -> Arc<DynTypeMapper> {
+    let mut type_mapper = DynTypeMapper::default();
+    type_mapper.add_basic_mappings::<()>();
+    type_mapper.add_basic_mappings::<Frame<ColorPixel>>();
+    type_mapper.add_basic_mappings::<Frame<ScalarPixel>>();
+    type_mapper.add_basic_mappings::<Layer>();
+    Arc::new(type_mapper)
+}
 
-        let mut type_mapper = DynTypeMapper::default();
-        type_mapper.add_basic_mappings::<()>();
-        type_mapper.add_basic_mappings::<Frame<ColorPixel>>();
-        type_mapper.add_basic_mappings::<Frame<ScalarPixel>>();
-        type_mapper.add_basic_mappings::<Layer>();
-        let arc_type_mapper = Arc::new(type_mapper);
+impl PatternBuilder {
+    pub fn new(num_pixels: usize, event_sink: Arc<dyn EventSink>) -> PatternBuilder {
+        let arc_type_mapper = standard_type_mapper();
 
         Self {
             open_patterns: HashMap::new(),
             pattern_ordering: vec![],
             type_mapper: arc_type_mapper.clone(),
             pattern_context: watch::channel(PatternContext::new(num_pixels, arc_type_mapper)).0,
+            custom_layout: AtomicBool::new(false),
             pattern_update_sender: broadcast::channel(100).0,
-            app_handle,
+            event_sink,
         }
     }
 
     pub fn load_pattern(&mut self, pattern: Pattern) {
-        let app_handle = self.app_handle.clone();
-        let mut update_receiver = WatchStream::new(pattern.get_frame_receiver());
-        let id = pattern.id();
-        let update_sender = self.pattern_update_sender.clone();
         let open_pattern = OpenPattern {
-            pixel_updater_handle: spawn(async move {
-                while let Some(pixel_data) = update_receiver.next().await {
-                    let _ = update_sender.send((id, pixel_data.clone()));
-                    app_handle.emit(
-                        "pixel-update",
-                        PixelUpdatePayload { id, pixel_data: pixel_data.into_srgba_components() },
-                    ).unwrap();
-                }
-            }),
             pattern,
+            watchers: 0,
+            pixel_updater_handle: None,
         };
         self.pattern_ordering.push(open_pattern.pattern.id());
         self.open_patterns.insert(open_pattern.pattern.id(), open_pattern);
     }
 
+    ///
+    /// Starts forwarding a pattern's frames to the event sink and the pattern update channel.
+    ///
+    /// Patterns only render while something is watching them, so each call should be paired with
+    /// [`unwatch_pattern`](Self::unwatch_pattern) once the frames are no longer needed.
+    ///
+    pub fn watch_pattern(&mut self, id: RandId) -> Result<(), String> {
+        let event_sink = self.event_sink.clone();
+        let update_sender = self.pattern_update_sender.clone();
+        let open_pattern = self.open_patterns.get_mut(&id).ok_or(format!("Unknown pattern id {}", id))?;
+        open_pattern.watchers += 1;
+        if open_pattern.pixel_updater_handle.is_none() {
+            let mut update_receiver = WatchStream::new(open_pattern.pattern.get_frame_receiver());
+            open_pattern.pixel_updater_handle = Some(spawn(async move {
+                while let Some(pixel_data) = update_receiver.next().await {
+                    let _ = update_sender.send((id, pixel_data.clone()));
+                    event_sink.emit(AppEvent::PixelUpdate(
+                        PixelUpdatePayload { id, pixel_data: pixel_data.into_srgba_components() },
+                    ));
+                }
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn unwatch_pattern(&mut self, id: RandId) -> Result<(), String> {
+        let open_pattern = self.open_patterns.get_mut(&id).ok_or(format!("Unknown pattern id {}", id))?;
+        open_pattern.watchers = open_pattern.watchers.saturating_sub(1);
+        if open_pattern.watchers == 0 {
+            if let Some(handle) = open_pattern.pixel_updater_handle.take() {
+                handle.abort();
+            }
+        }
+        Ok(())
+    }
+
+    /// How many watchers a pattern has, see [`watch_pattern`](Self::watch_pattern).
+    pub fn watcher_count(&self, id: RandId) -> Option<usize> {
+        self.open_patterns.get(&id).map(|open_pattern| open_pattern.watchers)
+    }
+
     pub fn pattern(&self, id: RandId) -> Option<&Pattern> {
         self.open_patterns.get(&id).map(|open_pattern| &open_pattern.pattern)
     }
 
+    /// The open patterns, in the order they were loaded.
+    pub fn patterns(&self) -> impl Iterator<Item=&Pattern> {
+        self.pattern_ordering.iter()
+            .map(|id| &self.open_patterns.get(id).unwrap().pattern)
+    }
+
     pub fn pattern_mut(&mut self, id: RandId) -> Option<&mut Pattern> {
         self.open_patterns.get_mut(&id).map(|open_pattern| &mut open_pattern.pattern)
     }
@@ -109,25 +172,164 @@ impl PatternBuilder {
         self.pattern_context.subscribe()
     }
 
+    /// Loads a layout saved by [`save_position_map`](Self::save_position_map), along with its zones.
     pub fn load_position_map(&self, path: impl AsRef<Path>) -> Result<(), String> {
         let file_contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
-        let position_map: PositionMap<'static> = serde_json::from_str(&*file_contents).map_err(|err| err.to_string())?;
-        self.pattern_context.send_modify(|ctx| ctx.set_position_map(position_map));
+        let layout_file: LayoutFile = serde_json::from_str(&*file_contents).map_err(|err| err.to_string())?;
+        match layout_file {
+            LayoutFile::Positions(position_map) => self.set_position_map(position_map),
+            LayoutFile::WithZones { positions, zones } => {
+                for (name, selection) in &zones {
+                    selection.validate(positions.len()).map_err(|e| format!("Zone {}: {}", name, e))?;
+                }
+                self.set_position_map(positions);
+                self.pattern_context.send_modify(|ctx| ctx.set_zones(zones));
+            }
+        }
         Ok(())
     }
+
+    /// Loads a layout exported by another tool. See [`import::import_position_map`].
+    pub fn import_position_map(&self, path: impl AsRef<Path>, format: Option<ImportFormat>, normalization: &Normalization) -> Result<(), String> {
+        self.set_position_map(import::import_position_map(path, format, normalization)?);
+        Ok(())
+    }
+
+    /// Saves the layout as a list of positions, or with its zones alongside if it has any.
+    pub fn save_position_map(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let ctx = self.pattern_context.borrow();
+        let contents = if ctx.zones().is_empty() {
+            serde_json::to_string(ctx.position_map())
+        } else {
+            serde_json::to_string(&serde_json::json!({ "positions": ctx.position_map(), "zones": ctx.zones() }))
+        }.map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    /// Replaces the layout, so patterns render one pixel per position in it and the old layout's zones are cleared.
+    pub fn set_position_map(&self, position_map: PositionMap<'static>) {
+        self.custom_layout.store(true, Ordering::Relaxed);
+        self.pattern_context.send_modify(|ctx| {
+            ctx.set_num_pixels(position_map.len());
+            ctx.set_position_map(position_map);
+            ctx.set_zones(Zones::new());
+        });
+    }
+
+    ///
+    /// Changes how many pixels patterns render, e.g. to match the strip they're shown on. The
+    /// default layout is a line of pixels that grows or shrinks to fit, but a loaded layout can't
+    /// be changed this way, so the count has to match it. Zones that no longer fit are removed.
+    ///
+    pub fn set_num_pixels(&self, num_pixels: usize) -> Result<(), String> {
+        let custom_layout = self.custom_layout.load(Ordering::Relaxed);
+        let layout_pixels = self.pattern_context.borrow().position_map().len();
+        if custom_layout && layout_pixels != num_pixels {
+            return Err(format!("The layout has {} pixels, not {}. Load a layout for {} pixels first.", layout_pixels, num_pixels, num_pixels));
+        }
+        self.pattern_context.send_if_modified(|ctx| {
+            if ctx.num_pixels() == num_pixels && layout_pixels == num_pixels {
+                return false;
+            }
+            if !custom_layout {
+                ctx.set_position_map(PositionMap::new_linear(num_pixels));
+            }
+            ctx.set_num_pixels(num_pixels);
+            if ctx.zones().values().any(|selection| selection.validate(num_pixels).is_err()) {
+                let zones = ctx.zones().iter()
+                    .filter(|(_, selection)| selection.validate(num_pixels).is_ok())
+                    .map(|(name, selection)| (name.clone(), selection.clone()))
+                    .collect();
+                ctx.set_zones(zones);
+            }
+            true
+        });
+        Ok(())
+    }
+
+    pub fn generate_position_map(&self, generator: &LayoutGenerator) -> Result<(), String> {
+        self.set_position_map(generator.generate()?);
+        Ok(())
+    }
+
+    /// Moves a single pixel, or clears its position with `None`.
+    pub fn set_pixel_position(&self, pixel_index: usize, position: Option<DVec3>) -> Result<(), String> {
+        let num_pixels = self.pattern_context.borrow().num_pixels();
+        if pixel_index >= num_pixels {
+            return Err(format!("Pixel {} is out of range, there are {} pixels.", pixel_index, num_pixels));
+        }
+        self.custom_layout.store(true, Ordering::Relaxed);
+        self.pattern_context.send_modify(|ctx| {
+            let mut position_map = PositionMap::new(ctx.position_map().iter().cloned().collect());
+            position_map.set_pos(pixel_index, position);
+            ctx.set_position_map(position_map);
+        });
+        Ok(())
+    }
+
+    /// Adds a zone, or replaces the zone with the same name.
+    pub fn set_zone(&self, name: &str, selection: PixelSelection) -> Result<(), String> {
+        if name.trim().is_empty() {
+            return Err("Zones need a name.".to_string());
+        }
+        selection.validate(self.pattern_context.borrow().num_pixels())?;
+        self.pattern_context.send_modify(|ctx| {
+            let mut zones = ctx.zones().clone();
+            zones.insert(name.to_string(), selection);
+            ctx.set_zones(zones);
+        });
+        Ok(())
+    }
+
+    pub fn remove_zone(&self, name: &str) -> Result<(), String> {
+        if !self.pattern_context.borrow().zones().contains_key(name) {
+            return Err(format!("There is no zone named {}.", name));
+        }
+        self.pattern_context.send_modify(|ctx| {
+            let mut zones = ctx.zones().clone();
+            zones.remove(name);
+            ctx.set_zones(zones);
+        });
+        Ok(())
+    }
+
+    pub fn zones(&self) -> Zones {
+        self.pattern_context.borrow().zones().clone()
+    }
+
+    #[cfg(feature = "gui")]
+    fn position_map_json(&self) -> String {
+        serde_json::to_string(self.pattern_context.borrow().position_map())
+            .expect("Failed converting Position Map to string.")
+    }
+}
+
+/// Layouts are saved as a bare list of positions unless they have zones.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LayoutFile {
+    Positions(PositionMap<'static>),
+    WithZones {
+        positions: PositionMap<'static>,
+        zones: Zones,
+    },
 }
 
 struct OpenPattern {
     pattern: Pattern,
-    pixel_updater_handle: JoinHandle<()>,
+    watchers: usize,
+    pixel_updater_handle: Option<JoinHandle<()>>,
 }
 
 impl Drop for OpenPattern {
     fn drop(&mut self) {
-        self.pixel_updater_handle.abort();
+        if let Some(handle) = self.pixel_updater_handle.take() {
+            handle.abort();
+        }
     }
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn view_open_patterns(tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
     let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
@@ -142,6 +344,7 @@ pub async fn view_open_patterns(tauri_state: tauri::State<'_, LockedAppState>) -
     ).map_err(|e| e.to_string())
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn view_pattern(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
@@ -151,6 +354,96 @@ pub async fn view_pattern(id: RandId, tauri_state: tauri::State<'_, LockedAppSta
     serde_json::to_string(&view).map_err(|e| e.to_string())
 }
 
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn reseed_pattern(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    let pattern = state.pattern_builder
+        .pattern_mut(id).ok_or(format!("Unknown pattern id {}", id))?;
+    pattern.reseed();
+    serde_json::to_string(&pattern.view()).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn pattern_playback(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    let pattern = state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?;
+    serde_json::to_string(&pattern.playback_view()).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn pattern_stats(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    let pattern = state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?;
+    serde_json::to_string(&pattern.stats()).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn watch_pattern(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    state.pattern_builder.watch_pattern(id)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn unwatch_pattern(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    state.pattern_builder.unwatch_pattern(id)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_pattern_fps(id: RandId, fps: f64, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .set_fps(fps)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_pattern_running(id: RandId, running: bool, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .set_running(running);
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn seek_pattern(id: RandId, t: f64, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .seek(t)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn step_pattern(id: RandId, frames: i64, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .step(frames)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn reset_pattern_time(id: RandId, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .reset_time()
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_pattern_loop(id: RandId, loop_range: Option<LoopRange>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.pattern(id).ok_or(format!("Unknown pattern id {}", id))?
+        .set_loop_range(loop_range)
+}
+
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn update_property(pattern_id: RandId, prop_id: RandId, value: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
@@ -160,6 +453,7 @@ pub async fn update_property(pattern_id: RandId, prop_id: RandId, value: String,
         .try_update_prop(prop_id, value)
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn position_map(tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
@@ -170,13 +464,86 @@ pub async fn position_map(tauri_state: tauri::State<'_, LockedAppState>) -> Resu
     )
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn load_position_map(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
     state.pattern_builder.load_position_map(path)?;
+    state.check_pixel_count();
     let pattern_context = state.pattern_builder.pattern_context.borrow();
     Ok(
         serde_json::to_string(&pattern_context.position_map())
             .expect("Failed converting Position Map to string.")
     )
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn import_position_map(path: String, format: Option<ImportFormat>, normalization: Option<Normalization>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.import_position_map(path, format, &normalization.unwrap_or_default())?;
+    state.check_pixel_count();
+    Ok(state.pattern_builder.position_map_json())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn save_position_map(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.save_position_map(path)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn generate_position_map(generator: LayoutGenerator, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.generate_position_map(&generator)?;
+    state.check_pixel_count();
+    Ok(state.pattern_builder.position_map_json())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_pixel_position(pixel_index: usize, position: Option<[f64; 3]>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.set_pixel_position(pixel_index, position.map(DVec3::from))?;
+    Ok(state.pattern_builder.position_map_json())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn view_zones(tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    serde_json::to_string(&state.pattern_builder.zones()).map_err(|err| err.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_zone(name: String, selection: PixelSelection, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.set_zone(&name, selection)?;
+    serde_json::to_string(&state.pattern_builder.zones()).map_err(|err| err.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn remove_zone(name: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.pattern_builder.remove_zone(&name)?;
+    serde_json::to_string(&state.pattern_builder.zones()).map_err(|err| err.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn export_pattern(pattern_id: RandId, path: String, format: Option<ExportFormat>, options: Option<ExportOptions>, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    let format = format.or_else(|| ExportFormat::from_path(&path))
+        .ok_or(format!("Unknown export format for {}", path))?;
+    let mut renderer = OfflineRenderer::new(
+        state.pattern_builder.pattern(pattern_id).ok_or(format!("Unknown pattern id {}", pattern_id))?
+    );
+    drop(state);
+    spawn_blocking(move || export::export_to_file(&mut renderer, &options.unwrap_or_default(), format, path))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -1,9 +1,13 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::f64::consts::E;
+use std::fmt::{Debug, Formatter};
 use palette::{LinSrgba, Srgb, Srgba, WithAlpha};
 use palette::blend::Compose;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::vec::IntoIter;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Serialize, Deserialize)]
@@ -25,10 +29,84 @@ pub trait Opacity {
     }
 }
 
+///
+/// Unclamped arithmetic on pixels, for filters that weigh neighbouring pixels against each other.
+///
+pub trait Linear {
+    /// `self + other * weight`. Colour channels aren't clamped, so weights may be negative, but alpha stays within 0 and 1.
+    fn add_weighted(self, other: Self, weight: f64) -> Self;
+    /// How far apart two pixels are, from 0 for identical pixels upward.
+    fn difference(&self, other: &Self) -> f64;
+}
+
 pub trait Pixel: Blend + Mix + Opacity + Clone + Send + Sync + 'static {
     fn empty() -> Self;
 }
 
+/// The most spare buffers kept of each type.
+const MAX_POOLED_BUFFERS: usize = 16;
+
+thread_local! {
+    /// Spare frame buffers and layer output boxes, keyed by their type. Only set inside [`with_buffer_pool`].
+    static BUFFER_POOL: RefCell<Option<HashMap<TypeId, Box<dyn Any>>>> = RefCell::new(None);
+}
+
+///
+/// Runs `f` with frame buffers recycled on the current thread: frames dropped inside `f` leave
+/// their buffers for new frames to reuse, and the boxes layers pass their output in are reused the
+/// same way. The spare buffers are freed when `f` returns, so nothing is held between renders, and
+/// frames dropped on other threads are freed as usual.
+///
+pub fn with_buffer_pool<R>(f: impl FnOnce() -> R) -> R {
+    struct PoolScope;
+
+    impl Drop for PoolScope {
+        fn drop(&mut self) {
+            let pool = BUFFER_POOL.with(|pool| pool.borrow_mut().take());
+            drop(pool);
+        }
+    }
+
+    let outermost = BUFFER_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.is_some() {
+            return false;
+        }
+        *pool = Some(HashMap::new());
+        true
+    });
+    let _scope = outermost.then_some(PoolScope);
+    f()
+}
+
+/// Takes a pooled buffer of type `B` that `fits`, if there is one.
+pub(crate) fn take_pooled_buffer<B: 'static>(fits: impl Fn(&B) -> bool) -> Option<B> {
+    BUFFER_POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let buffers = pool.as_mut()?.get_mut(&TypeId::of::<B>())?.downcast_mut::<Vec<B>>()?;
+        let index = buffers.iter().position(fits)?;
+        Some(buffers.swap_remove(index))
+    }).ok().flatten()
+}
+
+/// Keeps an emptied buffer for reuse, or frees it if no pool is active.
+pub(crate) fn return_pooled_buffer<B: 'static>(buffer: B) {
+    // The pool may already be gone if the thread is shutting down.
+    let _ = BUFFER_POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let Some(pool) = pool.as_mut() else {
+            return;
+        };
+        let buffers = pool.entry(TypeId::of::<B>())
+            .or_insert_with(|| Box::new(Vec::<B>::new()))
+            .downcast_mut::<Vec<B>>()
+            .unwrap();
+        if buffers.len() < MAX_POOLED_BUFFERS {
+            buffers.push(buffer);
+        }
+    });
+}
+
 pub type ColorPixel = LinSrgba<f64>;
 const EMPTY_COLOR: ColorPixel = ColorPixel::new(0.0, 0.0, 0.0, 0.0);
 
@@ -53,6 +131,23 @@ impl Mix for ColorPixel {
     }
 }
 
+impl Linear for ColorPixel {
+    fn add_weighted(self, other: Self, weight: f64) -> Self {
+        ColorPixel::new(
+            self.red + other.red * weight,
+            self.green + other.green * weight,
+            self.blue + other.blue * weight,
+            (self.alpha + other.alpha * weight).clamp(0.0, 1.0),
+        )
+    }
+
+    fn difference(&self, other: &Self) -> f64 {
+        [self.red - other.red, self.green - other.green, self.blue - other.blue, self.alpha - other.alpha]
+            .into_iter()
+            .fold(0.0, |max, channel| f64::max(max, channel.abs()))
+    }
+}
+
 impl Pixel for ColorPixel {
     fn empty() -> Self {
         EMPTY_COLOR
@@ -86,30 +181,91 @@ impl Mix for ScalarPixel {
     }
 }
 
+impl Linear for ScalarPixel {
+    fn add_weighted(self, other: Self, weight: f64) -> Self {
+        self + other * weight
+    }
+
+    fn difference(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
 impl Pixel for ScalarPixel {
     fn empty() -> f64 {
         0.0
     }
 }
 
-#[derive(Clone)]
+///
+/// One value per pixel.
+///
+/// Frames are created and dropped every tick, so their buffers are recycled while rendering: inside
+/// [`with_buffer_pool`], a dropped frame's buffer goes back to a pool, and new frames take their
+/// buffer from it when one is big enough.
+///
 pub struct Frame<P: Pixel>(Vec<P>);
 
 impl<P: Pixel> Frame<P> {
     pub fn empty(num_pixels: usize) -> Self {
-        Self(vec![P::empty();num_pixels])
+        let mut frame = Self::with_capacity(num_pixels);
+        frame.0.resize(num_pixels, P::empty());
+        frame
+    }
+
+    /// An empty frame with room for `capacity` pixels, reusing a pooled buffer if one is free.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(take_pooled_buffer(|buffer: &Vec<P>| buffer.capacity() >= capacity).unwrap_or_else(|| Vec::with_capacity(capacity)))
     }
 
     pub fn resize_with_empty(&mut self, new_len: usize) {
         self.resize_with(new_len, P::empty);
     }
 
-    pub fn blend_using<F>(self, active: Self, f: F) -> Self where F: Fn(P, P) -> P {
-        self.into_iter()
-            .zip_longest(active.into_iter())
-            .map(|pixels| pixels.or_else(P::empty, P::empty))
-            .map(|(pixel, active)| f(pixel, active))
-            .collect()
+    /// Combines each pixel with the matching pixel of `active`, reusing this frame's buffer.
+    pub fn blend_using<F>(mut self, active: Self, f: F) -> Self where F: Fn(P, P) -> P {
+        if active.len() > self.len() {
+            self.resize_with_empty(active.len());
+        }
+        let mut active = active.0.iter();
+        for pixel in self.0.iter_mut() {
+            let active = active.next().cloned().unwrap_or_else(P::empty);
+            *pixel = f(mem::replace(pixel, P::empty()), active);
+        }
+        self
+    }
+
+    /// Applies `f` to every pixel in place.
+    pub fn map_in_place<F>(mut self, f: F) -> Self where F: Fn(P) -> P {
+        for pixel in self.0.iter_mut() {
+            *pixel = f(mem::replace(pixel, P::empty()));
+        }
+        self
+    }
+}
+
+impl<P: Pixel> Drop for Frame<P> {
+    fn drop(&mut self) {
+        if self.0.capacity() == 0 {
+            return;
+        }
+        let mut buffer = mem::take(&mut self.0);
+        buffer.clear();
+        return_pooled_buffer(buffer);
+    }
+}
+
+impl<P: Pixel> Clone for Frame<P> {
+    fn clone(&self) -> Self {
+        let mut frame = Self::with_capacity(self.len());
+        frame.0.extend_from_slice(&self.0);
+        frame
+    }
+}
+
+impl<P: Pixel + Debug> Debug for Frame<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Frame").field(&self.0).finish()
     }
 }
 
@@ -134,9 +290,7 @@ impl<P: Pixel> Blend for Frame<P> {
 
 impl<P> Opacity for Frame<P> where P: Pixel {
     fn scale_opacity(self, amount: f64) -> Self {
-        self.into_iter()
-            .map(|p| p.scale_opacity(amount))
-            .collect()
+        self.map_in_place(|p| p.scale_opacity(amount))
     }
 }
 
@@ -167,8 +321,8 @@ impl<P: Pixel> From<Vec<P>> for Frame<P> {
 }
 
 impl<P: Pixel> From<Frame<P>> for Vec<P> {
-    fn from(value: Frame<P>) -> Self {
-        value.0
+    fn from(mut value: Frame<P>) -> Self {
+        mem::take(&mut value.0)
     }
 }
 
@@ -176,13 +330,16 @@ impl<P: Pixel> IntoIterator for Frame<P> {
     type Item = P;
     type IntoIter = IntoIter<P>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut self.0).into_iter()
     }
 }
 
 impl<P: Pixel> FromIterator<P> for Frame<P> {
     fn from_iter<T: IntoIterator<Item=P>>(iter: T) -> Self {
-        Self(iter.into_iter().collect())
+        let iter = iter.into_iter();
+        let mut frame = Self::with_capacity(iter.size_hint().0);
+        frame.0.extend(iter);
+        frame
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Instant;
 use dyn_clone::{clone_trait_object, DynClone};
 use rand::random;
 use serde::{Serialize, Serializer};
@@ -7,6 +8,7 @@ use crate::pattern_builder::component::frame::{Blend, Opacity};
 use crate::pattern_builder::component::RandId;
 use crate::pattern_builder::component::layer::io_type::{DynType, DynTypeDef, NoMappingError, DynTypeMapper, DynValue};
 use crate::pattern_builder::component::layer::layer_stack::StackTypeError;
+use crate::pattern_builder::component::layer::stats::{LayerStats, LayerStatsView};
 use crate::pattern_builder::component::layer::texture::BlendingLayerCore;
 use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
 use crate::pattern_builder::component::property::string::OptionStringPropCore;
@@ -15,6 +17,7 @@ use crate::pattern_builder::pattern_context::PatternContext;
 pub mod texture;
 pub mod layer_stack;
 pub mod io_type;
+pub mod stats;
 
 pub trait LayerCore: Send + Sync + DynClone + 'static {
     type Input: DynType;
@@ -23,6 +26,9 @@ pub trait LayerCore: Send + Sync + DynClone + 'static {
     fn next(&mut self, input: Self::Input, t: f64, ctx: &PatternContext) -> Self::Output;
     fn view_properties(&self) -> Vec<PropView>;
     fn detach(&mut self);
+
+    /// Clears any state carried between frames, so the next frame renders as if it were the first.
+    fn reset(&mut self) {}
 }
 clone_trait_object!(<I, O> LayerCore<Input=I, Output=O>);
 
@@ -40,6 +46,10 @@ impl<T> LayerCore for Box<T> where T: LayerCore + Clone + ?Sized {
     fn detach(&mut self) {
         self.as_mut().detach()
     }
+
+    fn reset(&mut self) {
+        self.as_mut().reset()
+    }
 }
 
 trait DynLayerCore: Send + Sync + DynClone + 'static {
@@ -47,6 +57,7 @@ trait DynLayerCore: Send + Sync + DynClone + 'static {
     fn eval_type(&self, input_type: Option<DynTypeDef>, type_errors: &mut Vec<StackTypeError>, type_mapper: &DynTypeMapper) -> Result<Option<DynTypeDef>, NoMappingError>;
     fn view_properties(&self) -> Vec<PropView>;
     fn detach(&mut self);
+    fn reset(&mut self);
 }
 
 clone_trait_object!(DynLayerCore);
@@ -72,6 +83,10 @@ impl<L> DynLayerCore for L where L: LayerCore {
     fn detach(&mut self) {
         L::detach(self);
     }
+
+    fn reset(&mut self) {
+        L::reset(self);
+    }
 }
 
 #[derive(Clone)]
@@ -79,6 +94,7 @@ pub struct Layer {
     info: LayerInfo,
     type_info: LayerTypeInfo,
     core: Box<dyn DynLayerCore>,
+    stats: LayerStats,
 }
 
 impl Layer {
@@ -87,6 +103,7 @@ impl Layer {
             info: LayerInfo::new(),
             type_info,
             core: Box::new(core),
+            stats: LayerStats::default(),
         }
     }
 
@@ -98,6 +115,7 @@ impl Layer {
             info: LayerInfo::new(),
             type_info,
             core: Box::new(BlendingLayerCore::new(core)),
+            stats: LayerStats::default(),
         }
     }
 
@@ -109,6 +127,7 @@ impl Layer {
             info: LayerInfo::new(),
             type_info,
             core: Box::new(core),
+            stats: LayerStats::default(),
         }
     }
 
@@ -141,8 +160,14 @@ impl Layer {
     }
 
     pub fn try_next(&mut self, input: DynValue, t: f64, ctx: &PatternContext) -> Result<DynValue, StackTypeError> {
-        self.core.try_next(input, t, ctx)
-            .map_err(|err| StackTypeError::LayerInput(self.info().clone(), err))
+        let start = Instant::now();
+        let result = self.core.try_next(input, t, ctx);
+        self.stats.record(start.elapsed());
+        result.map_err(|err| StackTypeError::LayerInput(self.info().clone(), err))
+    }
+
+    pub fn stats(&self) -> &LayerStats {
+        &self.stats
     }
 
     pub fn view(&self) -> LayerView {
@@ -151,12 +176,18 @@ impl Layer {
             info: self.info().clone(),
             property_views: self.core.view_properties(),
             data: HashMap::new(),
+            stats: self.stats.view(),
         }
     }
 
     pub fn detach(&mut self) {
         self.info.detach();
         self.core.detach();
+        self.stats.clear();
+    }
+
+    pub fn reset(&mut self) {
+        self.core.reset();
     }
 }
 
@@ -173,6 +204,7 @@ pub struct LayerView {
     info: LayerInfo,
     property_views: Vec<PropView>,
     data: HashMap<String, Box<dyn erased_serde::Serialize + 'static>>,
+    stats: LayerStatsView,
 }
 
 impl LayerView {
@@ -188,17 +220,26 @@ impl LayerView {
     pub fn property_views(&self) -> &Vec<PropView> {
         &self.property_views
     }
+
+    pub fn type_info(&self) -> &LayerTypeInfo {
+        &self.type_info
+    }
+
+    pub fn stats(&self) -> &LayerStatsView {
+        &self.stats
+    }
 }
 
 impl Serialize for LayerView {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-        let mut struct_ser = serializer.serialize_struct("Layer", 6)?;
+        let mut struct_ser = serializer.serialize_struct("Layer", 7)?;
         struct_ser.serialize_field("id", &self.info.id())?;
         struct_ser.serialize_field("type", &self.type_info)?;
         struct_ser.serialize_field("name", &self.info.name().view())?;
         struct_ser.serialize_field("description", &self.info.description().view())?;
         struct_ser.serialize_field("data", &self.data)?;
         struct_ser.serialize_field("properties", &self.property_views)?;
+        struct_ser.serialize_field("stats", &self.stats)?;
         struct_ser.end()
     }
 }
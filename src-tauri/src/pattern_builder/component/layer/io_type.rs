@@ -5,6 +5,7 @@ use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::iter::repeat;
 use itertools::Itertools;
+use crate::pattern_builder::component::frame::{return_pooled_buffer, take_pooled_buffer};
 
 #[derive(Copy, Clone, Debug, Default)]
 struct OptionLevel(u16);
@@ -22,12 +23,32 @@ pub trait DynType: Sized + Send + Sync + 'static {
     }
     fn into_dyn_value(self) -> DynValue {
         DynValue {
-            value: Box::new(self),
+            value: box_value(self),
             type_def: Self::dyn_type_def(),
         }
     }
 }
 
+///
+/// Boxes `value` for passing between layers. Inside [`with_buffer_pool`], the box is one a previous
+/// value of the same type was taken out of, so layers don't allocate a box for every frame.
+///
+/// [`with_buffer_pool`]: crate::pattern_builder::component::frame::with_buffer_pool
+///
+fn box_value<T: 'static>(value: T) -> Box<dyn Any> {
+    let mut slot = take_pooled_buffer(|_: &Box<Option<T>>| true).unwrap_or_else(|| Box::new(None));
+    *slot = Some(value);
+    slot
+}
+
+/// Takes the value out of a box made by [`box_value`], leaving the box for reuse.
+fn unbox_value<T: 'static>(value: Box<dyn Any>) -> T {
+    let mut slot = value.downcast::<Option<T>>().unwrap();
+    let value = slot.take().unwrap();
+    return_pooled_buffer(slot);
+    value
+}
+
 impl<T> DynType for Option<T> where T: DynType
 {
     fn dyn_type_info() -> &'static DynTypeInfo {
@@ -121,11 +142,11 @@ impl DynTypeMapper {
     pub fn set_mapping<I: DynType, O: DynType>(&mut self, mapper: fn(I) -> O) {
         self.mappers.insert(
             (I::dyn_type_def(), O::dyn_type_def()),
-            Box::new(move |value: Box<dyn Any>| Box::new(mapper(*value.downcast().unwrap())))
+            Box::new(move |value: Box<dyn Any>| box_value(mapper(unbox_value(value))))
         );
         self.mappers.insert(
             (I::dyn_type_def(), O::dyn_type_def().option()),
-            Box::new(move |value: Box<dyn Any>| Box::new(Some(mapper(*value.downcast().unwrap()))))
+            Box::new(move |value: Box<dyn Any>| box_value(Some(mapper(unbox_value(value)))))
         );
     }
 
@@ -160,7 +181,12 @@ impl DynValue {
 
     pub fn try_into<T: DynType>(self, type_mapper: &DynTypeMapper) -> Result<T, MappingFailedError> {
         if let Some(mapper) = type_mapper.mappers.get(&(self.dyn_type_def(), T::dyn_type_def())) {
-            Ok(*mapper(self.value).downcast().unwrap())
+            if self.type_def == T::dyn_type_def() {
+                // Identity mappings would only unbox and rebox the value.
+                Ok(unbox_value(self.value))
+            } else {
+                Ok(unbox_value(mapper(self.value)))
+            }
         } else {
             Err(MappingFailedError { type_err: NoMappingError::new(self.dyn_type_def(), T::dyn_type_def()), original_value: self })
         }
@@ -168,7 +194,7 @@ impl DynValue {
 
     pub fn downcast<T: DynType>(self) -> Result<T, DowncastError> {
         if T::dyn_type_def() == self.type_def {
-            Ok(*self.value.downcast().unwrap())
+            Ok(unbox_value(self.value))
         } else {
             Err(DowncastError { target_type: T::dyn_type_def(), value: self })
         }
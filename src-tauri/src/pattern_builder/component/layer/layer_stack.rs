@@ -1,21 +1,25 @@
 use std::fmt::{Debug, Formatter};
+use std::time::Instant;
 use itertools::{FoldWhile, Itertools};
 use serde::{Serialize, Serializer};
 use serde::ser::SerializeStruct;
 use crate::pattern_builder::component::RandId;
 use crate::pattern_builder::component::layer::io_type::{DynTypeDef, NoMappingError, DynTypeMapper, DynValue, DynType};
 use crate::pattern_builder::component::layer::{Layer, LayerInfo, LayerView};
+use crate::pattern_builder::component::layer::stats::LayerStats;
 use crate::pattern_builder::pattern_context::PatternContext;
 
 #[derive(Clone)]
 pub struct LayerStack {
     stack: Vec<Layer>,
+    stats: LayerStats,
 }
 
 impl LayerStack {
     pub fn new() -> Self {
         Self {
             stack: Vec::new(),
+            stats: LayerStats::default(),
         }
     }
 
@@ -50,14 +54,22 @@ impl LayerStack {
     }
 
     pub fn next_dyn(&mut self, input: DynValue, t: f64, ctx: &PatternContext) -> Result<DynValue, StackTypeError> {
-        self.stack.iter_mut()
+        let start = Instant::now();
+        let result = self.stack.iter_mut()
             .fold_while(Ok(input), |value, layer| {
                 match layer.try_next(value.unwrap(), t, &ctx) {
                     Ok(value) => FoldWhile::Continue(Ok(value)),
                     Err(err) => FoldWhile::Done(Err(err)),
                 }
             })
-            .into_inner()
+            .into_inner();
+        self.stats.record(start.elapsed());
+        result
+    }
+
+    /// Evaluation times for the whole stack. Each layer keeps its own times as well.
+    pub fn stats(&self) -> &LayerStats {
+        &self.stats
     }
 
     pub fn next<I: DynType, O: DynType>(&mut self, input: I, t: f64, ctx: &PatternContext) -> Result<O, StackTypeError> {
@@ -66,10 +78,17 @@ impl LayerStack {
     }
 
     pub fn detach(&mut self) {
+        self.stats.clear();
         for layer in self.stack.iter_mut() {
             layer.detach();
         }
     }
+
+    pub fn reset(&mut self) {
+        for layer in self.stack.iter_mut() {
+            layer.reset();
+        }
+    }
 }
 
 pub enum StackTypeError {
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use serde::Serialize;
+
+/// The number of recent frames the averages and maximums are taken over.
+const WINDOW: usize = 60;
+
+///
+/// Evaluation times for a layer over its most recent frames.
+///
+/// Times include any layers nested inside the layer, so a group's time covers its whole stack.
+///
+#[derive(Clone, Default)]
+pub struct LayerStats {
+    recent: VecDeque<Duration>,
+    total_frames: u64,
+}
+
+impl LayerStats {
+    pub fn record(&mut self, duration: Duration) {
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(duration);
+        self.total_frames += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.total_frames = 0;
+    }
+
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.recent.back().copied()
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        if self.recent.is_empty() {
+            None
+        } else {
+            Some(self.recent.iter().sum::<Duration>() / self.recent.len() as u32)
+        }
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.recent.iter().max().copied()
+    }
+
+    pub fn view(&self) -> LayerStatsView {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        LayerStatsView {
+            total_frames: self.total_frames,
+            last_ms: self.last().map(millis),
+            average_ms: self.average().map(millis),
+            max_ms: self.max().map(millis),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LayerStatsView {
+    pub total_frames: u64,
+    pub last_ms: Option<f64>,
+    pub average_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
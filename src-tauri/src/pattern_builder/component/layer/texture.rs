@@ -71,4 +71,8 @@ impl<T> LayerCore for BlendingLayerCore<T> where T: DynType + Blend + Opacity {
             self.blend_mode,
         );
     }
+
+    fn reset(&mut self) {
+        self.texture.reset();
+    }
 }
\ No newline at end of file
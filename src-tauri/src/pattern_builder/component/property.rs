@@ -6,6 +6,7 @@ pub mod color;
 pub mod string;
 pub mod num_vec;
 pub mod layer_stack;
+pub mod seed;
 
 use std::collections::HashMap;
 use std::mem;
@@ -285,6 +286,10 @@ impl PropView {
         &self.0.info()
     }
 
+    pub fn prop_type_id(&self) -> String {
+        self.0.read_core().prop_type_id()
+    }
+
     pub fn child_layer_views(&self) -> Vec<LayerView> {
         self.0.read_core().child_layer_views()
     }
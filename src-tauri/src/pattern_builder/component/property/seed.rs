@@ -0,0 +1,58 @@
+use rand::random;
+use crate::pattern_builder::component::property::{PropCore, ErasedPropCore, PropRead, PropWrite};
+
+///
+/// A layer's random seed. Updating it with `"random"` picks a new seed.
+///
+/// Seeds are kept to 32 bits so they survive the round trip through the frontend unchanged.
+///
+#[derive(Clone)]
+pub struct SeedPropCore(u32);
+
+impl SeedPropCore {
+    pub fn new(seed: u32) -> Self {
+        Self(seed)
+    }
+
+    pub fn new_random() -> Self {
+        Self(random())
+    }
+
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl PropCore for SeedPropCore {
+    type Value = u32;
+
+    fn read(&self) -> PropRead<'_, Self::Value> {
+        PropRead::Ref(&self.0)
+    }
+
+    fn write(&mut self) -> PropWrite<'_, Self::Value> {
+        PropWrite::Ref(&mut self.0)
+    }
+
+    fn fork_dyn(&self) -> Box<dyn PropCore<Value=Self::Value>> {
+        Box::new(self.fork())
+    }
+}
+
+impl ErasedPropCore for SeedPropCore {
+    fn prop_type_id(&self) -> String {
+        "seed".to_string()
+    }
+
+    fn try_update(&mut self, str: &str) -> Result<(), String> {
+        self.0 = match serde_json::from_str::<serde_json::Value>(str).map_err(|e| e.to_string())? {
+            serde_json::Value::String(s) if s == "random" => random(),
+            value => serde_json::from_value(value).map_err(|e| e.to_string())?,
+        };
+        Ok(())
+    }
+
+    fn value_serialize(&self) -> Box<dyn erased_serde::Serialize + '_> {
+        Box::new(&self.0)
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use nalgebra_glm::DVec3;
+use serde::Deserialize;
+use crate::pattern_builder::component::frame::{ColorPixel, Frame};
+use crate::pattern_builder::offline_render::OfflineRenderer;
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum ExportFormat {
+    /// An animation, one GIF frame per rendered frame, laid out with [`ExportLayout`].
+    Gif,
+    /// A single image with one row per frame and one column per pixel.
+    Png,
+    /// One `frame,t,pixel,r,g,b` row per pixel per frame.
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gif" => Some(ExportFormat::Gif),
+            "png" => Some(ExportFormat::Png),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Where each pixel is drawn in an animated export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum ExportLayout {
+    /// Pixels in a single row, in index order.
+    Strip,
+    /// Pixel positions projected onto the XY plane, with +Y up.
+    Flat,
+    /// Pixel positions in an isometric view, for 3D layouts.
+    Isometric,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub num_frames: usize,
+    pub fps: f64,
+    pub start_t: f64,
+    pub layout: ExportLayout,
+    /// The width and height of each drawn pixel, in image pixels.
+    pub pixel_size: u32,
+    /// The size of the longest side of a projected layout, in image pixels.
+    pub image_size: u32,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            num_frames: 120,
+            fps: 30.0,
+            start_t: 0.0,
+            layout: ExportLayout::Strip,
+            pixel_size: 8,
+            image_size: 256,
+        }
+    }
+}
+
+pub fn export_to_file(renderer: &mut OfflineRenderer, options: &ExportOptions, format: ExportFormat, path: impl AsRef<Path>) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    export(renderer, options, format, &mut writer)?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+pub fn export(renderer: &mut OfflineRenderer, options: &ExportOptions, format: ExportFormat, writer: impl Write) -> Result<(), String> {
+    if options.num_frames == 0 || !options.fps.is_finite() || options.fps <= 0.0 {
+        return Err(format!("Nothing to export. (Check the frame count and fps.)"));
+    }
+    let frames = renderer.render_frames(options.start_t, 1.0 / options.fps, options.num_frames)?;
+    match format {
+        ExportFormat::Gif => write_gif(&frames, renderer.pattern_context().position_map(), options, writer),
+        ExportFormat::Png => write_png(&frames, options, writer),
+        ExportFormat::Csv => write_csv(&frames, options, writer).map_err(|e| e.to_string()),
+    }
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            rgb: vec![0; (width * height * 3) as usize],
+        }
+    }
+
+    fn fill_square(&mut self, (left, top): (u32, u32), size: u32, (r, g, b): (u8, u8, u8)) {
+        for y in top..u32::min(top + size, self.height) {
+            for x in left..u32::min(left + size, self.width) {
+                let i = ((y * self.width + x) * 3) as usize;
+                self.rgb[i..i + 3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    }
+}
+
+///
+/// Works out the canvas size and the top-left corner of each pixel's square. Pixels without a
+/// position are not drawn.
+///
+fn layout_pixels(num_pixels: usize, position_map: &PositionMap, options: &ExportOptions) -> (u32, u32, Vec<Option<(u32, u32)>>) {
+    let pixel_size = options.pixel_size.max(1);
+    let project = match options.layout {
+        ExportLayout::Strip => {
+            let corners = (0..num_pixels)
+                .map(|i| Some((i as u32 * pixel_size, 0)))
+                .collect();
+            return (num_pixels as u32 * pixel_size, pixel_size, corners);
+        },
+        ExportLayout::Flat => |pos: DVec3| (pos.x, pos.y),
+        ExportLayout::Isometric => |pos: DVec3| {
+            let (sin, cos) = 30f64.to_radians().sin_cos();
+            ((pos.x - pos.z) * cos, pos.y + (pos.x + pos.z) * sin)
+        },
+    };
+    let projected: Vec<Option<(f64, f64)>> = (0..num_pixels)
+        .map(|i| position_map.pos(i).map(project))
+        .collect();
+    let (min_x, max_x, min_y, max_y) = projected.iter().flatten().fold(
+        (f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY),
+        |(min_x, max_x, min_y, max_y), &(x, y)| (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y)),
+    );
+    if min_x > max_x {
+        return (pixel_size, pixel_size, vec![None; num_pixels]);
+    }
+    let drawable_size = options.image_size.saturating_sub(pixel_size).max(1) as f64;
+    let scale = drawable_size / f64::max(max_x - min_x, max_y - min_y).max(f64::EPSILON);
+    let width = ((max_x - min_x) * scale).round() as u32 + pixel_size;
+    let height = ((max_y - min_y) * scale).round() as u32 + pixel_size;
+    let corners = projected.into_iter()
+        .map(|pos| pos.map(|(x, y)| (
+            ((x - min_x) * scale).round() as u32,
+            ((max_y - y) * scale).round() as u32,
+        )))
+        .collect();
+    (width, height, corners)
+}
+
+fn write_gif(frames: &[Frame<ColorPixel>], position_map: &PositionMap, options: &ExportOptions, writer: impl Write) -> Result<(), String> {
+    let num_pixels = frames.iter().map(|frame| frame.len()).max().unwrap_or(0);
+    let (width, height, corners) = layout_pixels(num_pixels, position_map, options);
+    let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => (width, height),
+        _ => return Err(format!("Image is too large for a GIF ({}x{}).", width, height)),
+    };
+    let mut encoder = gif::Encoder::new(writer, width, height, &[]).map_err(|e| e.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+    let delay = (100.0 / options.fps).round().max(1.0) as u16;
+    for frame in frames {
+        let mut canvas = Canvas::new(width as u32, height as u32);
+        for (corner, color) in corners.iter().zip(frame.clone().into_srgb_components()) {
+            if let Some(corner) = corner {
+                canvas.fill_square(*corner, options.pixel_size.max(1), color);
+            }
+        }
+        let mut gif_frame = gif::Frame::from_rgb(width, height, &canvas.rgb);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_png(frames: &[Frame<ColorPixel>], options: &ExportOptions, writer: impl Write) -> Result<(), String> {
+    let num_pixels = frames.iter().map(|frame| frame.len()).max().unwrap_or(0);
+    let pixel_size = options.pixel_size.max(1);
+    let mut canvas = Canvas::new(num_pixels as u32 * pixel_size, frames.len() as u32 * pixel_size);
+    for (frame_index, frame) in frames.iter().enumerate() {
+        for (pixel_index, color) in frame.clone().into_srgb_components().into_iter().enumerate() {
+            canvas.fill_square((pixel_index as u32 * pixel_size, frame_index as u32 * pixel_size), pixel_size, color);
+        }
+    }
+    let mut encoder = png::Encoder::new(writer, canvas.width, canvas.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut png_writer| png_writer.write_image_data(&canvas.rgb))
+        .map_err(|e| e.to_string())
+}
+
+fn write_csv(frames: &[Frame<ColorPixel>], options: &ExportOptions, mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "frame,t,pixel,r,g,b")?;
+    for (frame_index, frame) in frames.iter().enumerate() {
+        let t = options.start_t + frame_index as f64 / options.fps;
+        for (pixel_index, (r, g, b)) in frame.clone().into_srgb_components().into_iter().enumerate() {
+            writeln!(writer, "{},{},{},{},{},{}", frame_index, t, pixel_index, r, g, b)?;
+        }
+    }
+    Ok(())
+}
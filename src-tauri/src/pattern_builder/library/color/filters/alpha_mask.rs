@@ -48,4 +48,8 @@ impl LayerCore for AlphaMask {
     fn detach(&mut self) {
         fork_properties!(self.mask);
     }
+
+    fn reset(&mut self) {
+        self.mask.write().reset();
+    }
 }
\ No newline at end of file
@@ -99,4 +99,8 @@ impl LayerCore for MapHslComponent {
             self.map,
         );
     }
+
+    fn reset(&mut self) {
+        self.map.write().reset();
+    }
 }
\ No newline at end of file
@@ -63,4 +63,8 @@ impl<T> LayerCore for Repeater<T> where T: Pixel, Frame<T>: DynType {
             self.pixels_per_repeat,
         );
     }
+
+    fn reset(&mut self) {
+        self.layer_stack.write().reset();
+    }
 }
@@ -1,2 +1,4 @@
 pub mod group;
-pub mod empty;
\ No newline at end of file
+pub mod empty;
+pub mod zone;
+pub mod transform;
@@ -50,5 +50,9 @@ impl LayerCore for Group {
     fn detach(&mut self) {
         fork_properties!(self.stack);
     }
+
+    fn reset(&mut self) {
+        self.stack.write().reset();
+    }
 }
 
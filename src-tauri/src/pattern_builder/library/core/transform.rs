@@ -0,0 +1,124 @@
+use std::marker::PhantomData;
+use nalgebra_glm::{DMat3, DVec3};
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{DisplayPane, Layer, LayerCore, LayerIcon, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::layer::layer_stack::LayerStack;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::layer_stack::LayerStackPropCore;
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// Scales smaller than this are treated as this, so the transform can always be undone.
+const MIN_SCALE: f64 = 1e-6;
+
+///
+/// Moves, turns and stretches whatever its layers draw, by giving them a layout with every position
+/// transformed the opposite way. Works in centered coordinates (see [`Bounds::centered`]): turning
+/// spins around the middle of the layout, and a translation of 1 is half its longest side.
+///
+/// The content is scaled, then rotated about X, Y and Z in turn, then translated. Rotations are in
+/// degrees, and the velocity and rotation speed keep it moving and spinning over time.
+///
+/// [`Bounds::centered`]: crate::pattern_builder::pattern_context::position_map::Bounds::centered
+///
+#[derive(Clone)]
+pub struct Transform<T> where T: Pixel, Frame<T>: DynType {
+    stack: Prop<LayerStack>,
+    translation: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    rotation: Prop<DVec3>,
+    rotation_speed: Prop<DVec3>,
+    scale: Prop<DVec3>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Transform<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new() -> Self {
+        Self {
+            stack: LayerStackPropCore::new(LayerStack::new()).into_prop(PropertyInfo::unnamed().set_display_pane(DisplayPane::Tree)),
+            translation: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Translation")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            rotation: NumVecPropCore::new_slider(DVec3::zeros(), -180.0..180.0, 1.0).into_prop(PropertyInfo::new("Rotation")),
+            rotation_speed: NumVecPropCore::new_slider(DVec3::zeros(), -360.0..360.0, 1.0).into_prop(PropertyInfo::new("Rotation Speed")),
+            scale: NumVecPropCore::new_slider(DVec3::repeat(1.0), 0.0..4.0, 0.01).into_prop(PropertyInfo::new("Scale")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn stack(&self) -> &Prop<LayerStack> {
+        &self.stack
+    }
+
+    pub fn translation(&self) -> &Prop<DVec3> {
+        &self.translation
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn rotation(&self) -> &Prop<DVec3> {
+        &self.rotation
+    }
+
+    pub fn rotation_speed(&self) -> &Prop<DVec3> {
+        &self.rotation_speed
+    }
+
+    pub fn scale(&self) -> &Prop<DVec3> {
+        &self.scale
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Transform").with_icon(LayerIcon::Group))
+    }
+}
+
+/// Rotates about X, then Y, then Z, by the given angles in degrees.
+fn rotation_matrix(degrees: DVec3) -> DMat3 {
+    let axes = [DVec3::x(), DVec3::y(), DVec3::z()];
+    axes.iter().zip(degrees.iter())
+        .map(|(axis, angle)| nalgebra_glm::mat4_to_mat3(&nalgebra_glm::rotation(angle.to_radians(), axis)))
+        .fold(DMat3::identity(), |rotation, axis_rotation| axis_rotation * rotation)
+}
+
+impl<T> LayerCore for Transform<T> where T: Pixel, Frame<T>: DynType {
+    type Input = ();
+    type Output = Frame<T>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Frame<T> {
+        let translation = *self.translation.read() + t * *self.velocity.read();
+        let rotation = rotation_matrix(*self.rotation.read() + t * *self.rotation_speed.read());
+        let scale = self.scale.read().map(|s| if s.abs() < MIN_SCALE { MIN_SCALE.copysign(s) } else { s });
+
+        // Each position is taken back to where the content drawn there came from.
+        let bounds = *ctx.bounds();
+        let (center, bounds_scale) = (bounds.center(), bounds.scale());
+        let inverse_rotation = rotation.transpose();
+        // Only stretching one axis more than another changes which pixels are nearest.
+        let keeps_neighbors = scale.x.abs() == scale.y.abs() && scale.y.abs() == scale.z.abs();
+        let transformed_ctx = ctx.transform_positions(|pos| {
+            let moved = inverse_rotation * (bounds.centered(pos) - translation);
+            center + moved.component_div(&scale) * bounds_scale
+        }, keeps_neighbors);
+
+        let mut pixel_data: Frame<T> = self.stack.write().next((), t, &transformed_ctx)
+            .unwrap_or_else(|_err| Frame::empty(ctx.num_pixels()));
+        pixel_data.resize_with_empty(ctx.num_pixels());
+        pixel_data
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.stack, self.translation, self.velocity, self.rotation, self.rotation_speed, self.scale)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.stack, self.translation, self.velocity, self.rotation, self.rotation_speed, self.scale);
+    }
+
+    fn reset(&mut self) {
+        self.stack.write().reset();
+    }
+}
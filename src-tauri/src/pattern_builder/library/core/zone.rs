@@ -0,0 +1,72 @@
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{ColorPixel, Frame};
+use crate::pattern_builder::component::layer::{DisplayPane, Layer, LayerCore, LayerIcon, LayerTypeInfo};
+use crate::pattern_builder::component::layer::layer_stack::LayerStack;
+use crate::pattern_builder::component::property::PropertyInfo;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropView};
+use crate::pattern_builder::component::property::layer_stack::LayerStackPropCore;
+use crate::pattern_builder::component::property::string::StringPropCore;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// A group that only draws into one zone of the layout. Its layers see the zone as if it were the
+/// whole strip, and every pixel outside the zone is left empty. Nothing is drawn if the layout has
+/// no zone by that name.
+///
+#[derive(Clone)]
+pub struct Zone {
+    zone: Prop<String>,
+    stack: Prop<LayerStack>,
+}
+
+impl Zone {
+    pub fn new(zone: &str) -> Self {
+        Self {
+            zone: StringPropCore::new(zone.to_string()).into_prop(PropertyInfo::new("Zone")),
+            stack: LayerStackPropCore::new(LayerStack::new()).into_prop(PropertyInfo::unnamed().set_display_pane(DisplayPane::Tree)),
+        }
+    }
+
+    pub fn zone(&self) -> &Prop<String> {
+        &self.zone
+    }
+
+    pub fn stack(&self) -> &Prop<LayerStack> {
+        &self.stack
+    }
+
+    pub fn into_layer(self) -> Layer where Self: Sized {
+        Layer::new_texture(self, LayerTypeInfo::new("Zone").with_icon(LayerIcon::Group))
+    }
+}
+
+impl LayerCore for Zone {
+    type Input = ();
+    type Output = Frame<ColorPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Frame<ColorPixel> {
+        let mut pixel_data = Frame::empty(ctx.num_pixels());
+        let Some(selection) = ctx.zone(&self.zone.read()).cloned() else {
+            return pixel_data;
+        };
+        let zone_ctx = ctx.select(&selection);
+        let zone_data: Frame<ColorPixel> = self.stack.write().next((), t, &zone_ctx)
+            .unwrap_or_else(|_err| vec![].into());
+        for (index, pixel) in selection.indices(ctx.num_pixels()).into_iter().zip(zone_data.iter()) {
+            pixel_data[index] = *pixel;
+        }
+        pixel_data
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.zone, self.stack)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.zone, self.stack);
+    }
+
+    fn reset(&mut self) {
+        self.stack.write().reset();
+    }
+}
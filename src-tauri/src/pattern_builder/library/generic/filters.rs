@@ -1,2 +1,4 @@
 pub mod persistence;
 pub mod stutter;
+pub mod remap;
+pub mod neighborhood;
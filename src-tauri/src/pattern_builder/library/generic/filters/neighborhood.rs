@@ -0,0 +1,40 @@
+//!
+//! Filters that mix each pixel with its neighbours, for any kind of pixel. Neighbours are either
+//! the pixels either side on the strip or the pixels closest in the layout, chosen with each
+//! filter's "Neighbors" slider: 0 for the strip, or how many of the closest pixels to use.
+//!
+
+use crate::pattern_builder::component::frame::{Frame, Linear, Pixel};
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::pattern_context::neighbors::{NeighborGraph, Topology};
+
+pub mod blur;
+pub mod glow;
+pub mod sharpen;
+pub mod edge_detect;
+
+fn neighbors_prop(topology: Topology) -> Prop<f64> {
+    NumPropCore::new_slider(topology.count(), 0.0..12.0, 1.0).into_prop(PropertyInfo::new("Neighbors"))
+}
+
+/// The average of each pixel and its neighbours.
+fn neighbor_means<T: Pixel + Linear>(frame: &Frame<T>, graph: &NeighborGraph) -> Frame<T> {
+    (0..frame.len())
+        .map(|index| {
+            let neighbors = graph.neighbors(index).iter().filter(|neighbor| **neighbor < frame.len());
+            let weight = 1.0 / (neighbors.clone().count() + 1) as f64;
+            neighbors.fold(T::empty().add_weighted(frame[index].clone(), weight), |sum, neighbor| {
+                sum.add_weighted(frame[*neighbor].clone(), weight)
+            })
+        })
+        .collect()
+}
+
+/// Averages with neighbours `passes` times over, so each pass spreads the blur one neighbour further.
+fn blur<T: Pixel + Linear>(mut frame: Frame<T>, graph: &NeighborGraph, passes: usize) -> Frame<T> {
+    for _ in 0..passes {
+        frame = neighbor_means(&frame, graph);
+    }
+    frame
+}
@@ -0,0 +1,58 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Linear, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::neighborhood::{blur, neighbors_prop};
+use crate::pattern_builder::pattern_context::neighbors::Topology;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// Softens the frame by averaging each pixel with its neighbours, `passes` times over.
+#[derive(Clone)]
+pub struct Blur<T> where T: Pixel + Linear, Frame<T>: DynType {
+    neighbors: Prop<f64>,
+    passes: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Blur<T> where T: Pixel + Linear, Frame<T>: DynType {
+    pub fn new(topology: Topology, passes: usize) -> Self {
+        Self {
+            neighbors: neighbors_prop(topology),
+            passes: NumPropCore::new_slider(passes as f64, 1.0..20.0, 1.0).into_prop(PropertyInfo::new("Passes")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn neighbors(&self) -> &Prop<f64> {
+        &self.neighbors
+    }
+
+    pub fn passes(&self) -> &Prop<f64> {
+        &self.passes
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Blur"))
+    }
+}
+
+impl<T> LayerCore for Blur<T> where T: Pixel + Linear, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, ctx: &PatternContext) -> Frame<T> {
+        let graph = ctx.neighbors(Topology::from_count(*self.neighbors.read()));
+        blur(active, &graph, self.passes.read().round().max(0.0) as usize)
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.neighbors, self.passes)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.neighbors, self.passes);
+    }
+}
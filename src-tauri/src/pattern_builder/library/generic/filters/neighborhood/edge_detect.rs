@@ -0,0 +1,65 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Linear, Pixel, ScalarPixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerIcon, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::neighborhood::{neighbor_means, neighbors_prop};
+use crate::pattern_builder::pattern_context::neighbors::Topology;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Finds where the frame changes sharply: each pixel becomes how far it is from the average of its
+/// neighbours, multiplied by `gain` and capped at 1. Flat areas come out as 0 whatever their color.
+///
+#[derive(Clone)]
+pub struct EdgeDetect<T> where T: Pixel + Linear, Frame<T>: DynType {
+    neighbors: Prop<f64>,
+    gain: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> EdgeDetect<T> where T: Pixel + Linear, Frame<T>: DynType {
+    pub fn new(topology: Topology, gain: f64) -> Self {
+        Self {
+            neighbors: neighbors_prop(topology),
+            gain: NumPropCore::new_slider(gain, 0.0..10.0, 0.01).into_prop(PropertyInfo::new("Gain")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn neighbors(&self) -> &Prop<f64> {
+        &self.neighbors
+    }
+
+    pub fn gain(&self) -> &Prop<f64> {
+        &self.gain
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new(self, LayerTypeInfo::new("Edge Detect").with_icon(LayerIcon::Transformer))
+    }
+}
+
+impl<T> LayerCore for EdgeDetect<T> where T: Pixel + Linear, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, ctx: &PatternContext) -> Frame<ScalarPixel> {
+        let graph = ctx.neighbors(Topology::from_count(*self.neighbors.read()));
+        let means = neighbor_means(&active, &graph);
+        let gain = *self.gain.read();
+        active.iter().zip(means.iter())
+            .map(|(pixel, mean)| (pixel.difference(mean) * gain).min(1.0))
+            .collect()
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.neighbors, self.gain)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.neighbors, self.gain);
+    }
+}
@@ -0,0 +1,70 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Linear, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::neighborhood::{blur, neighbors_prop};
+use crate::pattern_builder::pattern_context::neighbors::Topology;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Adds a blurred copy of the frame back on top of itself, so bright pixels bleed light into the
+/// pixels around them. `spread` is how many blur passes the halo gets, and `intensity` how much of
+/// it is added. Bright areas can end up past full brightness, which the strip clips.
+///
+#[derive(Clone)]
+pub struct Glow<T> where T: Pixel + Linear, Frame<T>: DynType {
+    neighbors: Prop<f64>,
+    spread: Prop<f64>,
+    intensity: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Glow<T> where T: Pixel + Linear, Frame<T>: DynType {
+    pub fn new(topology: Topology, spread: usize, intensity: f64) -> Self {
+        Self {
+            neighbors: neighbors_prop(topology),
+            spread: NumPropCore::new_slider(spread as f64, 1.0..20.0, 1.0).into_prop(PropertyInfo::new("Spread")),
+            intensity: NumPropCore::new_slider(intensity, 0.0..2.0, 0.01).into_prop(PropertyInfo::new("Intensity")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn neighbors(&self) -> &Prop<f64> {
+        &self.neighbors
+    }
+
+    pub fn spread(&self) -> &Prop<f64> {
+        &self.spread
+    }
+
+    pub fn intensity(&self) -> &Prop<f64> {
+        &self.intensity
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Glow"))
+    }
+}
+
+impl<T> LayerCore for Glow<T> where T: Pixel + Linear, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, ctx: &PatternContext) -> Frame<T> {
+        let graph = ctx.neighbors(Topology::from_count(*self.neighbors.read()));
+        let halo = blur(active.clone(), &graph, self.spread.read().round().max(0.0) as usize);
+        let intensity = *self.intensity.read();
+        active.blend_using(halo, |pixel, halo| pixel.add_weighted(halo, intensity))
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.neighbors, self.spread, self.intensity)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.neighbors, self.spread, self.intensity);
+    }
+}
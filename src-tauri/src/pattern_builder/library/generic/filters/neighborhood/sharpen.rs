@@ -0,0 +1,61 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Linear, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::neighborhood::{neighbor_means, neighbors_prop};
+use crate::pattern_builder::pattern_context::neighbors::Topology;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// Pushes each pixel away from the average of its neighbours by `amount`, exaggerating contrast.
+#[derive(Clone)]
+pub struct Sharpen<T> where T: Pixel + Linear, Frame<T>: DynType {
+    neighbors: Prop<f64>,
+    amount: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Sharpen<T> where T: Pixel + Linear, Frame<T>: DynType {
+    pub fn new(topology: Topology, amount: f64) -> Self {
+        Self {
+            neighbors: neighbors_prop(topology),
+            amount: NumPropCore::new_slider(amount, 0.0..5.0, 0.01).into_prop(PropertyInfo::new("Amount")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn neighbors(&self) -> &Prop<f64> {
+        &self.neighbors
+    }
+
+    pub fn amount(&self) -> &Prop<f64> {
+        &self.amount
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Sharpen"))
+    }
+}
+
+impl<T> LayerCore for Sharpen<T> where T: Pixel + Linear, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, ctx: &PatternContext) -> Frame<T> {
+        let graph = ctx.neighbors(Topology::from_count(*self.neighbors.read()));
+        let means = neighbor_means(&active, &graph);
+        let amount = *self.amount.read();
+        // Alpha is clamped at each step, so subtract the mean before adding the pixel to keep opaque pixels opaque.
+        active.blend_using(means, |pixel, mean| pixel.clone().add_weighted(mean, -amount).add_weighted(pixel, amount))
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.neighbors, self.amount)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.neighbors, self.amount);
+    }
+}
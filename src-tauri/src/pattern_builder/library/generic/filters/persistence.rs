@@ -60,4 +60,9 @@ impl<T> LayerCore for Persistence<T> where T: Blend + Opacity + DynType + Clone
             self.decay_rate,
         );
     }
+
+    fn reset(&mut self) {
+        self.state = None;
+        self.last_t = 0.0;
+    }
 }
\ No newline at end of file
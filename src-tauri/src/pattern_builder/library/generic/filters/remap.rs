@@ -0,0 +1,53 @@
+//!
+//! Filters that rearrange a frame by pixel index, for any kind of pixel. Each output pixel is read
+//! from a fractional position in the input, mixing the two nearest pixels, so remaps can move
+//! smoothly rather than jumping a pixel at a time.
+//!
+
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+
+pub mod reverse;
+pub mod mirror;
+pub mod offset;
+pub mod stretch;
+pub mod kaleidoscope;
+
+/// What to read for positions beyond either end of the frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Edge {
+    /// The nearest end pixel.
+    Clamp,
+    /// Around from the other end.
+    Wrap,
+    /// An empty pixel.
+    Empty,
+}
+
+/// Reads `frame` at a fractional pixel index.
+fn sample<T: Pixel>(frame: &Frame<T>, pos: f64, edge: Edge) -> T {
+    let len = frame.len() as i64;
+    if len == 0 || !pos.is_finite() {
+        return T::empty();
+    }
+    let pixel = |index: i64| -> T {
+        let index = match edge {
+            Edge::Clamp => index.clamp(0, len - 1),
+            Edge::Wrap => index.rem_euclid(len),
+            Edge::Empty if index < 0 || index >= len => return T::empty(),
+            Edge::Empty => index,
+        };
+        frame[index as usize].clone()
+    };
+    let below = pos.floor();
+    let amount = pos - below;
+    if amount == 0.0 {
+        pixel(below as i64)
+    } else {
+        pixel(below as i64).mix(pixel(below as i64 + 1), amount)
+    }
+}
+
+/// Builds a frame the same length as `frame`, reading each pixel from `source(index)`.
+fn remap<T: Pixel>(frame: &Frame<T>, edge: Edge, source: impl Fn(usize) -> f64) -> Frame<T> {
+    (0..frame.len()).map(|i| sample(frame, source(i), edge)).collect()
+}
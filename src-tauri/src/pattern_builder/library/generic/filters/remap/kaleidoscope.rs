@@ -0,0 +1,62 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::remap::{remap, Edge};
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Splits the frame into `folds` equal segments that all show the first one, with every other
+/// segment mirrored so neighbouring segments meet seamlessly.
+///
+#[derive(Clone)]
+pub struct Kaleidoscope<T> where T: Pixel, Frame<T>: DynType {
+    folds: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Kaleidoscope<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new(folds: f64) -> Self {
+        Self {
+            folds: NumPropCore::new_slider(folds, 1.0..16.0, 1.0).into_prop(PropertyInfo::new("Folds")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn folds(&self) -> &Prop<f64> {
+        &self.folds
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Kaleidoscope"))
+    }
+}
+
+impl<T> LayerCore for Kaleidoscope<T> where T: Pixel, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, _ctx: &PatternContext) -> Frame<T> {
+        let folds = self.folds.read().max(1.0);
+        let segment = active.len() as f64 / folds;
+        // Works with pixel centres, so mirrored segments line up on whole pixels.
+        remap(&active, Edge::Clamp, |i| {
+            let x = i as f64 + 0.5;
+            let segment_index = (x / segment).floor();
+            let within = x - segment_index * segment;
+            let within = if segment_index % 2.0 == 1.0 { segment - within } else { within };
+            within - 0.5
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.folds)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.folds);
+    }
+}
@@ -0,0 +1,54 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::remap::{remap, Edge};
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Reflects the start of the frame onto the end, about `center`: 0 is the first pixel, 1 the last
+/// and 0.5 the middle.
+///
+#[derive(Clone)]
+pub struct Mirror<T> where T: Pixel, Frame<T>: DynType {
+    center: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Mirror<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new(center: f64) -> Self {
+        Self {
+            center: NumPropCore::new_slider(center, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn center(&self) -> &Prop<f64> {
+        &self.center
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Mirror"))
+    }
+}
+
+impl<T> LayerCore for Mirror<T> where T: Pixel, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, _ctx: &PatternContext) -> Frame<T> {
+        let center = *self.center.read() * (active.len() as f64 - 1.0);
+        remap(&active, Edge::Clamp, |i| center - (i as f64 - center).abs())
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center);
+    }
+}
@@ -0,0 +1,68 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::component::property::raw::RawPropCore;
+use crate::pattern_builder::library::generic::filters::remap::{remap, Edge};
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Shifts the frame along by `offset` pixels, plus `speed` pixels a second. Pixels pushed off one
+/// end come back round at the other when wrapping, or else leave empty pixels behind.
+///
+#[derive(Clone)]
+pub struct Offset<T> where T: Pixel, Frame<T>: DynType {
+    offset: Prop<f64>,
+    speed: Prop<f64>,
+    wrap: Prop<bool>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Offset<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new(offset: f64, speed: f64, wrap: bool) -> Self {
+        Self {
+            offset: NumPropCore::new_slider(offset, -500.0..500.0, 0.1).into_prop(PropertyInfo::new("Offset")),
+            speed: NumPropCore::new_slider(speed, -100.0..100.0, 0.1).into_prop(PropertyInfo::new("Speed")),
+            wrap: RawPropCore::new(wrap).into_prop(PropertyInfo::new("Wrap")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn offset(&self) -> &Prop<f64> {
+        &self.offset
+    }
+
+    pub fn speed(&self) -> &Prop<f64> {
+        &self.speed
+    }
+
+    pub fn wrap(&self) -> &Prop<bool> {
+        &self.wrap
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Offset"))
+    }
+}
+
+impl<T> LayerCore for Offset<T> where T: Pixel, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, t: f64, _ctx: &PatternContext) -> Frame<T> {
+        let shift = *self.offset.read() + t * *self.speed.read();
+        let edge = if *self.wrap.read() { Edge::Wrap } else { Edge::Empty };
+        remap(&active, edge, |i| i as f64 - shift)
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.offset, self.speed, self.wrap)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.offset, self.speed, self.wrap);
+    }
+}
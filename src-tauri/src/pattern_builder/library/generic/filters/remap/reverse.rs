@@ -0,0 +1,41 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::PropView;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// Flips the frame end to end.
+#[derive(Clone)]
+pub struct Reverse<T> where T: Pixel, Frame<T>: DynType {
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Reverse<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new() -> Self {
+        Self { phantom_data: PhantomData::default() }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Reverse"))
+    }
+}
+
+impl<T> LayerCore for Reverse<T> where T: Pixel, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, mut active: Frame<T>, _t: f64, _ctx: &PatternContext) -> Frame<T> {
+        active.reverse();
+        active
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!()
+    }
+
+    fn detach(&mut self) {
+        fork_properties!();
+    }
+}
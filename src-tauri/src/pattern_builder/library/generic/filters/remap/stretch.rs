@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::layer::io_type::DynType;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::pattern_builder::library::generic::filters::remap::{remap, Edge};
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Stretches the frame by `factor` away from `anchor`, where 0 is the first pixel and 1 the last.
+/// Factors below 1 squash it instead, leaving empty pixels where it no longer reaches.
+///
+#[derive(Clone)]
+pub struct Stretch<T> where T: Pixel, Frame<T>: DynType {
+    factor: Prop<f64>,
+    anchor: Prop<f64>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<T> Stretch<T> where T: Pixel, Frame<T>: DynType {
+    pub fn new(factor: f64, anchor: f64) -> Self {
+        Self {
+            factor: NumPropCore::new_slider(factor, 0.0..10.0, 0.01).into_prop(PropertyInfo::new("Factor")),
+            anchor: NumPropCore::new_slider(anchor, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Anchor")),
+            phantom_data: PhantomData::default(),
+        }
+    }
+
+    pub fn factor(&self) -> &Prop<f64> {
+        &self.factor
+    }
+
+    pub fn anchor(&self) -> &Prop<f64> {
+        &self.anchor
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_filter(self, LayerTypeInfo::new("Stretch"))
+    }
+}
+
+impl<T> LayerCore for Stretch<T> where T: Pixel, Frame<T>: DynType {
+    type Input = Frame<T>;
+    type Output = Frame<T>;
+
+    fn next(&mut self, active: Frame<T>, _t: f64, _ctx: &PatternContext) -> Frame<T> {
+        let factor = *self.factor.read();
+        if factor <= 0.0 {
+            return Frame::empty(active.len());
+        }
+        let anchor = *self.anchor.read() * (active.len() as f64 - 1.0);
+        remap(&active, Edge::Empty, |i| anchor + (i as f64 - anchor) / factor)
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.factor, self.anchor)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.factor, self.anchor);
+    }
+}
@@ -96,4 +96,9 @@ impl<T> LayerCore for Stutter<T> where T: DynType + Clone {
             fork_properties!(*prop);
         }
     }
+
+    fn reset(&mut self) {
+        self.last_frame_t = 0.0;
+        self.frame = None;
+    }
 }
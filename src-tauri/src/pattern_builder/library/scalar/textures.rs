@@ -3,3 +3,4 @@ pub mod simplex_noise;
 pub mod dual_waves;
 pub mod pulse;
 pub mod heart;
+pub mod masks;
@@ -62,15 +62,17 @@ impl LayerCore for DualWaves {
     type Input = ();
     type Output = Frame<ScalarPixel>;
     fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Frame<ScalarPixel> {
-        (0..ctx.num_pixels()).map(|x_int| {
+        let (wave1_speed, wave1_scale, wave1_skew) = (*self.wave1_speed.read(), *self.wave1_scale.read(), *self.wave1_skew.read());
+        let (wave2_speed, wave2_scale, wave2_skew) = (*self.wave2_speed.read(), *self.wave2_scale.read(), *self.wave2_skew.read());
+        ctx.map_pixels(|x_int, _| {
             let x = x_int as f64;
             // let t = t + ((x/10.0 + t).sin() / 2.0);
-            let mut wave1_val = skew_sin(*self.wave1_skew.read(), 1.0, (x + *self.wave1_speed.read() * t) / *self.wave1_scale.read());
-            let mut wave2_val = skew_sin(*self.wave2_skew.read(), 1.0, (x + *self.wave2_speed.read() * t) / *self.wave2_scale.read());
+            let mut wave1_val = skew_sin(wave1_skew, 1.0, (x + wave1_speed * t) / wave1_scale);
+            let mut wave2_val = skew_sin(wave2_skew, 1.0, (x + wave2_speed * t) / wave2_scale);
             wave1_val = wave1_val / 2.0 + 0.5;
             wave2_val = wave2_val / 2.0 + 0.5;
             (wave1_val + wave2_val) / 2.0
-        }).collect()
+        })
     }
 
     fn view_properties(&self) -> Vec<PropView> {
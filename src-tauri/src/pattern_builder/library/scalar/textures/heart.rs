@@ -7,6 +7,12 @@ use crate::{fork_properties, view_properties};
 use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
 use crate::pattern_builder::pattern_context::PatternContext;
 
+///
+/// A heart outline. Positions are centered on the layout (see [`Bounds::centered`]), so `center`,
+/// `scale` and `width` look the same whatever units the layout is in.
+///
+/// [`Bounds::centered`]: crate::pattern_builder::pattern_context::position_map::Bounds::centered
+///
 #[derive(Clone)]
 pub struct Heart {
     center: Prop<DVec3>,
@@ -19,7 +25,7 @@ impl Heart {
         Self {
             center: NumVecPropCore::new(center).into_prop(PropertyInfo::new("Center")),
             scale: NumPropCore::new_slider(scale, 0.0..10.0, 0.1).into_prop(PropertyInfo::new("Scale")),
-            width: NumPropCore::new_slider(2.5, 0.0..10.0, 0.1).into_prop(PropertyInfo::new("Width")),
+            width: NumPropCore::new_slider(0.3, 0.0..2.0, 0.01).into_prop(PropertyInfo::new("Width")),
         }
     }
 
@@ -45,22 +51,21 @@ impl LayerCore for Heart {
     type Output = Frame<ScalarPixel>;
 
     fn next(&mut self, input: Self::Input, t: f64, ctx: &PatternContext) -> Self::Output {
-        (0..ctx.num_pixels())
-            .map(|x| {
-                if let Some(pos) = ctx.position_map().pos(x) {
-                    let mut vector = pos - *self.center.read();
-                    let scale = *self.scale.read();
-                    vector = vector.scale(5.0 / scale);
-                    let c = vector.x.powf(2.0) + (vector.y - vector.x.powf(2.0).powf(1.0/3.0)).powf(2.0);
-                    // x^2 + (y - x^(2/3))^2 = 5
-                    let target_c = 5.0;
-                    let w2 = *self.width.read() * 25.0 / scale;
-                    smoothstep(target_c-w2, target_c, c) - smoothstep(target_c, target_c+w2, c)
-                } else {
-                    ScalarPixel::empty()
-                }
-            })
-            .collect()
+        let center = *self.center.read();
+        let scale = *self.scale.read();
+        let w2 = *self.width.read() * 25.0 / scale;
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| {
+            if let Some(pos) = pos {
+                let vector = (bounds.centered(pos) - center).scale(5.0 / scale);
+                let c = vector.x.powf(2.0) + (vector.y - vector.x.powf(2.0).powf(1.0/3.0)).powf(2.0);
+                // x^2 + (y - x^(2/3))^2 = 5
+                let target_c = 5.0;
+                smoothstep(target_c-w2, target_c, c) - smoothstep(target_c, target_c+w2, c)
+            } else {
+                ScalarPixel::empty()
+            }
+        })
     }
 
     fn view_properties(&self) -> Vec<PropView> {
@@ -0,0 +1,30 @@
+//!
+//! Scalar textures that are 1 inside a shape and 0 outside it, for masking by region with
+//! `AlphaMask` or colouring with `ScalarToTexture`.
+//!
+//! Shapes are placed in centered coordinates (see [`Bounds::centered`]), so they cover the same
+//! part of any layout. Each has a velocity its center moves at, to sweep it across the layout.
+//!
+//! [`Bounds::centered`]: crate::pattern_builder::pattern_context::position_map::Bounds::centered
+//!
+
+use nalgebra_glm::smoothstep;
+use crate::pattern_builder::component::frame::ScalarPixel;
+
+pub mod plane;
+pub mod sphere;
+pub mod cuboid;
+pub mod cylinder;
+pub mod distance;
+
+///
+/// The mask value at `distance` from a shape's surface, negative inside. The edge fades out over
+/// `softness`, centered on the surface, or is hard with no softness.
+///
+fn edge(distance: f64, softness: f64) -> ScalarPixel {
+    if softness <= 0.0 {
+        if distance <= 0.0 { 1.0 } else { 0.0 }
+    } else {
+        1.0 - smoothstep(-softness / 2.0, softness / 2.0, distance)
+    }
+}
@@ -0,0 +1,78 @@
+use nalgebra_glm::DVec3;
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
+use crate::pattern_builder::library::scalar::textures::masks::edge;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// An axis-aligned box, `size` across on each axis.
+#[derive(Clone)]
+pub struct BoxMask {
+    center: Prop<DVec3>,
+    size: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    softness: Prop<f64>,
+}
+
+impl BoxMask {
+    pub fn new(center: DVec3, size: DVec3) -> Self {
+        Self {
+            center: NumVecPropCore::new_slider(center, -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            size: NumVecPropCore::new_slider(size, 0.0..4.0, 0.01).into_prop(PropertyInfo::new("Size")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            softness: NumPropCore::new_slider(0.0, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Softness")),
+        }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Box Mask"))
+    }
+
+    pub fn center(&self) -> &Prop<DVec3> {
+        &self.center
+    }
+
+    pub fn size(&self) -> &Prop<DVec3> {
+        &self.size
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn softness(&self) -> &Prop<f64> {
+        &self.softness
+    }
+}
+
+impl LayerCore for BoxMask {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
+        let center = *self.center.read() + t * *self.velocity.read();
+        let half_size = *self.size.read() / 2.0;
+        let softness = *self.softness.read();
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| match pos {
+            Some(pos) => {
+                // How far outside each pair of faces the pixel is, negative when between them.
+                let outside = (bounds.centered(pos) - center).abs() - half_size;
+                let distance = outside.sup(&DVec3::zeros()).magnitude() + outside.max().min(0.0);
+                edge(distance, softness)
+            }
+            None => ScalarPixel::empty(),
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center, self.size, self.velocity, self.softness)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center, self.size, self.velocity, self.softness);
+    }
+}
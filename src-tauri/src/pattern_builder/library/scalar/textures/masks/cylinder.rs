@@ -0,0 +1,100 @@
+use nalgebra_glm::{DVec2, DVec3};
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
+use crate::pattern_builder::library::scalar::textures::masks::edge;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// A cylinder around `axis` through its center, `length` long, or endless with a length of 0.
+#[derive(Clone)]
+pub struct CylinderMask {
+    center: Prop<DVec3>,
+    axis: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    radius: Prop<f64>,
+    length: Prop<f64>,
+    softness: Prop<f64>,
+}
+
+impl CylinderMask {
+    pub fn new(center: DVec3, axis: DVec3, radius: f64) -> Self {
+        Self {
+            center: NumVecPropCore::new_slider(center, -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            axis: NumVecPropCore::new_slider(axis, -1.0..1.0, 0.01).into_prop(PropertyInfo::new("Axis")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            radius: NumPropCore::new_slider(radius, 0.0..2.0, 0.01).into_prop(PropertyInfo::new("Radius")),
+            length: NumPropCore::new_slider(0.0, 0.0..4.0, 0.01).into_prop(PropertyInfo::new("Length")),
+            softness: NumPropCore::new_slider(0.0, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Softness")),
+        }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Cylinder Mask"))
+    }
+
+    pub fn center(&self) -> &Prop<DVec3> {
+        &self.center
+    }
+
+    pub fn axis(&self) -> &Prop<DVec3> {
+        &self.axis
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn radius(&self) -> &Prop<f64> {
+        &self.radius
+    }
+
+    pub fn length(&self) -> &Prop<f64> {
+        &self.length
+    }
+
+    pub fn softness(&self) -> &Prop<f64> {
+        &self.softness
+    }
+}
+
+impl LayerCore for CylinderMask {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
+        let center = *self.center.read() + t * *self.velocity.read();
+        let Some(axis) = self.axis.read().try_normalize(f64::EPSILON) else {
+            return Frame::empty(ctx.num_pixels());
+        };
+        let radius = *self.radius.read();
+        let half_length = *self.length.read() / 2.0;
+        let softness = *self.softness.read();
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| match pos {
+            Some(pos) => {
+                let offset = bounds.centered(pos) - center;
+                let along = offset.dot(&axis);
+                let around = (offset - along * axis).magnitude() - radius;
+                let distance = if half_length > 0.0 {
+                    let outside = DVec2::new(around, along.abs() - half_length);
+                    outside.sup(&DVec2::zeros()).magnitude() + outside.max().min(0.0)
+                } else {
+                    around
+                };
+                edge(distance, softness)
+            }
+            None => ScalarPixel::empty(),
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center, self.axis, self.velocity, self.radius, self.length, self.softness)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center, self.axis, self.velocity, self.radius, self.length, self.softness);
+    }
+}
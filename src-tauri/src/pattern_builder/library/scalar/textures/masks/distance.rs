@@ -0,0 +1,84 @@
+use nalgebra_glm::DVec3;
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// 1 at a point, falling to 0 at `range` away from it. A falloff of 1 fades linearly, higher
+/// values fade faster near the point and lower values hold on longer.
+///
+#[derive(Clone)]
+pub struct DistanceFalloff {
+    center: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    range: Prop<f64>,
+    falloff: Prop<f64>,
+}
+
+impl DistanceFalloff {
+    pub fn new(center: DVec3, range: f64) -> Self {
+        Self {
+            center: NumVecPropCore::new_slider(center, -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            range: NumPropCore::new_slider(range, 0.0..4.0, 0.01).into_prop(PropertyInfo::new("Range")),
+            falloff: NumPropCore::new_slider(1.0, 0.0..5.0, 0.1).into_prop(PropertyInfo::new("Falloff")),
+        }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Distance Falloff"))
+    }
+
+    pub fn center(&self) -> &Prop<DVec3> {
+        &self.center
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn range(&self) -> &Prop<f64> {
+        &self.range
+    }
+
+    pub fn falloff(&self) -> &Prop<f64> {
+        &self.falloff
+    }
+}
+
+impl LayerCore for DistanceFalloff {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
+        let center = *self.center.read() + t * *self.velocity.read();
+        let range = *self.range.read();
+        let falloff = *self.falloff.read();
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| match pos {
+            Some(_) if range <= 0.0 => 0.0,
+            Some(pos) => {
+                let distance = (bounds.centered(pos) - center).magnitude();
+                // Checked before `powf`, as `0.0.powf(0.0)` is 1.
+                if distance >= range {
+                    0.0
+                } else {
+                    (1.0 - distance / range).powf(falloff)
+                }
+            },
+            None => ScalarPixel::empty(),
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center, self.velocity, self.range, self.falloff)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center, self.velocity, self.range, self.falloff);
+    }
+}
@@ -0,0 +1,76 @@
+use nalgebra_glm::DVec3;
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
+use crate::pattern_builder::library::scalar::textures::masks::edge;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// Everything on the side of a plane its normal points towards, e.g. the top half of the layout.
+#[derive(Clone)]
+pub struct PlaneMask {
+    center: Prop<DVec3>,
+    normal: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    softness: Prop<f64>,
+}
+
+impl PlaneMask {
+    pub fn new(center: DVec3, normal: DVec3) -> Self {
+        Self {
+            center: NumVecPropCore::new_slider(center, -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            normal: NumVecPropCore::new_slider(normal, -1.0..1.0, 0.01).into_prop(PropertyInfo::new("Normal")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            softness: NumPropCore::new_slider(0.0, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Softness")),
+        }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Plane Mask"))
+    }
+
+    pub fn center(&self) -> &Prop<DVec3> {
+        &self.center
+    }
+
+    pub fn normal(&self) -> &Prop<DVec3> {
+        &self.normal
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn softness(&self) -> &Prop<f64> {
+        &self.softness
+    }
+}
+
+impl LayerCore for PlaneMask {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
+        let center = *self.center.read() + t * *self.velocity.read();
+        // Without a direction there's no side to fill.
+        let Some(normal) = self.normal.read().try_normalize(f64::EPSILON) else {
+            return Frame::empty(ctx.num_pixels());
+        };
+        let softness = *self.softness.read();
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| match pos {
+            Some(pos) => edge(-(bounds.centered(pos) - center).dot(&normal), softness),
+            None => ScalarPixel::empty(),
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center, self.normal, self.velocity, self.softness)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center, self.normal, self.velocity, self.softness);
+    }
+}
@@ -0,0 +1,73 @@
+use nalgebra_glm::DVec3;
+use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
+use crate::pattern_builder::component::property::num::NumPropCore;
+use crate::{fork_properties, view_properties};
+use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
+use crate::pattern_builder::library::scalar::textures::masks::edge;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+/// A ball of `radius` around its center.
+#[derive(Clone)]
+pub struct SphereMask {
+    center: Prop<DVec3>,
+    velocity: Prop<DVec3>,
+    radius: Prop<f64>,
+    softness: Prop<f64>,
+}
+
+impl SphereMask {
+    pub fn new(center: DVec3, radius: f64) -> Self {
+        Self {
+            center: NumVecPropCore::new_slider(center, -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Center")),
+            velocity: NumVecPropCore::new_slider(DVec3::zeros(), -2.0..2.0, 0.01).into_prop(PropertyInfo::new("Velocity")),
+            radius: NumPropCore::new_slider(radius, 0.0..2.0, 0.01).into_prop(PropertyInfo::new("Radius")),
+            softness: NumPropCore::new_slider(0.0, 0.0..1.0, 0.01).into_prop(PropertyInfo::new("Softness")),
+        }
+    }
+
+    pub fn into_layer(self) -> Layer {
+        Layer::new_texture(self, LayerTypeInfo::new("Sphere Mask"))
+    }
+
+    pub fn center(&self) -> &Prop<DVec3> {
+        &self.center
+    }
+
+    pub fn velocity(&self) -> &Prop<DVec3> {
+        &self.velocity
+    }
+
+    pub fn radius(&self) -> &Prop<f64> {
+        &self.radius
+    }
+
+    pub fn softness(&self) -> &Prop<f64> {
+        &self.softness
+    }
+}
+
+impl LayerCore for SphereMask {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
+        let center = *self.center.read() + t * *self.velocity.read();
+        let radius = *self.radius.read();
+        let softness = *self.softness.read();
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| match pos {
+            Some(pos) => edge((bounds.centered(pos) - center).magnitude() - radius, softness),
+            None => ScalarPixel::empty(),
+        })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!(self.center, self.velocity, self.radius, self.softness)
+    }
+
+    fn detach(&mut self) {
+        fork_properties!(self.center, self.velocity, self.radius, self.softness);
+    }
+}
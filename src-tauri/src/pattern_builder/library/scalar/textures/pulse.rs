@@ -44,15 +44,14 @@ impl LayerCore for Pulse {
     type Input = ();
     type Output = Frame<ScalarPixel>;
     fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Frame<ScalarPixel> {
-        let pulse_pos = 0.5 * (triangle_sin(*self.smoothness.read(), *self.period.read(), t) + 1.0) * (ctx.num_pixels() as f64 - *self.width.read());
+        let width = *self.width.read();
+        let pulse_pos = 0.5 * (triangle_sin(*self.smoothness.read(), *self.period.read(), t) + 1.0) * (ctx.num_pixels() as f64 - width);
         let step1 = [pulse_pos - 0.5, pulse_pos + 0.5];
-        let step2 = [pulse_pos + *self.width.read() - 0.5, pulse_pos + *self.width.read() + 0.5];
-        (0..ctx.num_pixels()).into_iter()
-            .map(|x| x as f64)
-            .map(|x| {
-                smoothstep(step1[0], step1[1], x) - smoothstep(step2[0], step2[1], x)
-            })
-            .collect()
+        let step2 = [pulse_pos + width - 0.5, pulse_pos + width + 0.5];
+        ctx.map_pixels(|x, _| {
+            let x = x as f64;
+            smoothstep(step1[0], step1[1], x) - smoothstep(step2[0], step2[1], x)
+        })
     }
 
     fn view_properties(&self) -> Vec<PropView> {
@@ -1,19 +1,26 @@
 use nalgebra_glm::DVec3;
-use noise::{NoiseFn, OpenSimplex};
-use rand::random;
+use noise::{NoiseFn, OpenSimplex, Seedable};
 use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
 use crate::{fork_properties, view_properties};
 use crate::pattern_builder::component::frame::{Frame, Pixel, ScalarPixel};
 use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
 use crate::pattern_builder::component::property::num::NumPropCore;
 use crate::pattern_builder::component::property::num_vec::NumVecPropCore;
+use crate::pattern_builder::component::property::seed::SeedPropCore;
 use crate::pattern_builder::pattern_context::PatternContext;
 
+///
+/// Smoothly flowing noise. `scale` and `travel_vel` are relative to the layout's size, see
+/// [`Bounds::centered`], so the noise has the same grain on any layout.
+///
+/// [`Bounds::centered`]: crate::pattern_builder::pattern_context::position_map::Bounds::centered
+///
 #[derive(Clone)]
 pub struct SimplexNoise {
     flow_speed: Prop<f64>,
     scale: Prop<DVec3>,
     travel_vel: Prop<DVec3>,
+    seed: Prop<u32>,
     simplex_noise: OpenSimplex,
 }
 
@@ -21,12 +28,18 @@ impl SimplexNoise {
     pub fn new(flow_speed: f64) -> Self {
         Self {
             flow_speed: NumPropCore::new_slider(flow_speed, 0.0..20.0, 0.1).into_prop(PropertyInfo::new("Flow Speed")),
-            scale: NumVecPropCore::new_slider(DVec3::repeat(1.0), 0.0..1.0, 0.02).into_prop(PropertyInfo::new("Scale")),
-            travel_vel: NumVecPropCore::new_slider(DVec3::repeat(0.0), -100.0..100.0, 0.25).into_prop(PropertyInfo::new("Travel Velocity")),
-            simplex_noise: OpenSimplex::new(random()),
+            scale: NumVecPropCore::new_slider(DVec3::repeat(2.0), 0.0..10.0, 0.05).into_prop(PropertyInfo::new("Scale")),
+            travel_vel: NumVecPropCore::new_slider(DVec3::repeat(0.0), -5.0..5.0, 0.01).into_prop(PropertyInfo::new("Travel Velocity")),
+            seed: SeedPropCore::new_random().into_prop(PropertyInfo::new("Seed")),
+            simplex_noise: OpenSimplex::new(0),
         }
     }
 
+    pub fn with_seed(self, seed: u32) -> Self {
+        *self.seed.write() = seed;
+        self
+    }
+
     pub fn flow_speed(&self) -> &Prop<f64> {
         &self.flow_speed
     }
@@ -39,6 +52,10 @@ impl SimplexNoise {
         &self.travel_vel
     }
 
+    pub fn seed(&self) -> &Prop<u32> {
+        &self.seed
+    }
+
     pub fn into_layer(self) -> Layer {
         Layer::new_texture(self, LayerTypeInfo::new("Simplex Noise"))
     }
@@ -49,28 +66,36 @@ impl LayerCore for SimplexNoise {
     type Output = Frame<ScalarPixel>;
 
     fn next(&mut self, _: (), t: f64, ctx: &PatternContext) -> Self::Output {
-        (0..ctx.num_pixels())
-            .map(|i| {
-                match ctx.position_map().pos(i) {
-                    Some(pos) => {
-                        let noise_pos = (pos - t * *self.travel_vel.read()).component_mul(&*self.scale.read());
-                        self.simplex_noise.get([
-                            t * *self.flow_speed.read(),
-                            noise_pos.x,
-                            noise_pos.y,
-                            noise_pos.z,
-                        ])
-                    },
-                    None => ScalarPixel::empty(),
-                }
-            })
-            .collect()
+        let seed = ctx.derive_seed(*self.seed.read() as u64) as u32;
+        if self.simplex_noise.seed() != seed {
+            self.simplex_noise = OpenSimplex::new(seed);
+        }
+        let travel_vel = *self.travel_vel.read();
+        let scale = *self.scale.read();
+        let flow = t * *self.flow_speed.read();
+        let simplex_noise = &self.simplex_noise;
+        let bounds = *ctx.bounds();
+        ctx.map_pixels(|_, pos| {
+            match pos {
+                Some(pos) => {
+                    let noise_pos = (bounds.centered(pos) - t * travel_vel).component_mul(&scale);
+                    simplex_noise.get([
+                        flow,
+                        noise_pos.x,
+                        noise_pos.y,
+                        noise_pos.z,
+                    ])
+                },
+                None => ScalarPixel::empty(),
+            }
+        })
     }
     fn view_properties(&self) -> Vec<PropView> {
         view_properties!(
             self.flow_speed,
             self.scale,
             self.travel_vel,
+            self.seed,
         )
     }
 
@@ -79,6 +104,7 @@ impl LayerCore for SimplexNoise {
             self.flow_speed,
             self.scale,
             self.travel_vel,
+            self.seed,
         );
     }
 
@@ -1,9 +1,11 @@
 use rand::distributions::{Distribution, Uniform};
+use rand::rngs::StdRng;
 use rand_distr::Poisson;
 use crate::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
 use crate::pattern_builder::component::property::num::NumPropCore;
 use crate::pattern_builder::component::property::{Prop, PropCore, PropertyInfo, PropView};
 use crate::pattern_builder::component::property::computed::ComputedPropCore;
+use crate::pattern_builder::component::property::seed::SeedPropCore;
 use crate::{fork_properties, view_properties};
 use crate::pattern_builder::component::frame::{Frame, ScalarPixel};
 use crate::pattern_builder::library::generic::filters::persistence::Persistence;
@@ -13,8 +15,10 @@ use crate::pattern_builder::pattern_context::PatternContext;
 pub struct Sparkles {
     density: Prop<f64>,
     decay_rate: Prop<f64>,
+    seed: Prop<u32>,
     persistence: Persistence<Frame<ScalarPixel>>,
-    last_t: f64,
+    rng: Option<(u64, StdRng)>,
+    last_t: Option<f64>,
     num_sparkles_remainder: f64,
     weights: Vec<f64>,
 }
@@ -24,8 +28,10 @@ impl Sparkles {
         let new = Self {
             density: NumPropCore::new_slider(density, 0.0..10.0, 0.1).into_prop(PropertyInfo::new("Density")),
             decay_rate: NumPropCore::new_slider(decay_rate, 0.0..25.0, 0.1).into_prop(PropertyInfo::new("Decay Rate")),
+            seed: SeedPropCore::new_random().into_prop(PropertyInfo::new("Seed")),
             persistence: Persistence::new(decay_rate),
-            last_t: 0.0,
+            rng: None,
+            last_t: None,
             num_sparkles_remainder: 0.0,
             weights: vec![],
         };
@@ -33,6 +39,11 @@ impl Sparkles {
         new
     }
 
+    pub fn with_seed(self, seed: u32) -> Self {
+        *self.seed.write() = seed;
+        self
+    }
+
     fn sync_decay_rate(&self) {
         let decay_rate = self.decay_rate.clone();
         self.persistence.decay_rate().replace_core(ComputedPropCore::new(move || *decay_rate.read()));
@@ -46,6 +57,10 @@ impl Sparkles {
         &self.decay_rate
     }
 
+    pub fn seed(&self) -> &Prop<u32> {
+        &self.seed
+    }
+
     pub fn into_layer(self) -> Layer {
         Layer::new_texture(self, LayerTypeInfo::new("Sparkles"))
     }
@@ -56,12 +71,18 @@ impl LayerCore for Sparkles {
     type Output = Frame<ScalarPixel>;
 
     fn next(&mut self, _: Self::Input, t: f64, ctx: &PatternContext) -> Self::Output {
-        let delta_t = (t - self.last_t).max(0.0);
-        self.last_t = t;
+        let delta_t = self.last_t.map_or(0.0, |last_t| (t - last_t).max(0.0));
+        self.last_t = Some(t);
+        let layer_seed = *self.seed.read() as u64;
+        let seed = ctx.derive_seed(layer_seed);
+        if self.rng.as_ref().is_none_or(|(rng_seed, _)| *rng_seed != seed) {
+            self.rng = Some((seed, ctx.seeded_rng(layer_seed)));
+        }
+        let rng = &mut self.rng.as_mut().unwrap().1;
         self.weights.resize(ctx.num_pixels(), 1.0);
         let num_sparkles = if let Ok(poisson) =
             Poisson::new(delta_t * *self.density.read() * ctx.num_pixels() as f64) {
-            poisson.sample(&mut rand::thread_rng()) + self.num_sparkles_remainder
+            poisson.sample(rng) + self.num_sparkles_remainder
         } else {
             self.num_sparkles_remainder
         };
@@ -73,8 +94,8 @@ impl LayerCore for Sparkles {
         }
         let weighted_index = rand::distributions::WeightedIndex::new(self.weights.clone()).unwrap();
         for _ in 0..num_sparkles {
-            let x = weighted_index.sample(&mut rand::thread_rng());
-            let strength = Uniform::new(0.0, 1.0).sample(&mut rand::thread_rng());
+            let x = weighted_index.sample(rng);
+            let strength = Uniform::new(0.0, 1.0).sample(rng);
             values[x] = strength;
             self.weights[x] /= 1.0 + strength;
         }
@@ -84,15 +105,25 @@ impl LayerCore for Sparkles {
     fn view_properties(&self) -> Vec<PropView> {
         view_properties!(
             self.density,
-            self.decay_rate
+            self.decay_rate,
+            self.seed,
         )
     }
 
     fn detach(&mut self) {
         fork_properties!(
             self.density,
-            self.decay_rate
+            self.decay_rate,
+            self.seed,
         );
         self.sync_decay_rate();
     }
+
+    fn reset(&mut self) {
+        self.persistence.reset();
+        self.rng = None;
+        self.last_t = None;
+        self.num_sparkles_remainder = 0.0;
+        self.weights.clear();
+    }
 }
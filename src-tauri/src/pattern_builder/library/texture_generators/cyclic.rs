@@ -54,4 +54,11 @@ impl LayerCore for CyclicLayerGenerator {
             self.textures,
         );
     }
+
+    fn reset(&mut self) {
+        self.next_texture = 0;
+        for texture in self.textures.write().iter_mut() {
+            texture.reset();
+        }
+    }
 }
@@ -87,4 +87,9 @@ impl LayerCore for ScalarToDualTexture {
             self.upper_bound,
         );
     }
+
+    fn reset(&mut self) {
+        self.texture_a.write().reset();
+        self.texture_b.write().reset();
+    }
 }
\ No newline at end of file
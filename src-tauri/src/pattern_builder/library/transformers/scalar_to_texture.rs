@@ -77,4 +77,8 @@ impl LayerCore for ScalarToTexture {
             self.upper_bound,
         );
     }
+
+    fn reset(&mut self) {
+        self.texture.write().reset();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use crate::pattern_builder::component::frame::{with_buffer_pool, ColorPixel, Frame};
+use crate::pattern_builder::component::layer::layer_stack::LayerStack;
+use crate::pattern_builder::component::property::Prop;
+use crate::pattern_builder::pattern::Pattern;
+use crate::pattern_builder::pattern_context::PatternContext;
+
+///
+/// Renders a pattern's layers without a runner task, stepping `t` explicitly rather than following
+/// the clock.
+///
+/// The renderer works on a reset fork of the pattern's stack, so rendering never disturbs the live
+/// pattern, and the same `t` sequence and seed always produce the same frames.
+///
+pub struct OfflineRenderer {
+    stack: Prop<LayerStack>,
+    pattern_context: PatternContext<'static>,
+}
+
+impl OfflineRenderer {
+    pub fn new(pattern: &Pattern) -> Self {
+        Self::from_stack(pattern.stack(), pattern.pattern_context().borrow().clone())
+    }
+
+    pub fn from_stack(stack: &Prop<LayerStack>, pattern_context: PatternContext<'static>) -> Self {
+        let stack = stack.fork();
+        stack.write().reset();
+        Self {
+            stack,
+            pattern_context,
+        }
+    }
+
+    /// Replaces the pattern's seed, for renders that differ from the live pattern.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.pattern_context.set_seed(seed);
+        self
+    }
+
+    /// Returns the stack to its initial state, so the next render starts afresh.
+    pub fn reset(&mut self) {
+        self.stack.write().reset();
+    }
+
+    pub fn pattern_context(&self) -> &PatternContext<'static> {
+        &self.pattern_context
+    }
+
+    pub fn render(&mut self, t: f64) -> Result<Frame<ColorPixel>, String> {
+        with_buffer_pool(|| self.stack.write().next((), t, &self.pattern_context))
+            .map_err(|err| format!("Failed to evaluate stack: {:?}", err))
+    }
+
+    /// Renders one frame for each `t`, in order.
+    pub fn render_sequence(&mut self, ts: impl IntoIterator<Item=f64>) -> Result<Vec<Frame<ColorPixel>>, String> {
+        ts.into_iter()
+            .map(|t| self.render(t))
+            .collect()
+    }
+
+    /// Renders `num_frames` frames, starting at `start_t` and advancing `t` by `t_step` each frame.
+    pub fn render_frames(&mut self, start_t: f64, t_step: f64, num_frames: usize) -> Result<Vec<Frame<ColorPixel>>, String> {
+        self.render_sequence((0..num_frames).map(|i| start_t + i as f64 * t_step))
+    }
+}
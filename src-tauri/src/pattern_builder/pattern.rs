@@ -1,17 +1,20 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use rand::random;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use tauri::async_runtime::{JoinHandle, spawn};
-use tokio::sync::watch;
-use tokio::time::{interval, MissedTickBehavior};
+use crate::runtime::{JoinHandle, spawn};
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{interval, Interval, MissedTickBehavior};
 
 use crate::{fork_properties};
 use crate::pattern_builder::component::{RandId};
-use crate::pattern_builder::component::frame::{ColorPixel, Frame};
+use crate::pattern_builder::component::frame::{with_buffer_pool, ColorPixel, Frame};
 use crate::pattern_builder::component::layer::{DisplayPane, LayerView};
 use crate::pattern_builder::component::layer::layer_stack::LayerStack;
+use crate::pattern_builder::component::layer::stats::LayerStatsView;
 use crate::pattern_builder::component::property::{Prop, PropCore, PropView};
 use crate::pattern_builder::component::property::layer_stack::LayerStackPropCore;
 use crate::pattern_builder::component::property::num::NumPropCore;
@@ -19,114 +22,244 @@ use crate::pattern_builder::component::property::raw::RawPropCore;
 use crate::pattern_builder::component::property::PropertyInfo;
 use crate::pattern_builder::pattern_context::PatternContext;
 
+///
+/// A section of the timeline that playback repeats, for tuning part of a pattern.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoopRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl LoopRange {
+    pub fn new(start: f64, end: f64) -> Result<Self, String> {
+        Self { start, end }.validate()
+    }
+
+    fn validate(self) -> Result<Self, String> {
+        if !self.start.is_finite() || !self.end.is_finite() {
+            Err(format!("Invalid loop range {}..{}", self.start, self.end))
+        } else if self.end <= self.start {
+            Err(format!("Loop end ({}) must be after its start ({})", self.end, self.start))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Wraps `t` back into the range once it reaches the end. Times before the start are left alone,
+    /// so playback runs on into the loop.
+    pub fn wrap(&self, t: f64) -> f64 {
+        if t >= self.end {
+            self.start + (t - self.start) % (self.end - self.start)
+        } else {
+            t
+        }
+    }
+}
+
+enum PlaybackCommand {
+    Seek(f64),
+    Step(i64),
+    Render,
+}
+
+#[derive(Serialize)]
+pub struct PatternStats {
+    render_time_ms: Option<f64>,
+    stack: LayerStatsView,
+    /// Every layer in the pattern, slowest first.
+    layers: Vec<LayerStatsEntry>,
+}
+
+#[derive(Serialize)]
+pub struct LayerStatsEntry {
+    id: RandId,
+    name: Option<String>,
+    type_name: String,
+    stats: LayerStatsView,
+}
+
+impl PatternStats {
+    pub fn layers(&self) -> &Vec<LayerStatsEntry> {
+        &self.layers
+    }
+}
+
+impl LayerStatsEntry {
+    pub fn id(&self) -> RandId {
+        self.id
+    }
+
+    pub fn stats(&self) -> &LayerStatsView {
+        &self.stats
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlaybackView {
+    t: f64,
+    running: bool,
+    speed: f64,
+    fps: f64,
+    loop_range: Option<LoopRange>,
+    rendering: bool,
+    render_time_ms: Option<f64>,
+}
+
 struct PatternRunnerTask {
     layer: Prop<LayerStack>,
     pattern_context: watch::Receiver<PatternContext<'static>>,
-    update_sender: watch::Sender<Frame<ColorPixel>>,
+    update_sender: Arc<watch::Sender<Frame<ColorPixel>>>,
     running: Prop<bool>,
     speed: Prop<f64>,
+    fps: Prop<f64>,
     t: watch::Sender<f64>,
     last_instant: watch::Sender<Instant>,
+    loop_range: watch::Receiver<Option<LoopRange>>,
+    commands: mpsc::UnboundedReceiver<PlaybackCommand>,
+    render_time: watch::Sender<Option<Duration>>,
 }
 
 impl PatternRunnerTask {
-    async fn run(self, fps: f32) {
+    async fn run(mut self) {
         self.last_instant.send(Instant::now()).unwrap();
-        let frame_duration = Duration::from_secs(1).div_f32(fps);
-        let mut interval = interval(frame_duration);
-        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
-        // enum WaitResult {
-        //     Elapsed,
-        //     LayerChange,
-        //     NotRunning,
-        // }
+        let mut fps = *self.fps.read();
+        let mut interval = Self::frame_interval(fps);
         loop {
-            interval.tick().await;
-            while !*self.running.read() {
-                self.last_instant.send(Instant::now()).unwrap();
-                interval.tick().await;
+            select! {
+                _ = interval.tick() => {
+                    let now = Instant::now();
+                    if !*self.running.read() {
+                        self.last_instant.send(now).unwrap();
+                        continue;
+                    }
+                    let elapsed = now.duration_since(*self.last_instant.borrow()).as_secs_f64() * *self.speed.read();
+                    self.last_instant.send(now).unwrap();
+                    let t = *self.t.borrow() + elapsed;
+                    self.set_t(t, true);
+                }
+                Some(command) = self.commands.recv() => {
+                    self.last_instant.send(Instant::now()).unwrap();
+                    match command {
+                        PlaybackCommand::Seek(t) => self.set_t(t, false),
+                        PlaybackCommand::Step(frames) => {
+                            let t = *self.t.borrow() + frames as f64 * (1.0 / fps) * *self.speed.read();
+                            self.set_t(t, true);
+                        },
+                        PlaybackCommand::Render => {},
+                    }
+                }
+                Ok(()) = self.pattern_context.changed() => {
+                    if *self.running.read() {
+                        continue;
+                    }
+                }
+            }
+
+            if *self.fps.read() != fps {
+                fps = *self.fps.read();
+                interval = Self::frame_interval(fps);
+            }
+
+            if self.update_sender.receiver_count() > 0 {
+                self.render();
             }
-            // 'interval: loop {
-            //     let wait_result = select! {
-            //         _ = interval.tick() => WaitResult::Elapsed,
-            //         _ = self.layer.changed() => WaitResult::LayerChange,
-            //         _ = self.running.wait_for(|&r| !r) => WaitResult::NotRunning,
-            //     };
-            //     match wait_result {
-            //         WaitResult::Elapsed => break 'interval,
-            //         WaitResult::LayerChange => {
-            //             interval.reset();
-            //             break 'interval;
-            //         },
-            //         WaitResult::NotRunning => {
-            //             self.running.wait_for(|&r| r).await.unwrap();
-            //             interval.reset();
-            //             self.last_instant.send(Instant::now()).unwrap();
-            //         }
-            //     }
-            // }
-
-            let now = Instant::now();
-            self.t.send_modify(
-                |t|
-                    *t += now.duration_since(*self.last_instant.borrow()).as_secs_f64()
-                        * *self.speed.read()
-            );
-            self.last_instant.send(now).unwrap();
-
-            let ctx = self.pattern_context.borrow();
-            let pixel_data: Frame<ColorPixel> = self.layer.write().next((), *self.t.borrow(), &ctx)
-                .unwrap_or_else(|err| {
-                    eprintln!("Failed to evaluate stack: {:?}", err);
-                    Frame::<ColorPixel>::empty(ctx.num_pixels())
-                });
-
-            self.update_sender.send(pixel_data).unwrap();
         }
     }
+
+    fn frame_interval(fps: f64) -> Interval {
+        let mut interval = interval(Duration::from_secs_f64(1.0 / fps));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    }
+
+    fn render(&mut self) {
+        let start = Instant::now();
+        let ctx = self.pattern_context.borrow();
+        let pixel_data: Frame<ColorPixel> = with_buffer_pool(|| self.layer.write().next((), *self.t.borrow(), &ctx))
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to evaluate stack: {:?}", err);
+                Frame::<ColorPixel>::empty(ctx.num_pixels())
+            });
+        drop(ctx);
+
+        // Smooth the measurement, so one slow frame doesn't swamp the reading.
+        let elapsed = start.elapsed();
+        self.render_time.send_modify(|render_time| {
+            *render_time = Some(render_time.map_or(elapsed, |average| average.mul_f64(0.9) + elapsed.mul_f64(0.1)));
+        });
+        self.update_sender.send_replace(pixel_data);
+    }
+
+    ///
+    /// Moves playback to `t`, wrapping it into the loop range.
+    ///
+    /// Stateful layers build on the frames before them, so the stack is reset whenever time does
+    /// not carry on from the last frame: on seeks, on stepping backwards, and when the loop wraps.
+    ///
+    fn set_t(&mut self, t: f64, continuous: bool) {
+        let wrapped = self.loop_range.borrow().map_or(t, |loop_range| loop_range.wrap(t));
+        if !continuous || wrapped != t || wrapped < *self.t.borrow() {
+            self.layer.write().reset();
+        }
+        self.t.send_replace(wrapped);
+    }
 }
 
 pub struct Pattern {
     id: RandId,
     name: String,
     animation_runner_handle: JoinHandle<()>,
-    frame_receiver: watch::Receiver<Frame<ColorPixel>>,
-    fps: f32,
+    frame_sender: Arc<watch::Sender<Frame<ColorPixel>>>,
+    fps: Prop<f64>,
     t: watch::Receiver<f64>,
     last_instant: watch::Receiver<Instant>,
     stack: Prop<LayerStack>,
     pattern_context: watch::Receiver<PatternContext<'static>>,
     running: Prop<bool>,
     speed: Prop<f64>,
+    loop_range: watch::Sender<Option<LoopRange>>,
+    playback_commands: mpsc::UnboundedSender<PlaybackCommand>,
+    render_time: watch::Receiver<Option<Duration>>,
     property_view_map: HashMap<RandId, PropView>,
 }
 
 impl Pattern {
     pub fn new(name: &str, pattern_context: watch::Receiver<PatternContext<'static>>, fps: f32) -> Self {
-        let (update_sender, update_receiver) = watch::channel(Frame::empty(pattern_context.borrow().num_pixels()));
+        let update_sender = Arc::new(watch::Sender::new(Frame::empty(pattern_context.borrow().num_pixels())));
         let (t_send, t_recv) = watch::channel(0.0);
         let (last_instant_send, last_instant_recv) = watch::channel(Instant::now());
+        let (loop_range_send, loop_range_recv) = watch::channel(None);
+        let (command_send, command_recv) = mpsc::unbounded_channel();
+        let (render_time_send, render_time_recv) = watch::channel(None);
         let animation_runner = PatternRunnerTask {
             layer: LayerStackPropCore::new(LayerStack::new()).into_prop(PropertyInfo::unnamed().set_display_pane(DisplayPane::Tree)),
             pattern_context: pattern_context.clone(),
             update_sender,
             running: RawPropCore::new(true).into_prop(PropertyInfo::new("Running")),
             speed: NumPropCore::new_slider(1.0, 0.0..100.0, 0.05).into_prop(PropertyInfo::new("Speed")),
+            fps: NumPropCore::new_slider(fps as f64, 1.0..120.0, 1.0).into_prop(PropertyInfo::new("FPS")),
             t: t_send,
             last_instant: last_instant_send,
+            loop_range: loop_range_recv,
+            commands: command_recv,
+            render_time: render_time_send,
         };
         Pattern {
             id: random(),
             name: name.to_string(),
-            frame_receiver: update_receiver,
-            fps,
+            frame_sender: animation_runner.update_sender.clone(),
+            fps: animation_runner.fps.clone(),
             t: t_recv,
             last_instant: last_instant_recv,
             stack: animation_runner.layer.clone(),
             pattern_context,
             running: animation_runner.running.clone(),
             speed: animation_runner.speed.clone(),
-            animation_runner_handle: spawn(animation_runner.run(fps)),
+            loop_range: loop_range_send,
+            playback_commands: command_send,
+            render_time: render_time_recv,
+            animation_runner_handle: spawn(animation_runner.run()),
             property_view_map: HashMap::new(),
         }
     }
@@ -147,36 +280,156 @@ impl Pattern {
 
     pub fn try_update_prop(&mut self, prop_id: RandId, value: String) -> Result<(), String> {
         let property = self.property_view_map.get_mut(&prop_id).ok_or("Unknown property id")?;
-        property.try_update(value.as_str())
+        property.try_update(value.as_str())?;
+        self.render_if_paused()
+    }
+
+    /// Gives every seed property in the pattern a new random seed.
+    pub fn reseed(&mut self) {
+        for mut property in PatternView::new(self).generate_property_map().into_values() {
+            if property.prop_type_id() == "seed" {
+                property.try_update("\"random\"").unwrap();
+            }
+        }
+        let _ = self.render_if_paused();
     }
 
     pub fn stack(&self) -> &Prop<LayerStack> {
         &self.stack
     }
 
+    pub fn pattern_context(&self) -> &watch::Receiver<PatternContext<'static>> {
+        &self.pattern_context
+    }
+
     pub fn running(&self) -> &Prop<bool> {
         &self.running
     }
 
+    /// Subscribes to the pattern's frames. The pattern only renders while it has subscribers.
     pub fn get_frame_receiver(&self) -> watch::Receiver<Frame<ColorPixel>> {
-        self.frame_receiver.clone()
+        self.frame_sender.subscribe()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.frame_sender.receiver_count()
     }
 
     pub fn get_t(&self) -> f64 {
         if *self.running.read() {
-            *self.t.borrow()
+            let t = *self.t.borrow()
                 + Instant::now().duration_since(*self.last_instant.borrow()).as_secs_f64()
-                * *self.speed.read()
+                * *self.speed.read();
+            self.loop_range.borrow().map_or(t, |loop_range| loop_range.wrap(t))
         } else {
             *self.t.borrow()
         }
     }
 
+    pub fn set_running(&self, running: bool) {
+        *self.running.write() = running;
+    }
+
+    pub fn fps(&self) -> f64 {
+        *self.fps.read()
+    }
+
+    /// Changes the frame rate. The runner picks it up from the next frame.
+    pub fn set_fps(&self, fps: f64) -> Result<(), String> {
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(format!("Invalid frame rate {}", fps));
+        }
+        *self.fps.write() = fps;
+        Ok(())
+    }
+
+    /// The smoothed time taken to render a frame, once the pattern has rendered one.
+    pub fn render_time(&self) -> Option<Duration> {
+        *self.render_time.borrow()
+    }
+
+    /// Jumps playback to `t`. The next frame is rendered straight away, even while paused.
+    pub fn seek(&self, t: f64) -> Result<(), String> {
+        if !t.is_finite() {
+            return Err(format!("Cannot seek to {}", t));
+        }
+        self.send_playback_command(PlaybackCommand::Seek(t))
+    }
+
+    /// Moves a paused pattern on by a number of frames, or back if `frames` is negative.
+    pub fn step(&self, frames: i64) -> Result<(), String> {
+        if *self.running.read() {
+            return Err("Pause the pattern before stepping frames".to_string());
+        }
+        self.send_playback_command(PlaybackCommand::Step(frames))
+    }
+
+    /// Returns to the start of the loop range, or to `t = 0` without one.
+    pub fn reset_time(&self) -> Result<(), String> {
+        let start = self.loop_range.borrow().map_or(0.0, |loop_range| loop_range.start);
+        self.seek(start)
+    }
+
+    pub fn loop_range(&self) -> Option<LoopRange> {
+        *self.loop_range.borrow()
+    }
+
+    pub fn set_loop_range(&self, loop_range: Option<LoopRange>) -> Result<(), String> {
+        let loop_range = loop_range.map(LoopRange::validate).transpose()?;
+        self.loop_range.send_replace(loop_range);
+        Ok(())
+    }
+
+    pub fn playback_view(&self) -> PlaybackView {
+        PlaybackView {
+            t: self.get_t(),
+            running: *self.running.read(),
+            speed: *self.speed.read(),
+            fps: self.fps(),
+            loop_range: self.loop_range(),
+            rendering: self.subscriber_count() > 0,
+            render_time_ms: self.render_time().map(|render_time| render_time.as_secs_f64() * 1000.0),
+        }
+    }
+
+    /// Renders the current frame again, so edits show up while the pattern is paused.
+    fn render_if_paused(&self) -> Result<(), String> {
+        if *self.running.read() {
+            Ok(())
+        } else {
+            self.send_playback_command(PlaybackCommand::Render)
+        }
+    }
+
+    /// Per-layer evaluation times, for finding the layers that slow a pattern down.
+    pub fn stats(&self) -> PatternStats {
+        let mut layers: Vec<LayerStatsEntry> = PatternView::new(self).components.into_values()
+            .map(|layer_view| LayerStatsEntry {
+                id: layer_view.info().id(),
+                name: layer_view.info().name().read().clone(),
+                type_name: layer_view.type_info().name().clone(),
+                stats: layer_view.stats().clone(),
+            })
+            .collect();
+        layers.sort_by(|a, b| b.stats.average_ms.unwrap_or(0.0).total_cmp(&a.stats.average_ms.unwrap_or(0.0)));
+        PatternStats {
+            render_time_ms: self.render_time().map(|render_time| render_time.as_secs_f64() * 1000.0),
+            stack: self.stack.read().stats().view(),
+            layers,
+        }
+    }
+
+    fn send_playback_command(&self, command: PlaybackCommand) -> Result<(), String> {
+        self.playback_commands.send(command)
+            .map_err(|_| format!("Pattern {} is no longer running", self.id))
+    }
+
     fn detach(&mut self) {
         fork_properties!(
             self.stack,
             self.speed,
             self.running,
+            self.fps,
         );
     }
 }
@@ -189,30 +442,40 @@ impl Drop for Pattern {
 
 impl Clone for Pattern {
     fn clone(&self) -> Self {
-        let (update_send, update_recv) = watch::channel(self.frame_receiver.borrow().clone());
+        let update_sender = Arc::new(watch::Sender::new(self.frame_sender.borrow().clone()));
         let (t_send, t_recv) = watch::channel(self.get_t());
         let (last_instant_send, last_instant_recv) = watch::channel(Instant::now());
+        let (loop_range_send, loop_range_recv) = watch::channel(*self.loop_range.borrow());
+        let (command_send, command_recv) = mpsc::unbounded_channel();
+        let (render_time_send, render_time_recv) = watch::channel(None);
         let animation_runner = PatternRunnerTask {
             layer: self.stack.fork(),
             pattern_context: self.pattern_context.clone(),
-            update_sender: update_send,
+            update_sender,
             running: self.running.clone(),
             speed: self.speed.clone(),
+            fps: self.fps.clone(),
             t: t_send,
             last_instant: last_instant_send,
+            loop_range: loop_range_recv,
+            commands: command_recv,
+            render_time: render_time_send,
         };
         Pattern {
             id: random(),
             name: self.name.clone(),
-            frame_receiver: update_recv,
-            fps: self.fps,
+            frame_sender: animation_runner.update_sender.clone(),
+            fps: animation_runner.fps.clone(),
             t: t_recv,
             last_instant: last_instant_recv,
             stack: animation_runner.layer.clone(),
             pattern_context: self.pattern_context.clone(),
             running: animation_runner.running.clone(),
             speed: animation_runner.speed.clone(),
-            animation_runner_handle: spawn(animation_runner.run(self.fps)),
+            loop_range: loop_range_send,
+            playback_commands: command_send,
+            render_time: render_time_recv,
+            animation_runner_handle: spawn(animation_runner.run()),
             property_view_map: HashMap::new(),
         }
     }
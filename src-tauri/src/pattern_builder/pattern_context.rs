@@ -1,15 +1,80 @@
+use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::Arc;
+use nalgebra_glm::{DVec2, DVec3};
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use crate::pattern_builder::component::frame::{Frame, Pixel};
 use crate::pattern_builder::component::layer::io_type::DynTypeMapper;
-use crate::pattern_builder::pattern_context::position_map::PositionMap;
+use crate::pattern_builder::pattern_context::neighbors::{NeighborGraph, Topology};
+use crate::pattern_builder::pattern_context::position_map::{Bounds, PositionMap};
+use crate::pattern_builder::pattern_context::zone::{PixelSelection, Zones};
 
+pub mod coordinates;
+pub mod neighbors;
 pub mod position_map;
+pub mod zone;
+
+/// Below this many pixels, splitting work across threads costs more than it saves.
+const PARALLEL_MIN_PIXELS: usize = 1024;
+/// The number of pixels each thread takes at a time.
+const PARALLEL_CHUNK_PIXELS: usize = 256;
+/// The most selections of one layout whose neighbour graphs are kept, so a slider dragged through
+/// many slice sizes doesn't pile them up.
+const MAX_CACHED_SELECTIONS: usize = 16;
+
+/// A distorted copy of a layout's positions, and the neighbour graphs built for it.
+type DistortedLayout = (Vec<Option<DVec3>>, Arc<NeighborCache>);
+
+///
+/// Neighbour graphs for one layout, and for the layouts of contexts derived from it. Derived
+/// contexts are made afresh every frame, so their graphs are kept here rather than with them.
+///
+#[derive(Default)]
+struct NeighborCache {
+    graphs: Mutex<HashMap<Topology, Arc<NeighborGraph>>>,
+    /// Keyed on which of this layout's pixels were selected.
+    selections: Mutex<HashMap<PixelSelection, Arc<NeighborCache>>>,
+    /// The last distorted copy of this layout, reused while it's distorted the same way.
+    distorted: Mutex<Option<DistortedLayout>>,
+}
+
+impl NeighborCache {
+    fn selection(&self, selection: PixelSelection) -> Arc<NeighborCache> {
+        let mut selections = self.selections.lock();
+        if selections.len() >= MAX_CACHED_SELECTIONS && !selections.contains_key(&selection) {
+            selections.clear();
+        }
+        selections.entry(selection).or_default().clone()
+    }
+
+    fn distorted(&self, positions: &[Option<DVec3>]) -> Arc<NeighborCache> {
+        let mut distorted = self.distorted.lock();
+        match &*distorted {
+            Some((cached_positions, cache)) if cached_positions == positions => cache.clone(),
+            _ => {
+                let cache = Arc::new(NeighborCache::default());
+                *distorted = Some((positions.to_vec(), cache.clone()));
+                cache
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct PatternContext<'a> {
     num_pixels: usize,
     position_map: PositionMap<'a>,
+    /// Kept from the whole layout when slicing, so positions mean the same in every slice.
+    bounds: Bounds,
+    /// Zones address pixels on the whole strip, so only the top-level context has them.
+    zones: Arc<Zones>,
+    /// Built as filters ask for them, and shared with clones and derived contexts until the layout changes.
+    neighbor_graphs: Arc<NeighborCache>,
     type_mapper: Arc<DynTypeMapper>,
+    seed: u64,
 }
 
 impl PatternContext<'static> {
@@ -17,34 +82,178 @@ impl PatternContext<'static> {
 
 impl<'a> PatternContext<'a> {
     pub fn new(num_pixels: usize, type_mapper: Arc<DynTypeMapper>) -> Self {
+        let position_map = PositionMap::new_linear(num_pixels);
         Self {
             num_pixels,
-            position_map: PositionMap::new_linear(num_pixels),
+            bounds: position_map.bounds(),
+            position_map,
+            zones: Arc::default(),
+            neighbor_graphs: Arc::default(),
             type_mapper,
+            seed: 0,
         }
     }
     pub fn slice<'b: 'a>(&'b self, mut range: Range<usize>) -> PatternContext<'b> {
         range.end = usize::min(range.end, self.num_pixels);
         Self {
             num_pixels: range.end - range.start,
+            neighbor_graphs: self.neighbor_graphs.selection(PixelSelection::Range { start: range.start, end: range.end }),
             position_map: self.position_map.slice(range),
+            bounds: self.bounds,
+            zones: Arc::default(),
             type_mapper: self.type_mapper.clone(),
+            seed: self.seed,
+        }
+    }
+    ///
+    /// A context for just the selected pixels, in the selection's order. Pixels beyond the end of
+    /// the strip are left out.
+    ///
+    pub fn select<'b: 'a>(&'b self, selection: &PixelSelection) -> PatternContext<'b> {
+        match selection {
+            PixelSelection::Range { start, end } => {
+                let start = usize::min(*start, self.num_pixels);
+                self.slice(start..usize::max(start, *end))
+            }
+            PixelSelection::Pixels(_) => {
+                let indices = selection.indices(self.num_pixels);
+                Self {
+                    num_pixels: indices.len(),
+                    position_map: PositionMap::Vec(indices.iter().map(|index| self.position_map.pos(*index)).collect()),
+                    bounds: self.bounds,
+                    zones: Arc::default(),
+                    neighbor_graphs: self.neighbor_graphs.selection(PixelSelection::Pixels(indices)),
+                    type_mapper: self.type_mapper.clone(),
+                    seed: self.seed,
+                }
+            }
+        }
+    }
+    ///
+    /// A context with every position moved by `f`. The bounds stay those of the original layout,
+    /// so centered positions are measured the same way before and after.
+    ///
+    /// Set `keeps_neighbors` when `f` scales every distance by the same amount, as moving, turning,
+    /// mirroring and even scaling do. The nearest pixels are then the same, so neighbour graphs are
+    /// shared with this context rather than built for each new set of positions.
+    ///
+    pub fn transform_positions(&self, f: impl Fn(DVec3) -> DVec3, keeps_neighbors: bool) -> PatternContext<'a> {
+        let positions: Vec<_> = self.position_map.iter().map(|pos| pos.map(&f)).collect();
+        let neighbor_graphs = if keeps_neighbors {
+            self.neighbor_graphs.clone()
+        } else {
+            self.neighbor_graphs.distorted(&positions)
+        };
+        Self {
+            num_pixels: self.num_pixels,
+            position_map: PositionMap::Vec(positions),
+            bounds: self.bounds,
+            zones: self.zones.clone(),
+            neighbor_graphs,
+            type_mapper: self.type_mapper.clone(),
+            seed: self.seed,
         }
     }
     pub fn set_num_pixels(&mut self, num_pixels: usize) {
         self.num_pixels = num_pixels;
+        self.neighbor_graphs = Arc::default();
     }
     pub fn num_pixels(&self) -> usize {
         self.num_pixels
     }
     pub fn set_position_map(&mut self, map: PositionMap<'a>) {
+        self.bounds = map.bounds();
         self.position_map = map;
+        self.neighbor_graphs = Arc::default();
     }
     pub fn position_map(&self) -> &PositionMap {
         &self.position_map
     }
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+    pub fn set_zones(&mut self, zones: Zones) {
+        self.zones = Arc::new(zones);
+    }
+    pub fn zones(&self) -> &Zones {
+        &self.zones
+    }
+    pub fn zone(&self, name: &str) -> Option<&PixelSelection> {
+        self.zones.get(name)
+    }
+
+    /// The pixel's position in a -1 to 1 cube around the layout. See [`Bounds::centered`].
+    pub fn centered_pos(&self, pixel_index: usize) -> Option<DVec3> {
+        self.position_map.pos(pixel_index).map(|pos| self.bounds.centered(pos))
+    }
+    /// The pixel's position in a 0 to 1 cube around the layout. See [`Bounds::normalized`].
+    pub fn normalized_pos(&self, pixel_index: usize) -> Option<DVec3> {
+        self.position_map.pos(pixel_index).map(|pos| self.bounds.normalized(pos))
+    }
+    /// See [`coordinates::polar`].
+    pub fn polar_pos(&self, pixel_index: usize) -> Option<DVec2> {
+        self.centered_pos(pixel_index).map(coordinates::polar)
+    }
+    /// See [`coordinates::cylindrical`].
+    pub fn cylindrical_pos(&self, pixel_index: usize) -> Option<DVec3> {
+        self.centered_pos(pixel_index).map(coordinates::cylindrical)
+    }
+    /// See [`coordinates::spherical`].
+    pub fn spherical_pos(&self, pixel_index: usize) -> Option<DVec3> {
+        self.centered_pos(pixel_index).map(coordinates::spherical)
+    }
+    /// The neighbours of every pixel, built the first time each topology is asked for.
+    pub fn neighbors(&self, topology: Topology) -> Arc<NeighborGraph> {
+        let mut graphs = self.neighbor_graphs.graphs.lock();
+        graphs.entry(topology)
+            .or_insert_with(|| Arc::new(NeighborGraph::new(topology, self.num_pixels, &self.position_map)))
+            .clone()
+    }
     pub fn type_mapper(&self) -> &DynTypeMapper {
         &self.type_mapper
     }
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Combines the pattern's seed with a layer's own, so each layer gets a distinct but repeatable seed.
+    pub fn derive_seed(&self, layer_seed: u64) -> u64 {
+        (self.seed ^ layer_seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)).rotate_left(31)
+    }
+    pub fn seeded_rng(&self, layer_seed: u64) -> StdRng {
+        StdRng::seed_from_u64(self.derive_seed(layer_seed))
+    }
+
+    ///
+    /// Builds a frame by calling `f` with each pixel's index and position.
+    ///
+    /// Large layouts are split into chunks that run in parallel, so `f` must not depend on the
+    /// order pixels are visited in.
+    ///
+    pub fn map_pixels<P, F>(&self, f: F) -> Frame<P> where P: Pixel, F: Fn(usize, Option<DVec3>) -> P + Send + Sync {
+        let mut frame = Frame::with_capacity(self.num_pixels);
+        self.fill_pixels(&mut frame, f);
+        frame
+    }
+
+    /// Like [`map_pixels`](Self::map_pixels), but overwrites an existing frame, resizing it to fit.
+    pub fn fill_pixels<P, F>(&self, frame: &mut Frame<P>, f: F) where P: Pixel, F: Fn(usize, Option<DVec3>) -> P + Send + Sync {
+        frame.resize_with_empty(self.num_pixels);
+        let fill_chunk = |chunk_start: usize, chunk: &mut [P]| {
+            for (offset, pixel) in chunk.iter_mut().enumerate() {
+                let index = chunk_start + offset;
+                *pixel = f(index, self.position_map.pos(index));
+            }
+        };
+        if self.num_pixels >= PARALLEL_MIN_PIXELS {
+            frame.par_chunks_mut(PARALLEL_CHUNK_PIXELS)
+                .enumerate()
+                .for_each(|(chunk_index, chunk)| fill_chunk(chunk_index * PARALLEL_CHUNK_PIXELS, chunk));
+        } else {
+            fill_chunk(0, frame);
+        }
+    }
 }
 
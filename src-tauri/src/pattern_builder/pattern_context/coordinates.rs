@@ -0,0 +1,28 @@
+//!
+//! Conversions from centered positions, as given by [`Bounds::centered`], to other coordinate
+//! systems. Angles are in radians from -π to π.
+//!
+//! [`Bounds::centered`]: crate::pattern_builder::pattern_context::position_map::Bounds::centered
+//!
+
+use nalgebra_glm::{DVec2, DVec3};
+
+/// `(radius, angle)` in the XY plane, with the angle measured from the X axis.
+pub fn polar(pos: DVec3) -> DVec2 {
+    DVec2::new(pos.xy().magnitude(), pos.y.atan2(pos.x))
+}
+
+/// `(radius, angle, height)` around the vertical Y axis, with the angle measured from the X axis.
+pub fn cylindrical(pos: DVec3) -> DVec3 {
+    DVec3::new(pos.xz().magnitude(), pos.z.atan2(pos.x), pos.y)
+}
+
+///
+/// `(radius, azimuth, elevation)`, with the azimuth around the vertical Y axis as in
+/// [`cylindrical`], and the elevation from -π/2 straight down to π/2 straight up.
+///
+pub fn spherical(pos: DVec3) -> DVec3 {
+    let radius = pos.magnitude();
+    let elevation = if radius > 0.0 { (pos.y / radius).asin() } else { 0.0 };
+    DVec3::new(radius, pos.z.atan2(pos.x), elevation)
+}
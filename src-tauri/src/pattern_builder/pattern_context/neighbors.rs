@@ -0,0 +1,103 @@
+//!
+//! Which pixels count as next to each other, for filters that look beyond a single pixel. Building
+//! a graph can be slow for big layouts, so [`PatternContext::neighbors`] builds each one once and
+//! keeps it until the layout changes.
+//!
+//! [`PatternContext::neighbors`]: crate::pattern_builder::pattern_context::PatternContext::neighbors
+//!
+
+use std::cmp::Ordering;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+/// How to decide which pixels are neighbours.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// The pixels either side on the strip.
+    Strip,
+    /// The given number of pixels closest in the layout. Pixels without a position have none.
+    Nearest(usize),
+}
+
+impl Topology {
+    /// `Strip` for 0, otherwise that many nearest pixels, as set on a filter's "Neighbors" slider.
+    pub fn from_count(count: f64) -> Self {
+        match count.round() {
+            count if count >= 1.0 => Topology::Nearest(count as usize),
+            _ => Topology::Strip,
+        }
+    }
+
+    /// The inverse of [`from_count`](Self::from_count).
+    pub fn count(&self) -> f64 {
+        match self {
+            Topology::Strip => 0.0,
+            Topology::Nearest(count) => *count as f64,
+        }
+    }
+}
+
+/// The neighbours of every pixel, by index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NeighborGraph {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl NeighborGraph {
+    pub fn new(topology: Topology, num_pixels: usize, position_map: &PositionMap) -> Self {
+        match topology {
+            Topology::Strip => Self::strip(num_pixels),
+            Topology::Nearest(count) => Self::nearest(num_pixels, position_map, count),
+        }
+    }
+
+    pub fn strip(num_pixels: usize) -> Self {
+        let neighbors = (0..num_pixels)
+            .map(|index| [index.checked_sub(1), Some(index + 1).filter(|next| *next < num_pixels)]
+                .into_iter()
+                .flatten()
+                .collect())
+            .collect();
+        Self { neighbors }
+    }
+
+    /// Each pixel's `count` closest pixels, nearest first.
+    pub fn nearest(num_pixels: usize, position_map: &PositionMap, count: usize) -> Self {
+        let positions: Vec<_> = (0..num_pixels)
+            .filter_map(|index| position_map.pos(index).map(|pos| (index, pos)))
+            .collect();
+        let neighbors = (0..num_pixels).into_par_iter()
+            .map(|index| {
+                let Some(pos) = position_map.pos(index) else {
+                    return vec![];
+                };
+                let mut others: Vec<_> = positions.iter()
+                    .filter(|(other, _)| *other != index)
+                    .map(|(other, other_pos)| (*other, (other_pos - pos).norm_squared()))
+                    .collect();
+                let by_distance = |a: &(usize, f64), b: &(usize, f64)| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal);
+                if others.len() > count {
+                    others.select_nth_unstable_by(count, by_distance);
+                    others.truncate(count);
+                }
+                others.sort_by(by_distance);
+                others.into_iter().map(|(other, _)| other).collect()
+            })
+            .collect();
+        Self { neighbors }
+    }
+
+    /// The neighbours of `pixel_index`, or none if it's beyond the end of the graph.
+    pub fn neighbors(&self, pixel_index: usize) -> &[usize] {
+        self.neighbors.get(pixel_index).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn len(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+}
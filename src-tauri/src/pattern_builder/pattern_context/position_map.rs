@@ -6,6 +6,52 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::{SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 
+pub mod generator;
+pub mod import;
+
+///
+/// The box a layout fits in, used to describe positions independently of the layout's units.
+///
+/// A layout with no positions has an empty box at the origin.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Bounds {
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+impl Bounds {
+    pub fn center(&self) -> DVec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> DVec3 {
+        self.max - self.min
+    }
+
+    ///
+    /// Half the longest side of the box, i.e. how far the layout reaches from its center. A layout
+    /// with no extent, like a single pixel, has a scale of 1 so positions can always be divided by it.
+    ///
+    pub fn scale(&self) -> f64 {
+        let scale = self.size().max() / 2.0;
+        if scale > 0.0 { scale } else { 1.0 }
+    }
+
+    ///
+    /// Moves `pos` into a -1 to 1 cube around the layout's center. Every axis is scaled the same,
+    /// so shapes keep their proportions and only the longest side spans the full range.
+    ///
+    pub fn centered(&self, pos: DVec3) -> DVec3 {
+        (pos - self.center()) / self.scale()
+    }
+
+    /// Like [`centered`](Self::centered), but in a 0 to 1 cube.
+    pub fn normalized(&self, pos: DVec3) -> DVec3 {
+        self.centered(pos).add_scalar(1.0) / 2.0
+    }
+}
+
 pub enum PositionMap<'a> {
     Vec(Vec<Option<DVec3>>),
     Slice(&'a[Option<DVec3>]),
@@ -52,6 +98,19 @@ impl<'a> PositionMap<'a> {
         }.unwrap_or(&None).clone()
     }
 
+    /// Moves a pixel, or removes its position with `None`. The map grows to fit if needed.
+    pub fn set_pos(&mut self, pixel_index: usize, pos: Option<DVec3>) {
+        if let PositionMap::Slice(s) = self {
+            *self = PositionMap::Vec(s.to_vec());
+        }
+        if let PositionMap::Vec(v) = self {
+            if pixel_index >= v.len() {
+                v.resize(pixel_index + 1, None);
+            }
+            v[pixel_index] = pos;
+        }
+    }
+
     pub fn len(&self) -> usize {
         match self {
             PositionMap::Vec(v) => v.len(),
@@ -65,6 +124,16 @@ impl<'a> PositionMap<'a> {
             PositionMap::Slice(s) => s.iter(),
         }
     }
+
+    /// The smallest box containing every position in the map.
+    pub fn bounds(&self) -> Bounds {
+        self.iter().flatten()
+            .fold(None, |bounds: Option<Bounds>, pos| match bounds {
+                None => Some(Bounds { min: *pos, max: *pos }),
+                Some(Bounds { min, max }) => Some(Bounds { min: min.inf(pos), max: max.sup(pos) }),
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl<'a> IntoIterator for &'a PositionMap<'a> {
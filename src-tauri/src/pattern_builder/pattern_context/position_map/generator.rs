@@ -0,0 +1,123 @@
+use std::f64::consts::TAU;
+use nalgebra_glm::DVec3;
+use serde::{Deserialize, Serialize};
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+///
+/// Procedural position maps for common physical layouts.
+///
+/// Flat layouts lie in the XY plane. Layouts with height, like [`LayoutGenerator::Helix`], rise
+/// along Y.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LayoutGenerator {
+    /// A grid of pixels wired row by row, or column by column. Serpentine wiring reverses every
+    /// other row, as most LED matrices do.
+    Matrix {
+        width: usize,
+        height: usize,
+        spacing: f64,
+        serpentine: bool,
+        #[serde(default)]
+        column_major: bool,
+    },
+    /// Pixels evenly spaced around a circle.
+    Ring {
+        num_pixels: usize,
+        radius: f64,
+    },
+    /// A flat spiral winding outwards from `inner_radius` to `outer_radius`.
+    Spiral {
+        num_pixels: usize,
+        turns: f64,
+        inner_radius: f64,
+        outer_radius: f64,
+    },
+    /// A strip wound upwards around a cone, like lights on a tree.
+    Helix {
+        num_pixels: usize,
+        turns: f64,
+        height: f64,
+        bottom_radius: f64,
+        top_radius: f64,
+    },
+    /// Straight runs between consecutive points, with `segment_pixels[i]` pixels between
+    /// `points[i]` and `points[i + 1]`.
+    Polyline {
+        points: Vec<[f64; 3]>,
+        segment_pixels: Vec<usize>,
+    },
+}
+
+impl LayoutGenerator {
+    pub fn generate(&self) -> Result<PositionMap<'static>, String> {
+        let positions: Vec<DVec3> = match self {
+            LayoutGenerator::Matrix { width, height, spacing, serpentine, column_major } => {
+                let line_len = if *column_major { *height } else { *width };
+                (0..width * height)
+                    .map(|i| {
+                        let line = i / line_len;
+                        let mut along = i % line_len;
+                        if *serpentine && line % 2 == 1 {
+                            along = line_len - 1 - along;
+                        }
+                        let (x, y) = if *column_major { (line, along) } else { (along, line) };
+                        DVec3::new(x as f64 * spacing, y as f64 * spacing, 0.0)
+                    })
+                    .collect()
+            },
+            LayoutGenerator::Ring { num_pixels, radius } => {
+                (0..*num_pixels)
+                    .map(|i| {
+                        let angle = TAU * i as f64 / *num_pixels as f64;
+                        DVec3::new(radius * angle.cos(), radius * angle.sin(), 0.0)
+                    })
+                    .collect()
+            },
+            LayoutGenerator::Spiral { num_pixels, turns, inner_radius, outer_radius } => {
+                fractions(*num_pixels)
+                    .map(|f| {
+                        let angle = TAU * turns * f;
+                        let radius = inner_radius + (outer_radius - inner_radius) * f;
+                        DVec3::new(radius * angle.cos(), radius * angle.sin(), 0.0)
+                    })
+                    .collect()
+            },
+            LayoutGenerator::Helix { num_pixels, turns, height, bottom_radius, top_radius } => {
+                fractions(*num_pixels)
+                    .map(|f| {
+                        let angle = TAU * turns * f;
+                        let radius = bottom_radius + (top_radius - bottom_radius) * f;
+                        DVec3::new(radius * angle.cos(), height * f, radius * angle.sin())
+                    })
+                    .collect()
+            },
+            LayoutGenerator::Polyline { points, segment_pixels } => {
+                if points.len() != segment_pixels.len() + 1 {
+                    return Err(format!("A polyline with {} points has {} segments, but {} pixel counts were given.", points.len(), points.len().saturating_sub(1), segment_pixels.len()));
+                }
+                points.windows(2)
+                    .zip(segment_pixels)
+                    .flat_map(|(ends, num_pixels)| {
+                        let (start, end) = (DVec3::from(ends[0]), DVec3::from(ends[1]));
+                        // Pixels sit in the middle of equal steps, so corners shared by two segments aren't doubled up.
+                        (0..*num_pixels).map(move |i| start + (end - start) * ((i as f64 + 0.5) / *num_pixels as f64))
+                    })
+                    .collect()
+            },
+        };
+        if positions.is_empty() {
+            return Err(format!("The layout has no pixels."));
+        }
+        if positions.iter().any(|pos| !pos.iter().all(|c| c.is_finite())) {
+            return Err(format!("The layout has pixels with invalid positions."));
+        }
+        Ok(PositionMap::new(positions.into_iter().map(Some).collect()))
+    }
+}
+
+/// How far along the layout each of `num_pixels` pixels is, from 0 at the first to 1 at the last.
+fn fractions(num_pixels: usize) -> impl Iterator<Item=f64> {
+    let last = usize::max(num_pixels, 2) - 1;
+    (0..num_pixels).map(move |i| i as f64 / last as f64)
+}
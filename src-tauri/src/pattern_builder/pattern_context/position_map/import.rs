@@ -0,0 +1,314 @@
+//!
+//! Reading layouts made by other tools into a [`PositionMap`].
+//!
+//! Every format is read as-is, in whatever units and orientation the tool used. [`Normalization`]
+//! then moves and scales the result so it lines up with layouts made here.
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use nalgebra_glm::DVec3;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::pattern_builder::pattern_context::position_map::PositionMap;
+
+/// The highest pixel index a layout can place. Strips can't have more pixels than this, and the
+/// bound stops a stray index from making a huge map.
+const MAX_PIXEL_INDEX: usize = u16::MAX as usize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum ImportFormat {
+    ///
+    /// A JSON array with one entry per pixel: `[x, y, z]`, `[x, y]`, `{"x": .., "y": .., "z": ..}`
+    /// or `null`. This covers maps saved here and the coordinate lists written by most LED mapping
+    /// tools. A WLED `ledmap.json`, an object with a `map` of pixel indices laid out on a grid of
+    /// `width` columns, is also accepted.
+    ///
+    Json,
+    ///
+    /// One pixel per row, as `index,x,y,z`, `x,y,z` or `x,y`. A header row naming the columns is
+    /// optional, and rows can be in any order when there's an index column.
+    ///
+    Csv,
+    /// An xLights custom model (`.xmodel`), with one cell of its grid per light.
+    XLights,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(ImportFormat::Json),
+            "csv" | "txt" => Some(ImportFormat::Csv),
+            "xmodel" => Some(ImportFormat::XLights),
+            _ => None,
+        }
+    }
+
+    pub fn parse(&self, contents: &str) -> Result<PositionMap<'static>, String> {
+        match self {
+            ImportFormat::Json => parse_json(contents),
+            ImportFormat::Csv => parse_csv(contents),
+            ImportFormat::XLights => parse_xlights(contents),
+        }
+    }
+}
+
+///
+/// Tidies up an imported layout. Steps apply in order: the layout is centred on the origin, scaled
+/// so its longest side is `fit_size`, then scaled by `unit_scale`. The default changes nothing.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Normalization {
+    /// Moves the centre of the layout's bounding box to the origin.
+    pub center: bool,
+    pub fit_size: Option<f64>,
+    /// Converts from the source's units, e.g. `0.001` for millimetres to metres.
+    pub unit_scale: f64,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self { center: false, fit_size: None, unit_scale: 1.0 }
+    }
+}
+
+impl Normalization {
+    pub fn apply(&self, position_map: &PositionMap) -> PositionMap<'static> {
+        let bounds = position_map.bounds();
+        let offset = if self.center { bounds.center() } else { DVec3::zeros() };
+        let longest_side = bounds.size().max();
+        let fit_scale = match self.fit_size {
+            Some(size) if longest_side > 0.0 => size / longest_side,
+            _ => 1.0,
+        };
+        let scale = fit_scale * self.unit_scale;
+        PositionMap::new(
+            position_map.iter()
+                .map(|pos| pos.map(|pos| (pos - offset) * scale))
+                .collect()
+        )
+    }
+}
+
+///
+/// Reads a layout from `path`, working out the format from its extension if `format` is `None`.
+/// Files with an unknown extension are read as JSON.
+///
+pub fn import_position_map(path: impl AsRef<Path>, format: Option<ImportFormat>, normalization: &Normalization) -> Result<PositionMap<'static>, String> {
+    let path = path.as_ref();
+    let format = format.or_else(|| ImportFormat::from_path(path)).unwrap_or(ImportFormat::Json);
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let position_map = format.parse(&contents).map_err(|e| format!("Cannot import {}: {}", path.display(), e))?;
+    Ok(normalization.apply(&position_map))
+}
+
+fn check_index(index: usize) -> Result<usize, String> {
+    if index > MAX_PIXEL_INDEX {
+        return Err(format!("Pixel index {} is too large, the most is {}.", index, MAX_PIXEL_INDEX));
+    }
+    Ok(index)
+}
+
+/// Builds a map from `(pixel index, position)` pairs, averaging positions given more than once.
+fn from_indexed(positions: impl IntoIterator<Item=(usize, DVec3)>) -> Result<PositionMap<'static>, String> {
+    let mut sums: BTreeMap<usize, (DVec3, f64)> = BTreeMap::new();
+    for (index, pos) in positions {
+        let (sum, count) = sums.entry(check_index(index)?).or_insert((DVec3::zeros(), 0.0));
+        *sum += pos;
+        *count += 1.0;
+    }
+    let mut position_map = PositionMap::new(vec![]);
+    for (index, (sum, count)) in sums {
+        position_map.set_pos(index, Some(sum / count));
+    }
+    Ok(position_map)
+}
+
+fn parse_json(contents: &str) -> Result<PositionMap<'static>, String> {
+    let value: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    match value {
+        Value::Array(entries) => entries.iter()
+            .enumerate()
+            .map(|(i, entry)| json_position(entry).map_err(|e| format!("Pixel {}: {}", i, e)))
+            .collect::<Result<Vec<_>, String>>()
+            .map(PositionMap::new),
+        Value::Object(object) if object.contains_key("map") => parse_ledmap(&object),
+        _ => Err("Expected an array of positions or a ledmap object.".to_string()),
+    }
+}
+
+fn json_position(entry: &Value) -> Result<Option<DVec3>, String> {
+    let coord = |value: Option<&Value>| -> Result<f64, String> {
+        match value {
+            None | Some(Value::Null) => Ok(0.0),
+            Some(value) => value.as_f64().ok_or_else(|| format!("{} is not a number.", value)),
+        }
+    };
+    match entry {
+        Value::Null => Ok(None),
+        Value::Array(coords) if (2..=3).contains(&coords.len()) => {
+            Ok(Some(DVec3::new(coord(coords.first())?, coord(coords.get(1))?, coord(coords.get(2))?)))
+        }
+        Value::Object(coords) if coords.contains_key("x") && coords.contains_key("y") => {
+            Ok(Some(DVec3::new(coord(coords.get("x"))?, coord(coords.get("y"))?, coord(coords.get("z"))?)))
+        }
+        _ => Err(format!("Expected [x, y, z], [x, y], {{\"x\", \"y\", \"z\"}} or null, got {}.", entry)),
+    }
+}
+
+/// Cells of a WLED ledmap hold the index of the pixel there, or -1 where there isn't one.
+fn parse_ledmap(object: &serde_json::Map<String, Value>) -> Result<PositionMap<'static>, String> {
+    let cells = object.get("map").and_then(Value::as_array).ok_or("The ledmap's map is not an array.")?;
+    let width = match object.get("width") {
+        Some(width) => width.as_u64().filter(|width| *width > 0).ok_or("The ledmap's width is not a positive integer.")? as usize,
+        None => cells.len().max(1),
+    };
+    let mut positions = vec![];
+    for (cell, value) in cells.iter().enumerate() {
+        let index = value.as_i64().ok_or_else(|| format!("Ledmap cell {} is not an integer.", cell))?;
+        if index >= 0 {
+            let (column, row) = (cell % width, cell / width);
+            positions.push((index as usize, DVec3::new(column as f64, -(row as f64), 0.0)));
+        }
+    }
+    from_indexed(positions)
+}
+
+fn parse_csv(contents: &str) -> Result<PositionMap<'static>, String> {
+    let mut rows = contents.lines()
+        .enumerate()
+        .map(|(line, row)| (line + 1, row.trim()))
+        .filter(|(_, row)| !row.is_empty() && !row.starts_with('#'))
+        .map(|(line, row)| (line, row.split([',', ';', '\t']).map(str::trim).collect::<Vec<_>>()))
+        .peekable();
+
+    let Some((_, first_row)) = rows.peek() else {
+        return Ok(PositionMap::new(vec![]));
+    };
+    let is_header = first_row.iter().any(|field| !field.is_empty() && field.parse::<f64>().is_err());
+    let columns = if is_header {
+        let find = |names: &[&str]| first_row.iter().position(|field| names.contains(&field.to_ascii_lowercase().as_str()));
+        let columns = CsvColumns { index: find(&["index", "i", "led", "pixel"]), x: find(&["x"]), y: find(&["y"]), z: find(&["z"]) };
+        if columns.x.is_none() || columns.y.is_none() {
+            return Err("The header needs x and y columns.".to_string());
+        }
+        rows.next();
+        columns
+    } else {
+        match first_row.len() {
+            2 => CsvColumns { index: None, x: Some(0), y: Some(1), z: None },
+            3 => CsvColumns { index: None, x: Some(0), y: Some(1), z: Some(2) },
+            n if n >= 4 => CsvColumns { index: Some(0), x: Some(1), y: Some(2), z: Some(3) },
+            _ => return Err("Expected index,x,y,z, x,y,z or x,y columns.".to_string()),
+        }
+    };
+
+    let mut positions = vec![];
+    for (order, (line, row)) in rows.enumerate() {
+        let field = |column: Option<usize>| column.and_then(|column| row.get(column)).copied().unwrap_or("");
+        let number = |column: Option<usize>| -> Result<Option<f64>, String> {
+            let text = field(column);
+            if text.is_empty() {
+                return Ok(None);
+            }
+            text.parse().map(Some).map_err(|_| format!("Line {}: {} is not a number.", line, text))
+        };
+        let index = match columns.index {
+            Some(_) => field(columns.index).parse::<usize>().map_err(|_| format!("Line {}: {} is not a pixel index.", line, field(columns.index)))?,
+            None => order,
+        };
+        let index = check_index(index).map_err(|e| format!("Line {}: {}", line, e))?;
+        // A row with no coordinates is a pixel without a position.
+        let pos = match (number(columns.x)?, number(columns.y)?) {
+            (Some(x), Some(y)) => Some(DVec3::new(x, y, number(columns.z)?.unwrap_or(0.0))),
+            _ => None,
+        };
+        positions.push((index, pos));
+    }
+
+    let mut position_map = PositionMap::new(vec![]);
+    for (index, pos) in positions {
+        position_map.set_pos(index, pos);
+    }
+    Ok(position_map)
+}
+
+struct CsvColumns {
+    index: Option<usize>,
+    x: Option<usize>,
+    y: Option<usize>,
+    z: Option<usize>,
+}
+
+///
+/// xLights lays a custom model out as a grid: `CustomModel` lists rows separated by `;`, cells
+/// separated by `,`, and depth layers separated by `|`. Each cell holds the 1-based node number lit
+/// there, or nothing. Newer versions may write `CustomModelCompressed` instead, a `;` separated
+/// list of `node,row,column[,layer]`.
+///
+/// Rows count down from the top of the grid, so they become negative y.
+///
+fn parse_xlights(contents: &str) -> Result<PositionMap<'static>, String> {
+    let mut reader = Reader::from_str(contents);
+    let (grid, compressed) = loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(element) | Event::Empty(element) if element.local_name().as_ref().eq_ignore_ascii_case(b"custommodel") => {
+                let mut grid = None;
+                let mut compressed = None;
+                for attribute in element.attributes() {
+                    let attribute = attribute.map_err(|e| e.to_string())?;
+                    let value = attribute.unescape_value().map_err(|e| e.to_string())?.into_owned();
+                    match attribute.key.as_ref() {
+                        b"CustomModel" => grid = Some(value),
+                        b"CustomModelCompressed" => compressed = Some(value),
+                        _ => {}
+                    }
+                }
+                break (grid, compressed);
+            }
+            Event::Eof => return Err("No custommodel element found.".to_string()),
+            _ => {}
+        }
+    };
+
+    let node_position = |node: &str, row: usize, column: usize, layer: usize| -> Result<Option<(usize, DVec3)>, String> {
+        if node.trim().is_empty() {
+            return Ok(None);
+        }
+        let node: usize = node.trim().parse().map_err(|_| format!("{} is not a node number.", node))?;
+        let index = node.checked_sub(1).ok_or("Node numbers start at 1.")?;
+        Ok(Some((index, DVec3::new(column as f64, -(row as f64), layer as f64))))
+    };
+
+    let mut positions = vec![];
+    match (grid.filter(|grid| !grid.is_empty()), compressed) {
+        (Some(grid), _) => {
+            for (layer, layer_text) in grid.split('|').enumerate() {
+                for (row, row_text) in layer_text.split(';').enumerate() {
+                    for (column, node) in row_text.split(',').enumerate() {
+                        positions.extend(node_position(node, row, column, layer)?);
+                    }
+                }
+            }
+        }
+        (None, Some(compressed)) => {
+            for entry in compressed.split(';').filter(|entry| !entry.trim().is_empty()) {
+                let fields: Vec<&str> = entry.split(',').collect();
+                let coord = |i: usize| -> Result<usize, String> {
+                    fields.get(i).map_or(Ok(0), |field| field.trim().parse().map_err(|_| format!("{} is not a grid coordinate.", field)))
+                };
+                if fields.len() < 3 {
+                    return Err(format!("Expected node,row,column, got {}.", entry));
+                }
+                positions.extend(node_position(fields[0], coord(1)?, coord(2)?, coord(3)?)?);
+            }
+        }
+        (None, None) => return Err("The custom model has no grid.".to_string()),
+    }
+    from_indexed(positions)
+}
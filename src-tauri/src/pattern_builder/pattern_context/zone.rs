@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+/// Named regions of the layout, like "roofline" or "left window".
+pub type Zones = BTreeMap<String, PixelSelection>;
+
+/// The pixels a zone covers, by their index on the strip.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PixelSelection {
+    /// Pixels from `start` up to, but not including, `end`.
+    Range { start: usize, end: usize },
+    /// Any pixels, in the order patterns drawn into the zone see them.
+    Pixels(Vec<usize>),
+}
+
+impl PixelSelection {
+    pub fn validate(&self, num_pixels: usize) -> Result<(), String> {
+        match self {
+            PixelSelection::Range { start, end } if start >= end => {
+                Err(format!("The range {}..{} has no pixels.", start, end))
+            }
+            PixelSelection::Range { end, .. } if *end > num_pixels => {
+                Err(format!("The range ends at pixel {}, but there are {} pixels.", end, num_pixels))
+            }
+            PixelSelection::Pixels(indices) => match indices.iter().find(|index| **index >= num_pixels) {
+                Some(index) => Err(format!("Pixel {} is out of range, there are {} pixels.", index, num_pixels)),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// The selected pixels that exist on a strip of `num_pixels`, in order.
+    pub fn indices(&self, num_pixels: usize) -> Vec<usize> {
+        match self {
+            PixelSelection::Range { start, end } => (*start..usize::min(*end, num_pixels)).collect(),
+            PixelSelection::Pixels(indices) => indices.iter().copied().filter(|index| *index < num_pixels).collect(),
+        }
+    }
+}
@@ -1,34 +1,90 @@
-use std::collections::LinkedList;
+use std::collections::HashMap;
 use std::future::Future;
 use std::io::{Error, ErrorKind};
 use std::pin::Pin;
 use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
 use futures::task::AtomicWaker;
-use tauri::async_runtime::{JoinHandle, spawn};
-use tauri::{Emitter, Manager};
-use tokio::io::{ReadBuf};
+use crate::runtime::{JoinHandle, spawn};
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::time::timeout;
+#[cfg(feature = "gui")]
 use tokio::sync::RwLockWriteGuard;
 
-use non_locking_io::{NonLockingRead, NonLockingWrite};
+use non_locking_io::NonLockingWrite;
 use packet_types::TcpPacketType;
+#[cfg(feature = "gui")]
 use crate::{AppState, LockedAppState};
+#[cfg(feature = "gui")]
+use crate::neopixel_controller::release_controller;
+use crate::event_sink::{AppEvent, EventSink};
 
+use crate::pico_connection::framing::{encode_tcp_packet, read_tcp_packet, UNSOLICITED_REQUEST_ID};
 use crate::pico_connection::non_locking_io::NonLockingSend;
 use crate::pico_connection::packet_types::UdpPacketType;
+#[cfg(feature = "gui")]
 use crate::tauri_events::ConnectionOpenPayload;
 
 pub mod packet_types;
 pub mod non_locking_io;
+pub mod framing;
+pub mod mock_device;
 
 struct PicoConnectionData {
     tcp_stream: RwLock<TcpStream>,
+    tcp_write_lock: tokio::sync::Mutex<()>,
     udp_socket: RwLock<UdpSocket>,
-    response_futures: Mutex<LinkedList<Arc<ResponseFutureInner>>>,
+    next_request_id: AtomicU16,
+    response_futures: Mutex<HashMap<u16, Arc<ResponseFutureInner>>>,
+    closed: AtomicBool,
+}
+
+impl PicoConnectionData {
+    fn next_request_id(&self) -> u16 {
+        loop {
+            let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+            if id != UNSOLICITED_REQUEST_ID {
+                return id;
+            }
+        }
+    }
+
+    /// Writes a whole packet, so that packets from concurrent senders are never interleaved.
+    async fn write_tcp_packet(&self, packet: &[u8]) -> std::io::Result<()> {
+        let _write_guard = self.tcp_write_lock.lock().await;
+        let mut written = 0;
+        while written < packet.len() {
+            let bytes_sent = self.tcp_stream.non_locking_write(&packet[written..]).await?;
+            if bytes_sent == 0 {
+                return Err(Error::new(ErrorKind::WriteZero, "Connection closed."));
+            }
+            written += bytes_sent;
+        }
+        Ok(())
+    }
+
+    fn resolve_response(&self, request_id: u16, response: Result<(), String>) {
+        match self.response_futures.lock().unwrap().remove(&request_id) {
+            Some(future) => {
+                future.response.lock().unwrap().replace(response);
+                future.waker.wake();
+            },
+            None => println!("Received response for unknown request {}.", request_id),
+        }
+    }
+
+    /// Marks the connection closed and fails every request still waiting for a response.
+    fn close(&self, reason: &str) {
+        self.closed.store(true, Ordering::SeqCst);
+        let pending: Vec<_> = self.response_futures.lock().unwrap().drain().collect();
+        for (_, future) in pending {
+            future.response.lock().unwrap().replace(Err(reason.to_string()));
+            future.waker.wake();
+        }
+    }
 }
 
 pub struct PicoConnection {
@@ -46,28 +102,30 @@ pub type PicoConnectionHandle = Arc<PicoConnection>;
 
 struct ResponseFutureInner {
     waker: AtomicWaker,
-    response: Mutex<Option<Result<(), ()>>>,
+    response: Mutex<Option<Result<(), String>>>,
 }
 
 #[derive(Clone)]
 pub struct PicoConnectionResponseFuture(Arc<ResponseFutureInner>);
 
 impl Future for PicoConnectionResponseFuture {
-    type Output = Result<(), ()>;
+    type Output = Result<(), String>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.waker.register(cx.waker());
         match self.0.response.lock().unwrap().take() {
             Some(result) => Poll::Ready(result),
-            None => {
-                self.0.waker.register(cx.waker());
-                Poll::Pending
-            }
+            None => Poll::Pending,
         }
     }
 }
 
 impl PicoConnection {
-    pub async fn new(ip: String, tcp_port: u16, udp_port: u16) -> Result<PicoConnectionHandle, String> {
+    ///
+    /// Connects to a pico. If the pico later drops the connection, any requests still waiting are
+    /// failed and `ConnectionClose` is sent to `event_sink`.
+    ///
+    pub async fn new(ip: String, tcp_port: u16, udp_port: u16, event_sink: Arc<dyn EventSink>) -> Result<PicoConnectionHandle, String> {
 
         let tcp_stream = timeout(Duration::from_secs(10),TcpStream::connect((ip.clone(), tcp_port))).await
             .map_err(|_| format!("TCP connection timed out."))?
@@ -81,42 +139,56 @@ impl PicoConnection {
 
         let conn_data = Arc::new(PicoConnectionData {
             tcp_stream: RwLock::new(tcp_stream),
+            tcp_write_lock: tokio::sync::Mutex::new(()),
             udp_socket: RwLock::new(udp_socket),
-            response_futures: Mutex::new(LinkedList::new()),
+            next_request_id: AtomicU16::new(1),
+            response_futures: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
         });
         let conn = PicoConnection {
             data: conn_data.clone(),
-            incoming_data_handler: spawn(handle_incoming_tcp_data(conn_data.clone())),
+            incoming_data_handler: spawn(handle_incoming_tcp_data(conn_data.clone(), event_sink)),
         };
         Ok(Arc::new(conn))
     }
 
-    pub async fn send_tcp(&self, packet_type: TcpPacketType, data: &[u8]) -> std::io::Result<usize> {
-        let full_data = [&[packet_type.into()], data].concat();
-        self.data.tcp_stream.non_locking_write(&full_data).await
-            .and_then(|bytes_sent| {
-                if bytes_sent == 0 {
-                    Err(Error::new(ErrorKind::WriteZero, "Connection closed."))
-                } else {
-                    Ok(bytes_sent)
-                }
-            })
+    /// Whether the pico has dropped the connection.
+    pub fn is_closed(&self) -> bool {
+        self.data.closed.load(Ordering::SeqCst)
     }
 
-    async fn await_response(&self) -> Result<(), String> {
+    /// Sends a packet without waiting for a response. Returns the request id the packet was sent with.
+    pub async fn send_tcp(&self, packet_type: TcpPacketType, data: &[u8]) -> std::io::Result<u16> {
+        let request_id = self.data.next_request_id();
+        self.data.write_tcp_packet(&encode_tcp_packet(packet_type, request_id, data)?).await?;
+        Ok(request_id)
+    }
+
+    pub async fn send_tcp_await_response(&self, packet_type: TcpPacketType, data: &[u8]) -> std::io::Result<Result<(), String>> {
+        let request_id = self.data.next_request_id();
+        let packet = encode_tcp_packet(packet_type, request_id, data)?;
         let fut = PicoConnectionResponseFuture(Arc::new(ResponseFutureInner{
             waker: AtomicWaker::new(),
             response: Mutex::new(None),
         }));
-        self.data.response_futures.lock().unwrap().push_back(fut.0.clone());
-        timeout(Duration::from_secs(5), fut).await
-            .map_err(|_| format!("Timed out waiting for server's response."))?
-            .map_err(|_| format!("Server returned ERR."))
-    }
-
-    pub async fn send_tcp_await_response(&self, packet_type: TcpPacketType, data: &[u8]) -> std::io::Result<Result<(), String>> {
-        self.send_tcp(packet_type, data).await?;
-        Ok(self.await_response().await)
+        // Registered before sending, so a fast response can't arrive before anyone is waiting for it.
+        self.data.response_futures.lock().unwrap().insert(request_id, fut.0.clone());
+        if self.is_closed() {
+            self.data.response_futures.lock().unwrap().remove(&request_id);
+            return Ok(Err(format!("Connection closed.")));
+        }
+        if let Err(e) = self.data.write_tcp_packet(&packet).await {
+            self.data.response_futures.lock().unwrap().remove(&request_id);
+            return Err(e);
+        }
+        let response = timeout(Duration::from_secs(5), fut).await;
+        Ok(match response {
+            Ok(result) => result,
+            Err(_) => {
+                self.data.response_futures.lock().unwrap().remove(&request_id);
+                Err(format!("Timed out waiting for server's response."))
+            }
+        })
     }
 
     pub async fn send_udp(&self, packet_type: UdpPacketType, data: &[u8]) -> std::io::Result<usize> {
@@ -132,65 +204,72 @@ impl PicoConnection {
     }
 }
 
-async fn handle_incoming_tcp_data(connection: Arc<PicoConnectionData>) {
-    loop {
-        let mut raw_buf = vec![0; 1];
-        let mut buf = ReadBuf::new(&mut raw_buf);
-        let read_result = connection.tcp_stream.non_locking_read(&mut buf).await;
-
-        let packet_type = match read_result {
-            Ok(()) => match buf.filled().first() {
-                Some(x) => TcpPacketType::try_from(*x)
-                    .expect(format!("Unknown packet type {} received.", *x).as_str()),
-                None => return // Socket closed
+async fn handle_incoming_tcp_data(connection: Arc<PicoConnectionData>, event_sink: Arc<dyn EventSink>) {
+    let reason = loop {
+        let packet = match read_tcp_packet(&connection.tcp_stream).await {
+            Ok(Some(packet)) => packet,
+            Ok(None) => break format!("Connection closed by the pico."),
+            Err(e) => break format!("Error in TCP socket: {}", e),
+        };
+
+        let packet_type = match packet.packet_type() {
+            Some(packet_type) => packet_type,
+            None => {
+                println!("Unknown packet type {} received.", packet.packet_type);
+                continue;
             },
-            Err(e) => panic!("Error in TCP socket: {}", e),
         };
 
         match packet_type {
-            TcpPacketType::Ok => {
-                let mut futures = connection.response_futures.lock().unwrap();
-                for future in futures.split_off(0).iter_mut() {
-                    future.response.lock().unwrap().replace(Ok(()));
-                    future.waker.wake();
-                }
-            },
+            TcpPacketType::Ok => connection.resolve_response(packet.request_id, Ok(())),
             TcpPacketType::Err => {
-                let mut futures = connection.response_futures.lock().unwrap();
-                for future in futures.split_off(0).iter_mut() {
-                    future.response.lock().unwrap().replace(Err(()));
-                    future.waker.wake();
-                }
-                println!("Received ERR from server.");
+                let message = String::from_utf8_lossy(&packet.payload).into_owned();
+                println!("Received ERR from server for request {}: {}", packet.request_id, message);
+                connection.resolve_response(
+                    packet.request_id,
+                    Err(if message.is_empty() {
+                        format!("Server returned ERR.")
+                    } else {
+                        format!("Server returned ERR: {}", message)
+                    }),
+                );
             },
             TcpPacketType::Ping => {
-                connection.tcp_stream.non_locking_write(&[TcpPacketType::Ping.into()]).await
-                    .expect("Ping response failed.");
+                let response = encode_tcp_packet(TcpPacketType::Ping, packet.request_id, &[])
+                    .expect("Ping response is always small enough to encode.");
+                if let Err(e) = connection.write_tcp_packet(&response).await {
+                    break format!("Ping response failed: {}", e);
+                }
             }
             _ => {}
         }
-    }
+    };
+    eprintln!("{}", reason);
+    connection.close(&reason);
+    event_sink.emit(AppEvent::ConnectionClose);
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn connect(ip: String, tcp_port: u16, udp_port: u16, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
 
-    if state.connection.is_some() {
+    if state.connection.as_ref().is_some_and(|connection| !connection.is_closed()) {
         Err(format!("Already connected to a Pico!"))
     } else {
-        let conn = PicoConnection::new(ip.clone(), tcp_port, udp_port).await?;
+        let conn = PicoConnection::new(ip.clone(), tcp_port, udp_port, state.event_sink.clone()).await?;
         state.connection = Some(conn);
-        state.app_handle.emit("connection-open", ConnectionOpenPayload{ ip }).unwrap();
+        state.event_sink.emit(AppEvent::ConnectionOpen(ConnectionOpenPayload{ ip }));
         Ok(())
     }
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn disconnect(tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
     let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
-    state.neopixel_controller.take();
+    release_controller(&mut state).await;
     state.connection.take().ok_or("Not connected to a pico!")?;
-    state.app_handle.emit("connection-close", {}).unwrap();
+    state.event_sink.emit(AppEvent::ConnectionClose);
     Ok(())
 }
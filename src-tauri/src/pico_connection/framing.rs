@@ -0,0 +1,97 @@
+use std::io::{Error, ErrorKind};
+use std::sync::RwLock;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::pico_connection::non_locking_io::NonLockingRead;
+use crate::pico_connection::packet_types::TcpPacketType;
+
+/// Request id used for packets that are not part of a request/response exchange.
+pub const UNSOLICITED_REQUEST_ID: u16 = 0;
+
+///
+/// Header sent in front of every TCP packet.
+///
+/// Encoded as `[packet type: u8][request id: u16][payload length: u16]`, big-endian. Responses
+/// (`Ok`, `Err` and `Ping` replies) carry the request id of the packet they answer.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TcpHeader {
+    pub packet_type: u8,
+    pub request_id: u16,
+    pub payload_len: u16,
+}
+
+impl TcpHeader {
+    pub const LEN: usize = 5;
+
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let [id_hi, id_lo] = self.request_id.to_be_bytes();
+        let [len_hi, len_lo] = self.payload_len.to_be_bytes();
+        [self.packet_type, id_hi, id_lo, len_hi, len_lo]
+    }
+
+    pub fn decode(bytes: [u8; Self::LEN]) -> Self {
+        Self {
+            packet_type: bytes[0],
+            request_id: u16::from_be_bytes([bytes[1], bytes[2]]),
+            payload_len: u16::from_be_bytes([bytes[3], bytes[4]]),
+        }
+    }
+}
+
+pub struct TcpPacket {
+    pub packet_type: u8,
+    pub request_id: u16,
+    pub payload: Vec<u8>,
+}
+
+impl TcpPacket {
+    /// The packet's type, or `None` if it isn't one we know.
+    pub fn packet_type(&self) -> Option<TcpPacketType> {
+        TcpPacketType::try_from(self.packet_type).ok()
+    }
+}
+
+pub fn encode_tcp_packet(packet_type: TcpPacketType, request_id: u16, payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let payload_len = u16::try_from(payload.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Packet payload of {} bytes is too large.", payload.len())))?;
+    let header = TcpHeader { packet_type: packet_type.into(), request_id, payload_len };
+    Ok([&header.encode()[..], payload].concat())
+}
+
+/// Reads exactly `len` bytes. Returns `None` if the stream was closed before any bytes were read.
+async fn read_exact<T: AsyncRead + Unpin>(io: &RwLock<T>, len: usize) -> std::io::Result<Option<Vec<u8>>> {
+    let mut raw_buf = vec![0; len];
+    let mut buf = ReadBuf::new(&mut raw_buf);
+    while buf.remaining() > 0 {
+        let filled_before = buf.filled().len();
+        io.non_locking_read(&mut buf).await?;
+        if buf.filled().len() == filled_before {
+            return if filled_before == 0 {
+                Ok(None)
+            } else {
+                Err(Error::new(ErrorKind::UnexpectedEof, "Connection closed mid-packet."))
+            };
+        }
+    }
+    Ok(Some(raw_buf))
+}
+
+/// Reads the next framed packet. Returns `None` once the stream has been closed.
+pub async fn read_tcp_packet<T: AsyncRead + Unpin>(io: &RwLock<T>) -> std::io::Result<Option<TcpPacket>> {
+    let header = match read_exact(io, TcpHeader::LEN).await? {
+        Some(bytes) => TcpHeader::decode(bytes.try_into().unwrap()),
+        None => return Ok(None),
+    };
+    let payload = if header.payload_len == 0 {
+        vec![]
+    } else {
+        read_exact(io, header.payload_len as usize).await?
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Connection closed mid-packet."))?
+    };
+    Ok(Some(TcpPacket {
+        packet_type: header.packet_type,
+        request_id: header.request_id,
+        payload,
+    }))
+}
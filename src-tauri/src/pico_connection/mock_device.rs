@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::runtime::{JoinHandle, spawn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+use crate::pico_connection::framing::{encode_tcp_packet, TcpHeader, UNSOLICITED_REQUEST_ID};
+use crate::pico_connection::packet_types::{TcpPacketType, UdpPacketType};
+
+///
+/// Everything a [`MockPicoDevice`] has received so far.
+///
+#[derive(Clone, Default)]
+pub struct MockDeviceLog {
+    pub tcp_packets: Vec<(TcpPacketType, Vec<u8>)>,
+    pub neopixel_num_pixels: Option<u16>,
+    pub neopixel_frames: Vec<Vec<u8>>,
+}
+
+struct MockDeviceState {
+    log: Mutex<MockDeviceLog>,
+    failures: Mutex<HashMap<u8, String>>,
+    ignored: Mutex<HashSet<u8>>,
+    frame_count: watch::Sender<usize>,
+    tcp_writer: tokio::sync::Mutex<Option<OwnedWriteHalf>>,
+}
+
+impl MockDeviceState {
+    fn record_frame(&self, frame: Vec<u8>) {
+        let mut log = self.log.lock().unwrap();
+        log.neopixel_frames.push(frame);
+        self.frame_count.send_replace(log.neopixel_frames.len());
+    }
+
+    fn response_for(&self, packet_type: Option<TcpPacketType>, payload: &[u8]) -> Option<Result<(), String>> {
+        let packet_type = match packet_type {
+            Some(packet_type) => packet_type,
+            None => return Some(Err(format!("Unknown packet type."))),
+        };
+        if self.ignored.lock().unwrap().contains(&packet_type.into()) {
+            return None;
+        }
+        if let Some(message) = self.failures.lock().unwrap().get(&packet_type.into()) {
+            return Some(Err(message.clone()));
+        }
+        match packet_type {
+            TcpPacketType::Hello | TcpPacketType::Neopixel_Off => Some(Ok(())),
+            TcpPacketType::Ping => None,
+            TcpPacketType::Neopixel_Init => match <[u8; 2]>::try_from(payload) {
+                Ok(bytes) => {
+                    let mut log = self.log.lock().unwrap();
+                    log.neopixel_num_pixels = Some(u16::from_be_bytes(bytes));
+                    log.neopixel_frames.clear();
+                    self.frame_count.send_replace(0);
+                    Some(Ok(()))
+                },
+                Err(_) => Some(Err(format!("Expected a 2 byte pixel count, got {} bytes.", payload.len()))),
+            },
+            TcpPacketType::Neopixel_Show => {
+                if self.log.lock().unwrap().neopixel_num_pixels.is_none() {
+                    return Some(Err(format!("Neopixels not initialised.")));
+                }
+                self.record_frame(payload.to_vec());
+                Some(Ok(()))
+            },
+            _ => Some(Err(format!("Unsupported packet type {:?}.", packet_type))),
+        }
+    }
+
+    async fn write(&self, packet: &[u8]) -> std::io::Result<()> {
+        match self.tcp_writer.lock().await.as_mut() {
+            Some(writer) => writer.write_all(packet).await,
+            None => Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "No client connected.")),
+        }
+    }
+}
+
+///
+/// An in-process stand-in for a Pico, speaking the same TCP/UDP protocol.
+///
+/// Responds `Ok` to `Hello`, `Neopixel_Init` and `Neopixel_Show`, and records every packet and
+/// neopixel frame it receives. Accepts one client at a time.
+///
+pub struct MockPicoDevice {
+    state: Arc<MockDeviceState>,
+    tcp_port: u16,
+    udp_port: u16,
+    tcp_handler: JoinHandle<()>,
+    udp_handler: JoinHandle<()>,
+}
+
+impl Drop for MockPicoDevice {
+    fn drop(&mut self) {
+        self.tcp_handler.abort();
+        self.udp_handler.abort();
+    }
+}
+
+impl MockPicoDevice {
+    /// Starts a device on random local ports.
+    pub async fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1", 0, 0).await
+    }
+
+    pub async fn bind(ip: &str, tcp_port: u16, udp_port: u16) -> std::io::Result<Self> {
+        let tcp_listener = TcpListener::bind((ip, tcp_port)).await?;
+        let udp_socket = UdpSocket::bind((ip, udp_port)).await?;
+        let state = Arc::new(MockDeviceState {
+            log: Mutex::new(MockDeviceLog::default()),
+            failures: Mutex::new(HashMap::new()),
+            ignored: Mutex::new(HashSet::new()),
+            frame_count: watch::channel(0).0,
+            tcp_writer: tokio::sync::Mutex::new(None),
+        });
+        Ok(Self {
+            tcp_port: tcp_listener.local_addr()?.port(),
+            udp_port: udp_socket.local_addr()?.port(),
+            tcp_handler: spawn(handle_tcp(tcp_listener, state.clone())),
+            udp_handler: spawn(handle_udp(udp_socket, state.clone())),
+            state,
+        })
+    }
+
+    pub fn tcp_port(&self) -> u16 {
+        self.tcp_port
+    }
+
+    pub fn udp_port(&self) -> u16 {
+        self.udp_port
+    }
+
+    pub fn log(&self) -> MockDeviceLog {
+        self.state.log.lock().unwrap().clone()
+    }
+
+    /// Makes the device respond `Err` with the given message to every packet of this type.
+    pub fn fail_packet_type(&self, packet_type: TcpPacketType, message: &str) {
+        self.state.failures.lock().unwrap().insert(packet_type.into(), message.to_string());
+    }
+
+    /// Makes the device never respond to packets of this type.
+    pub fn ignore_packet_type(&self, packet_type: TcpPacketType) {
+        self.state.ignored.lock().unwrap().insert(packet_type.into());
+    }
+
+    /// Closes the connected client's TCP connection, as a Pico losing power would.
+    pub async fn hang_up(&self) -> std::io::Result<()> {
+        match self.state.tcp_writer.lock().await.take() {
+            Some(mut writer) => writer.shutdown().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Sends a `Ping` to the connected client, as the Pico does to check the connection is alive.
+    pub async fn ping(&self) -> std::io::Result<()> {
+        self.state.write(&encode_tcp_packet(TcpPacketType::Ping, UNSOLICITED_REQUEST_ID, &[])?).await
+    }
+
+    /// Waits until at least `count` neopixel frames have been received, and returns all of them.
+    pub async fn wait_for_frames(&self, count: usize, max_wait: Duration) -> Result<Vec<Vec<u8>>, String> {
+        let mut frame_count = self.state.frame_count.subscribe();
+        timeout(max_wait, frame_count.wait_for(|&received| received >= count)).await
+            .map_err(|_| format!("Timed out waiting for {} frames, received {}.", count, *self.state.frame_count.borrow()))?
+            .map_err(|e| e.to_string())?;
+        Ok(self.log().neopixel_frames)
+    }
+}
+
+async fn handle_tcp(listener: TcpListener, state: Arc<MockDeviceState>) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else { return };
+        let (mut reader, writer) = stream.into_split();
+        *state.tcp_writer.lock().await = Some(writer);
+        loop {
+            let mut header_bytes = [0; TcpHeader::LEN];
+            if reader.read_exact(&mut header_bytes).await.is_err() {
+                break;
+            }
+            let header = TcpHeader::decode(header_bytes);
+            let mut payload = vec![0; header.payload_len as usize];
+            if reader.read_exact(&mut payload).await.is_err() {
+                break;
+            }
+            let packet_type = TcpPacketType::try_from(header.packet_type).ok();
+            let response = state.response_for(packet_type, &payload);
+            if let Some(packet_type) = packet_type {
+                state.log.lock().unwrap().tcp_packets.push((packet_type, payload));
+            }
+            let response_packet = match response {
+                Some(Ok(())) => encode_tcp_packet(TcpPacketType::Ok, header.request_id, &[]),
+                Some(Err(message)) => encode_tcp_packet(TcpPacketType::Err, header.request_id, message.as_bytes()),
+                None => continue,
+            };
+            if state.write(&response_packet.unwrap()).await.is_err() {
+                break;
+            }
+        }
+        state.tcp_writer.lock().await.take();
+    }
+}
+
+async fn handle_udp(socket: UdpSocket, state: Arc<MockDeviceState>) {
+    let mut buf = vec![0; u16::MAX as usize];
+    loop {
+        let Ok((len, _)) = socket.recv_from(&mut buf).await else { return };
+        let Some((&packet_type, data)) = buf[..len].split_first() else { continue };
+        if let Ok(UdpPacketType::Neopixel_Show) = UdpPacketType::try_from(packet_type) {
+            if state.log.lock().unwrap().neopixel_num_pixels.is_some() {
+                state.record_frame(data.to_vec());
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 macro_rules! packet_type_enum {
     ($enumName:ident<$valueType:ty>, {$($variantName:ident => $variantValue:expr),*$(,)?}) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
         pub enum $enumName {
             $($variantName,)*
         }
@@ -0,0 +1,325 @@
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "gui")]
+use std::time::Duration;
+use chrono::NaiveTime;
+#[cfg(feature = "gui")]
+use chrono::Local;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
+use crate::runtime::JoinHandle;
+#[cfg(feature = "gui")]
+use crate::runtime::spawn;
+#[cfg(feature = "gui")]
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+#[cfg(feature = "gui")]
+use crate::{AppState, LockedAppState};
+#[cfg(feature = "gui")]
+use crate::neopixel_controller::switch_neopixel_pattern;
+use crate::neopixel_controller::transition::Transition;
+use crate::pattern_builder::component::RandId;
+
+/// How long to wait before looking again when no entry can play right now.
+#[cfg(feature = "gui")]
+const IDLE_RECHECK: Duration = Duration::from_secs(60);
+
+///
+/// A time of day window, such as 22:00 to 06:00. Windows that end before they start run
+/// through midnight.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActiveHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ActiveHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub pattern_id: RandId,
+    /// Seconds to show the pattern for.
+    pub duration: f64,
+    /// When set, the entry is only played during these hours.
+    #[serde(default)]
+    pub active_hours: Option<ActiveHours>,
+}
+
+impl PlaylistEntry {
+    pub fn new(pattern_id: RandId, duration: f64) -> Self {
+        Self {
+            pattern_id,
+            duration,
+            active_hours: None,
+        }
+    }
+
+    pub fn with_active_hours(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+        self.active_hours = Some(ActiveHours { start, end });
+        self
+    }
+
+    pub fn is_active(&self, time: NaiveTime) -> bool {
+        self.active_hours.is_none_or(|hours| hours.contains(time))
+    }
+}
+
+///
+/// An ordered list of patterns to cycle through on the strip, each shown for its own duration.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playlist {
+    pub entries: Vec<PlaylistEntry>,
+    pub shuffle: bool,
+    /// Used when moving from one entry to the next.
+    #[serde(default)]
+    pub transition: Transition,
+}
+
+impl Playlist {
+    pub fn validate(&self) -> Result<(), String> {
+        for entry in &self.entries {
+            if !entry.duration.is_finite() || entry.duration <= 0.0 {
+                return Err(format!("Playlist entry durations must be positive, got {}.", entry.duration));
+            }
+        }
+        self.transition.validate()
+    }
+
+    ///
+    /// Picks the entry to play after `current` at the given time of day, skipping entries outside
+    /// their active hours. Shuffled playlists avoid repeating the current entry when they can.
+    ///
+    pub fn next_entry(&self, current: Option<usize>, time: NaiveTime, rng: &mut impl Rng) -> Option<usize> {
+        let active: Vec<usize> = (0..self.entries.len())
+            .filter(|i| self.entries[*i].is_active(time))
+            .collect();
+        if active.is_empty() {
+            return None;
+        }
+        if self.shuffle {
+            let candidates: Vec<usize> = active.iter().copied().filter(|i| Some(*i) != current).collect();
+            let candidates = if candidates.is_empty() { active } else { candidates };
+            Some(candidates[rng.gen_range(0..candidates.len())])
+        } else {
+            let start = current.map_or(0, |current| current + 1);
+            active.iter().copied().find(|i| *i >= start).or(active.first().copied())
+        }
+    }
+
+    ///
+    /// Writes the playlist as JSON. Pattern ids only last for a session, so entries are saved with
+    /// their pattern's name instead.
+    ///
+    pub fn save(&self, path: impl AsRef<Path>, pattern_name: impl Fn(RandId) -> Option<String>) -> Result<(), String> {
+        let entries = self.entries.iter()
+            .map(|entry| Ok(SavedPlaylistEntry {
+                pattern: pattern_name(entry.pattern_id).ok_or(format!("Pattern with id {} not found", entry.pattern_id))?,
+                duration: entry.duration,
+                active_hours: entry.active_hours,
+            }))
+            .collect::<Result<Vec<_>, String>>()?;
+        let saved = SavedPlaylist {
+            entries,
+            shuffle: self.shuffle,
+            transition: self.transition,
+        };
+        let contents = serde_json::to_string_pretty(&saved).map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    /// Reads a playlist written by [`Self::save`], looking up each pattern by name.
+    pub fn load(path: impl AsRef<Path>, pattern_id: impl Fn(&str) -> Option<RandId>) -> Result<Self, String> {
+        let file_contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let saved: SavedPlaylist = serde_json::from_str(&file_contents).map_err(|err| err.to_string())?;
+        let entries = saved.entries.into_iter()
+            .map(|entry| Ok(PlaylistEntry {
+                pattern_id: pattern_id(&entry.pattern).ok_or(format!("No open pattern is named \"{}\"", entry.pattern))?,
+                duration: entry.duration,
+                active_hours: entry.active_hours,
+            }))
+            .collect::<Result<Vec<_>, String>>()?;
+        let playlist = Self {
+            entries,
+            shuffle: saved.shuffle,
+            transition: saved.transition,
+        };
+        playlist.validate()?;
+        Ok(playlist)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPlaylistEntry {
+    pattern: String,
+    duration: f64,
+    #[serde(default)]
+    active_hours: Option<ActiveHours>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPlaylist {
+    entries: Vec<SavedPlaylistEntry>,
+    shuffle: bool,
+    #[serde(default)]
+    transition: Transition,
+}
+
+/// The playlist and, while it runs, the task moving the strip through it.
+#[derive(Default)]
+pub struct PlaylistPlayer {
+    playlist: Playlist,
+    current: Option<usize>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for PlaylistPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl PlaylistPlayer {
+    pub fn playlist(&self) -> &Playlist {
+        &self.playlist
+    }
+
+    pub fn set_playlist(&mut self, playlist: Playlist) -> Result<(), String> {
+        playlist.validate()?;
+        self.playlist = playlist;
+        self.current = self.current.filter(|current| *current < self.playlist.entries.len());
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+        self.current = None;
+    }
+
+    pub fn view(&self) -> PlaylistView {
+        PlaylistView {
+            playlist: self.playlist.clone(),
+            running: self.is_running(),
+            current: self.current,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlaylistView {
+    playlist: Playlist,
+    running: bool,
+    current: Option<usize>,
+}
+
+///
+/// Moves the strip on to the next entry, returning how long to wait before moving again. The strip
+/// is turned off while no entry is active. If the strip can't be switched, the player stops and
+/// `None` is returned.
+///
+#[cfg(feature = "gui")]
+async fn advance_playlist(state: &mut AppState, app_handle: &AppHandle) -> Option<Duration> {
+    let player = &state.playlist_player;
+    let next = player.playlist.next_entry(player.current, Local::now().time(), &mut rand::thread_rng());
+    let pattern_id = next.map(|next| player.playlist.entries[next].pattern_id);
+    let transition = player.playlist.transition;
+    if let Err(err) = switch_neopixel_pattern(state, pattern_id, transition, app_handle.clone()).await {
+        let message = format!("Playlist stopped, could not switch the strip: {}", err);
+        eprintln!("{}", message);
+        state.debug_println(&message);
+        // The task is finishing by itself, so it is detached rather than aborted.
+        drop(state.playlist_player.handle.take());
+        state.playlist_player.current = None;
+        return None;
+    }
+    state.playlist_player.current = next;
+    Some(next.map_or(IDLE_RECHECK, |next| Duration::from_secs_f64(state.playlist_player.playlist.entries[next].duration)))
+}
+
+#[cfg(feature = "gui")]
+async fn run_playlist(app_handle: AppHandle) {
+    loop {
+        let wait = {
+            let tauri_state = app_handle.state::<LockedAppState>();
+            let mut state = tauri_state.0.write().await;
+            advance_playlist(&mut state, &app_handle).await
+        };
+        let Some(wait) = wait else {
+            return;
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn view_playlist(tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    serde_json::to_string(&state.playlist_player.view()).map_err(|err| err.to_string())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn set_playlist(playlist: Playlist, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    for entry in &playlist.entries {
+        state.pattern_builder.pattern(entry.pattern_id).ok_or(format!("Pattern with id {} not found", entry.pattern_id))?;
+    }
+    state.playlist_player.set_playlist(playlist)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn start_playlist(app_handle: AppHandle, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    state.neopixel_controller.as_ref().ok_or("Neopixels not initialised!")?;
+    if state.playlist_player.playlist.entries.is_empty() {
+        return Err(format!("The playlist is empty."));
+    }
+    state.playlist_player.stop();
+    state.playlist_player.handle = Some(spawn(run_playlist(app_handle)));
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn stop_playlist(tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    state.playlist_player.stop();
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn save_playlist(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<(), String> {
+    let state: RwLockReadGuard<AppState> = tauri_state.0.read().await;
+    state.playlist_player.playlist.save(path, |id| state.pattern_builder.pattern(id).map(|pattern| pattern.name()))
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub async fn load_playlist(path: String, tauri_state: tauri::State<'_, LockedAppState>) -> Result<String, String> {
+    let mut state: RwLockWriteGuard<AppState> = tauri_state.0.write().await;
+    let playlist = Playlist::load(path, |name| {
+        state.pattern_builder.patterns().find(|pattern| pattern.name() == name).map(|pattern| pattern.id())
+    })?;
+    state.playlist_player.stop();
+    state.playlist_player.set_playlist(playlist)?;
+    serde_json::to_string(&state.playlist_player.view()).map_err(|err| err.to_string())
+}
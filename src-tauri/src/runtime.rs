@@ -0,0 +1,55 @@
+//!
+//! Where background tasks run. With the `gui` feature this is tauri's runtime, shared with the
+//! app's commands. Without it, a runtime of our own is started the first time a task is spawned.
+//!
+
+#[cfg(feature = "gui")]
+pub use tauri::async_runtime::{block_on, spawn, spawn_blocking, JoinHandle};
+
+#[cfg(not(feature = "gui"))]
+pub use headless::{block_on, spawn, spawn_blocking, JoinHandle};
+
+#[cfg(not(feature = "gui"))]
+mod headless {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use once_cell::sync::Lazy;
+    use tokio::runtime::Runtime;
+    use tokio::task::JoinError;
+
+    static RUNTIME: Lazy<Runtime> = Lazy::new(|| Runtime::new().expect("Failed to start the async runtime."));
+
+    /// A handle to a spawned task, with the same methods as tauri's.
+    pub struct JoinHandle<T>(tokio::task::JoinHandle<T>);
+
+    impl<T> JoinHandle<T> {
+        pub fn abort(&self) {
+            self.0.abort();
+        }
+
+        pub fn inner(&self) -> &tokio::task::JoinHandle<T> {
+            &self.0
+        }
+    }
+
+    impl<T> Future for JoinHandle<T> {
+        type Output = Result<T, JoinError>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            Pin::new(&mut self.0).poll(cx)
+        }
+    }
+
+    pub fn spawn<F>(task: F) -> JoinHandle<F::Output> where F: Future + Send + 'static, F::Output: Send + 'static {
+        JoinHandle(RUNTIME.spawn(task))
+    }
+
+    pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R> where F: FnOnce() -> R + Send + 'static, R: Send + 'static {
+        JoinHandle(RUNTIME.spawn_blocking(f))
+    }
+
+    pub fn block_on<F: Future>(task: F) -> F::Output {
+        RUNTIME.block_on(task)
+    }
+}
@@ -1,11 +1,14 @@
 use serde::Serialize;
 use crate::pattern_builder::component::RandId;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct DebugMessagePayload { pub message: String, }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ConnectionOpenPayload { pub ip: String, }
 
-#[derive(Clone, Serialize)]
-pub struct PixelUpdatePayload { pub id: RandId, pub pixel_data: Vec<(u8, u8, u8, u8)> }
\ No newline at end of file
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PixelUpdatePayload { pub id: RandId, pub pixel_data: Vec<(u8, u8, u8, u8)> }
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NeopixelOutputPayload { pub pixel_data: Vec<(u8, u8, u8)> }
\ No newline at end of file
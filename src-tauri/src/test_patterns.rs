@@ -155,7 +155,7 @@ fn test_pattern(pattern_context: watch::Receiver<PatternContext<'static>>) -> Pa
     let mask = AlphaMask::new();
     mask.stack().write().push(Pulse::new(4.0, 10.0, 3.0).into_layer());
     mask.stack().write().push(Persistence::<Frame<ScalarPixel>>::new(5.0).into_layer());
-    mask.stack().write().push(Sparkles::new(7.0, 5.0).into_layer());
+    mask.stack().write().push(Sparkles::new(7.0, 5.0).with_seed(1).into_layer());
     pattern.stack().write().push(mask.into_layer());
 
     pattern
@@ -165,10 +165,8 @@ fn stutter_pulse_pattern(pattern_context: watch::Receiver<PatternContext<'static
     let pattern = Pattern::new("Stutter Pulse", pattern_context, 60.0);
 
     let green_group = Group::new();
-    let noise = SimplexNoise::new(0.5);
-    noise.scale().write().x = 1.0/40.0;
-    noise.scale().write().y = 1.0/40.0;
-    noise.scale().write().z = 1.0/40.0;
+    let noise = SimplexNoise::new(0.5).with_seed(1);
+    *noise.scale().write() = DVec3::repeat(1.9);
     green_group.stack().write().push(noise.into_layer());
     let into_texture = ScalarToTexture::new();
     into_texture.texture().write().push(SolidColor::new(Rgb::from_str("#FEFF66").unwrap().into()).into_layer());
@@ -183,7 +181,7 @@ fn stutter_pulse_pattern(pattern_context: watch::Receiver<PatternContext<'static
     *range_a.variance().write() = 10.0;
     sparkles_group.stack().write().push(range_a.into_layer());
     let mask = AlphaMask::new();
-    mask.stack().write().push(Sparkles::new(7.0, 3.0).into_layer());
+    mask.stack().write().push(Sparkles::new(7.0, 3.0).with_seed(2).into_layer());
     sparkles_group.stack().write().push(mask.into_layer());
     pattern.stack().write().push(sparkles_group.into_layer().with_name("BG Sparkle"));
 
@@ -212,10 +210,8 @@ pub fn blocks_pattern(pattern_context: watch::Receiver<PatternContext<'static>>)
 
     for x in 0..num_colors {
         let layer = Group::new();
-        let noise = SimplexNoise::new(0.8 - (0.3 * (x + 1) as f64 / num_colors as f64));
-        noise.scale().write().x = 1.0/30.0;
-        noise.scale().write().y = 1.0/30.0;
-        noise.scale().write().z = 1.0/30.0;
+        let noise = SimplexNoise::new(0.8 - (0.3 * (x + 1) as f64 / num_colors as f64)).with_seed(x as u32);
+        *noise.scale().write() = DVec3::repeat(2.5);
         layer.stack().write().push(noise.into_layer());
         let into_texture = ScalarToTexture::new();
         let c2 = color.clone().with_hue(color.clone().hue.into_degrees() + (x as f64 * 360.0 / num_colors as f64));
@@ -266,19 +262,20 @@ fn simple_wave_pattern(pattern_context: watch::Receiver<PatternContext<'static>>
         type Output = Frame<ColorPixel>;
 
         fn next(&mut self, input: Self::Input, t: f64, ctx: &PatternContext) -> Self::Output {
-            (0..ctx.num_pixels())
-                .map(|x| ctx.position_map().pos(x))
-                .map(|o_pos| {
-                    if let Some(pos) = o_pos {
-                        let magnitude = pos.dot(&self.wavelength.read().scale(1.0 / self.wavelength.read().magnitude()));
-                        let mut amount = square_wave(*self.ratio.read(), *self.smoothness.read(), self.wavelength.read().magnitude(), magnitude - t * *self.wave_speed.read());
-                        amount = (amount).powf(3.0);
-                        self.color.read().scale_opacity(amount)
-                    } else {
-                        ColorPixel::empty()
-                    }
-                })
-                .collect()
+            let wavelength = *self.wavelength.read();
+            let direction = wavelength.scale(1.0 / wavelength.magnitude());
+            let (ratio, smoothness, wave_speed) = (*self.ratio.read(), *self.smoothness.read(), *self.wave_speed.read());
+            let color = *self.color.read();
+            ctx.map_pixels(|_, o_pos| {
+                if let Some(pos) = o_pos {
+                    let magnitude = pos.dot(&direction);
+                    let mut amount = square_wave(ratio, smoothness, wavelength.magnitude(), magnitude - t * wave_speed);
+                    amount = (amount).powf(3.0);
+                    color.scale_opacity(amount)
+                } else {
+                    ColorPixel::empty()
+                }
+            })
         }
 
         fn view_properties(&self) -> Vec<PropView> {
@@ -315,10 +312,10 @@ fn growing_hearts_pattern(pattern_context: watch::Receiver<PatternContext<'stati
         pub fn new(center: DVec3) -> Self {
             Self {
                 center: NumVecPropCore::new(center).into_prop(PropertyInfo::new("Center")),
-                width: NumPropCore::new_slider(2.5, 0.0..10.0, 0.1).into_prop(PropertyInfo::new("Width")),
-                grow_speed: NumPropCore::new_slider(10.0, 0.0..50.0, 0.5).into_prop(PropertyInfo::new("Grow Speed")),
+                width: NumPropCore::new_slider(0.3, 0.0..2.0, 0.01).into_prop(PropertyInfo::new("Width")),
+                grow_speed: NumPropCore::new_slider(1.3, 0.0..5.0, 0.05).into_prop(PropertyInfo::new("Grow Speed")),
                 heart_period: NumPropCore::new_slider(2.5, 0.0..10.0, 0.1).into_prop(PropertyInfo::new("Heart Period")),
-                max_size: NumPropCore::new_slider(50.0, 0.0..100.0, 1.0).into_prop(PropertyInfo::new("Max Size")),
+                max_size: NumPropCore::new_slider(6.5, 0.0..15.0, 0.1).into_prop(PropertyInfo::new("Max Size")),
                 hearts: vec![],
                 last_t: None,
                 last_heart: None,
@@ -381,10 +378,16 @@ fn growing_hearts_pattern(pattern_context: watch::Receiver<PatternContext<'stati
                 self.max_size,
             );
         }
+
+        fn reset(&mut self) {
+            self.hearts.clear();
+            self.last_t = None;
+            self.last_heart = None;
+        }
     }
 
-    // pattern.stack().write().push(Heart::new(DVec3::new(7.5, 7.5, 0.0), 15.0).into_layer());
-    pattern.stack().write().push(GrowingHearts::new(DVec3::new(7.5, 7.5, 0.0)).into_layer());
+    // pattern.stack().write().push(Heart::new(DVec3::zeros(), 2.0).into_layer());
+    pattern.stack().write().push(GrowingHearts::new(DVec3::zeros()).into_layer());
     let texture = ScalarToTexture::new();
     texture.texture().write().push(SolidColor::new(Rgb::from_str("#FF00E1").unwrap().into()).into_layer());
     pattern.stack().write().push(texture.into_layer());
@@ -396,14 +399,14 @@ pub fn pretty_light_pattern(pattern_context: watch::Receiver<PatternContext<'sta
     let pattern = Pattern::new("Pretty Lights", pattern_context, 30.0);
 
     let white_group = Group::new();
-    white_group.stack().write().push(Sparkles::new(0.1, 10.0).into_layer());
+    white_group.stack().write().push(Sparkles::new(0.1, 10.0).with_seed(1).into_layer());
     let white_texture = ScalarToTexture::new();
     white_texture.texture().write().push(SolidColor::new(Rgb::from_str("#FFFFFF").unwrap().into()).into_layer());
     white_group.stack().write().push(white_texture.into_layer());
     pattern.stack().write().push(white_group.into_layer());
 
     let pink_group = Group::new();
-    pink_group.stack().write().push(Sparkles::new(0.1, 10.0).into_layer());
+    pink_group.stack().write().push(Sparkles::new(0.1, 10.0).with_seed(2).into_layer());
     let pink_texture = ScalarToTexture::new();
     pink_texture.texture().write().push(SolidColor::new(Rgb::from_str("#D357FE").unwrap().into()).into_layer());
     pink_group.stack().write().push(pink_texture.into_layer());
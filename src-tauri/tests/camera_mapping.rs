@@ -0,0 +1,153 @@
+//! Maps a known layout from synthetic photos and checks the positions come back.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use nalgebra_glm::{DVec2, DVec3};
+use tokio::sync::broadcast;
+
+use dazzlefruit_lib::camera_mapping::{build_position_map, detect_view, scan_view, FolderFrameSource, FrameSource, DEFAULT_THRESHOLD};
+use dazzlefruit_lib::camera_mapping::detection::{find_brightest_blob, GrayImage};
+use dazzlefruit_lib::camera_mapping::triangulation::{triangulate, CameraView};
+use dazzlefruit_lib::neopixel_controller::NeopixelController;
+use dazzlefruit_lib::neopixel_controller::output_format::{ChannelOrder, NeopixelOutputFormat};
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::generator::LayoutGenerator;
+
+const IMAGE_SIZE: usize = 160;
+const AMBIENT: f32 = 0.1;
+const ANGLES: [f64; 3] = [0.0, 90.0, 200.0];
+/// Hidden from every view.
+const HIDDEN_PIXEL: usize = 3;
+/// Only visible from the first view, so it can't be placed.
+const ONE_VIEW_PIXEL: usize = 7;
+
+/// A dim scene with a soft spot of light where `pos` appears from `view`.
+fn photograph(view: &CameraView, pos: Option<DVec3>) -> GrayImage {
+    let centre = (IMAGE_SIZE - 1) as f64 / 2.0;
+    let spot = pos.map(|pos| view.project(pos)).map(|seen| DVec2::new(centre + seen.x, centre - seen.y));
+    let pixels = (0..IMAGE_SIZE * IMAGE_SIZE)
+        .map(|i| {
+            let (x, y) = ((i % IMAGE_SIZE) as f64, (i / IMAGE_SIZE) as f64);
+            let light = spot.map_or(0.0, |spot| (-(DVec2::new(x, y) - spot).norm_squared() / (2.0 * 1.5 * 1.5)).exp());
+            AMBIENT + 0.8 * light as f32
+        })
+        .collect();
+    GrayImage::new(IMAGE_SIZE, IMAGE_SIZE, pixels).unwrap()
+}
+
+fn tree() -> Vec<DVec3> {
+    LayoutGenerator::Helix { num_pixels: 12, turns: 2.0, height: 100.0, bottom_radius: 40.0, top_radius: 10.0 }
+        .generate().unwrap()
+        .iter()
+        .map(|pos| pos.unwrap() - DVec3::new(0.0, 50.0, 0.0))
+        .collect()
+}
+
+fn write_view(dir: &PathBuf, view_index: usize, view: &CameraView, tree: &[DVec3]) {
+    fs::create_dir_all(dir).unwrap();
+    photograph(view, None).save_png(dir.join("background.png")).unwrap();
+    for (i, pos) in tree.iter().enumerate() {
+        let visible = i != HIDDEN_PIXEL && (i != ONE_VIEW_PIXEL || view_index == 0);
+        photograph(view, visible.then_some(*pos)).save_png(dir.join(format!("{}.png", i))).unwrap();
+    }
+}
+
+/// The newest bytes sent to the strip since the last call, if any.
+fn last_output(output: &mut broadcast::Receiver<Vec<u8>>) -> Option<Vec<u8>> {
+    let mut last = None;
+    loop {
+        match output.try_recv() {
+            Ok(bytes) => last = Some(bytes),
+            Err(broadcast::error::TryRecvError::Lagged(_)) => {},
+            Err(_) => return last,
+        }
+    }
+}
+
+/// Photographs a virtual strip, showing the pixel it last lit at that pixel's place in the tree.
+struct StripCamera {
+    view: CameraView,
+    tree: Vec<DVec3>,
+    output: broadcast::Receiver<Vec<u8>>,
+    lit_pixel: Option<usize>,
+}
+
+impl FrameSource for StripCamera {
+    fn capture(&mut self, lit_pixel: Option<usize>) -> Result<Option<GrayImage>, String> {
+        if let Some(bytes) = last_output(&mut self.output) {
+            self.lit_pixel = bytes.chunks(3).position(|rgb| rgb.iter().any(|channel| *channel > 0));
+        }
+        assert_eq!(self.lit_pixel, lit_pixel, "The strip shows the wrong pixel");
+        Ok(Some(photograph(&self.view, self.lit_pixel.map(|i| self.tree[i]))))
+    }
+}
+
+#[test]
+fn blob_centre_is_found_to_subpixel_accuracy() {
+    let view = CameraView::orbit(0.0);
+    let pos = DVec3::new(10.3, -20.6, 5.0);
+    let found = find_brightest_blob(&photograph(&view, Some(pos)), Some(&photograph(&view, None)), DEFAULT_THRESHOLD).unwrap();
+    assert!((found - DVec2::new(10.3, -20.6)).norm() < 0.1, "Found the blob at {:?}", found);
+
+    assert_eq!(find_brightest_blob(&photograph(&view, None), None, DEFAULT_THRESHOLD), None);
+}
+
+#[test]
+fn parallel_views_cannot_place_a_pixel() {
+    let pos = DVec3::new(1.0, 2.0, 3.0);
+    let front = CameraView::orbit(0.0);
+    let back = CameraView::orbit(180.0);
+    assert_eq!(triangulate(&[(front, front.project(pos)), (back, back.project(pos))]), None);
+
+    let side = CameraView::orbit(90.0);
+    let found = triangulate(&[(front, front.project(pos)), (side, side.project(pos))]).unwrap();
+    assert!((found - pos).norm() < 1e-9);
+}
+
+#[test]
+fn tree_is_mapped_from_photos() {
+    let tree = tree();
+    let root = std::env::temp_dir().join(format!("dazzlefruit-mapping-{}", rand::random::<u32>()));
+    let mut detections = vec![];
+    for (view_index, angle) in ANGLES.iter().enumerate() {
+        let view = CameraView::orbit(*angle);
+        let dir = root.join(format!("view-{}", view_index));
+        write_view(&dir, view_index, &view, &tree);
+        detections.push(detect_view(&mut FolderFrameSource::new(&dir), view, tree.len(), DEFAULT_THRESHOLD).unwrap());
+    }
+    fs::remove_dir_all(root).unwrap();
+
+    let position_map = build_position_map(&detections, tree.len());
+    for (i, expected) in tree.iter().enumerate() {
+        match position_map.pos(i) {
+            Some(found) => assert!((found - expected).norm() < 0.5, "Pixel {} mapped to {:?}, expected {:?}", i, found, expected),
+            None => assert!(i == HIDDEN_PIXEL || i == ONE_VIEW_PIXEL, "Pixel {} was not mapped", i),
+        }
+    }
+    assert_eq!(position_map.pos(HIDDEN_PIXEL), None);
+    assert_eq!(position_map.pos(ONE_VIEW_PIXEL), None);
+}
+
+#[tokio::test]
+async fn tree_is_mapped_by_scanning_the_strip() {
+    let tree = tree();
+    let format = NeopixelOutputFormat { channel_order: ChannelOrder::Rgb, gamma: 1.0 };
+    let (_pattern_updates, pattern_update_receiver) = broadcast::channel(1);
+    let controller = NeopixelController::new_virtual(tree.len() as u16, format, pattern_update_receiver);
+    let mut output = controller.output_receiver();
+
+    let mut detections = vec![];
+    for angle in ANGLES {
+        let view = CameraView::orbit(angle);
+        let mut camera = StripCamera { view, tree: tree.clone(), output: controller.output_receiver(), lit_pixel: None };
+        detections.push(scan_view(&controller, &mut camera, view, Duration::ZERO, DEFAULT_THRESHOLD).await.unwrap());
+    }
+
+    let position_map = build_position_map(&detections, tree.len());
+    for (i, expected) in tree.iter().enumerate() {
+        let found = position_map.pos(i).unwrap_or_else(|| panic!("Pixel {} was not mapped", i));
+        assert!((found - expected).norm() < 0.5, "Pixel {} mapped to {:?}, expected {:?}", i, found, expected);
+    }
+    assert_eq!(last_output(&mut output), Some(vec![0; tree.len() * 3]), "The strip was left lit");
+}
@@ -0,0 +1,19 @@
+//!
+//! Render helpers shared by the integration tests.
+//!
+
+use dazzlefruit_lib::pattern_builder::component::frame::{Frame, Pixel};
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::component::layer::io_type::DynType;
+use dazzlefruit_lib::pattern_builder::component::layer::layer_stack::LayerStack;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+
+/// Renders `layers` as one stack at `t`.
+pub fn render<O>(layers: impl IntoIterator<Item=Layer>, t: f64, ctx: &PatternContext) -> Vec<O> where O: Pixel, Frame<O>: DynType {
+    let mut stack = LayerStack::new();
+    for layer in layers {
+        stack.push(layer);
+    }
+    let frame: Frame<O> = stack.next((), t, ctx).unwrap();
+    frame.to_vec()
+}
@@ -0,0 +1,78 @@
+use std::f64::consts::FRAC_PI_2;
+
+use nalgebra_glm::{DVec2, DVec3};
+
+use dazzlefruit_lib::pattern_builder::component::frame::{Frame, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::layer_stack::LayerStack;
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::heart::Heart;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::simplex_noise::SimplexNoise;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::generator::LayoutGenerator;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+fn context(position_map: PositionMap<'static>) -> PatternContext<'static> {
+    let mut ctx = PatternContext::new(position_map.len(), standard_type_mapper());
+    ctx.set_position_map(position_map);
+    ctx
+}
+
+fn render(layer: Layer, ctx: &PatternContext) -> Frame<ScalarPixel> {
+    let mut stack = LayerStack::new();
+    stack.push(layer);
+    stack.next((), 1.5, ctx).unwrap()
+}
+
+#[test]
+fn bounds_describe_the_layout() {
+    let position_map = PositionMap::new(vec![Some(DVec3::new(10.0, 0.0, 0.0)), None, Some(DVec3::new(30.0, 10.0, 4.0))]);
+    let bounds = position_map.bounds();
+    assert_eq!(bounds.center(), DVec3::new(20.0, 5.0, 2.0));
+    assert_eq!(bounds.size(), DVec3::new(20.0, 10.0, 4.0));
+    assert_eq!(bounds.scale(), 10.0);
+
+    let ctx = context(position_map);
+    assert_eq!(ctx.centered_pos(0), Some(DVec3::new(-1.0, -0.5, -0.2)));
+    assert_eq!(ctx.normalized_pos(2), Some(DVec3::new(1.0, 0.75, 0.6)));
+    assert_eq!(ctx.centered_pos(1), None);
+
+    // A single pixel has no extent to scale by.
+    assert_eq!(PositionMap::new(vec![Some(DVec3::new(1.0, 2.0, 3.0))]).bounds().scale(), 1.0);
+}
+
+#[test]
+fn round_coordinates_measure_from_the_centre() {
+    let ctx = context(PositionMap::new(vec![
+        Some(DVec3::new(-2.0, -2.0, -2.0)),
+        Some(DVec3::new(2.0, 2.0, 2.0)),
+        Some(DVec3::new(0.0, 2.0, 0.0)),
+        Some(DVec3::new(0.0, 0.0, 2.0)),
+    ]));
+    let polar = ctx.polar_pos(2).unwrap();
+    assert!((polar - DVec2::new(1.0, FRAC_PI_2)).norm() < 1e-12);
+
+    let cylindrical = ctx.cylindrical_pos(3).unwrap();
+    assert!((cylindrical - DVec3::new(1.0, FRAC_PI_2, 0.0)).norm() < 1e-12);
+
+    let spherical = ctx.spherical_pos(2).unwrap();
+    assert!((spherical.x - 1.0).abs() < 1e-12);
+    assert!((spherical.z - FRAC_PI_2).abs() < 1e-12);
+}
+
+#[test]
+fn position_based_layers_ignore_layout_units() {
+    let matrix = LayoutGenerator::Matrix { width: 12, height: 12, spacing: 1.0, serpentine: false, column_major: false }
+        .generate().unwrap();
+    let millimetres = PositionMap::new(matrix.iter().map(|pos| pos.map(|pos| pos * 16.5 + DVec3::new(400.0, -20.0, 0.0))).collect());
+    let (small, large) = (context(matrix), context(millimetres));
+
+    let layers = || [Heart::new(DVec3::zeros(), 2.0).into_layer(), SimplexNoise::new(1.0).with_seed(5).into_layer()];
+    for (small_layer, large_layer) in layers().into_iter().zip(layers()) {
+        let small_frame = render(small_layer, &small);
+        let large_frame = render(large_layer, &large);
+        for (a, b) in small_frame.iter().zip(large_frame.iter()) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+}
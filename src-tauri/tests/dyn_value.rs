@@ -0,0 +1,45 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dazzlefruit_lib::pattern_builder::component::frame::{with_buffer_pool, Frame, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::io_type::DynType;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+/// Counts allocations, so the test can tell whether values passed between layers are reboxed.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn mapped_values_reuse_their_boxes() {
+    let type_mapper = standard_type_mapper();
+    with_buffer_pool(|| {
+        let mut frame: Frame<ScalarPixel> = Frame::empty(10);
+        let mut pass_through = || {
+            // Frame -> Option<Frame> goes through a typed mapping rather than the identity.
+            let mapped: Option<Frame<ScalarPixel>> = frame.clone().into_dyn_value().try_into(&type_mapper).unwrap();
+            frame = mapped.unwrap().into_dyn_value().try_into(&type_mapper).unwrap();
+        };
+        pass_through();
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..10 {
+            pass_through();
+        }
+        assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), before);
+    });
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use dazzlefruit_lib::event_sink::{AppEvent, MemoryEventSink};
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+use dazzlefruit_lib::test_patterns::test_patterns;
+
+#[tokio::test]
+async fn pixel_updates_reach_the_sink() {
+    let sink = Arc::new(MemoryEventSink::new());
+    let mut pattern_builder = PatternBuilder::new(5, sink.clone());
+    let pattern = test_patterns(pattern_builder.pattern_context()).into_iter()
+        .find(|pattern| pattern.name() == "Solid Color")
+        .unwrap();
+    let pattern_id = pattern.id();
+    pattern_builder.load_pattern(pattern);
+    pattern_builder.watch_pattern(pattern_id).unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let events = sink.take();
+    assert!(!events.is_empty());
+    for event in events {
+        match event {
+            AppEvent::PixelUpdate(payload) => {
+                assert_eq!(payload.id, pattern_id);
+                assert_eq!(payload.pixel_data.len(), 5);
+            },
+            event => panic!("Unexpected {} event.", event.name()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn unwatched_patterns_stay_quiet() {
+    let sink = Arc::new(MemoryEventSink::new());
+    let mut pattern_builder = PatternBuilder::new(5, sink.clone());
+    let pattern = test_patterns(pattern_builder.pattern_context()).into_iter()
+        .find(|pattern| pattern.name() == "Solid Color")
+        .unwrap();
+    let pattern_id = pattern.id();
+    pattern_builder.load_pattern(pattern);
+    pattern_builder.watch_pattern(pattern_id).unwrap();
+    pattern_builder.unwatch_pattern(pattern_id).unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    sink.take();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(sink.take().is_empty());
+    assert_eq!(pattern_builder.pattern(pattern_id).unwrap().subscriber_count(), 0);
+}
@@ -0,0 +1,100 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::component::frame::ColorPixel;
+use dazzlefruit_lib::pattern_builder::export::{export, ExportFormat, ExportLayout, ExportOptions};
+use dazzlefruit_lib::pattern_builder::library::color::textures::solid_color::SolidColor;
+use dazzlefruit_lib::pattern_builder::offline_render::OfflineRenderer;
+use dazzlefruit_lib::pattern_builder::pattern::Pattern;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+
+const RED: [u8; 3] = [255, 0, 0];
+const BLACK: [u8; 3] = [0, 0, 0];
+
+/// Exports a solid red pattern. The layout has pixels at (0, 0), (1, 0) and (0, 1), plus one with no position.
+fn export_red(format: ExportFormat, options: ExportOptions) -> Vec<u8> {
+    let pattern_builder = PatternBuilder::new(4, Arc::new(NullEventSink));
+    pattern_builder.set_position_map(PositionMap::new(vec![
+        Some(DVec3::new(0.0, 0.0, 0.0)),
+        Some(DVec3::new(1.0, 0.0, 0.0)),
+        Some(DVec3::new(0.0, 1.0, 0.0)),
+        None,
+    ]));
+    let pattern = Pattern::new("Red", pattern_builder.pattern_context(), 30.0);
+    pattern.stack().write().push(SolidColor::new(ColorPixel::new(1.0, 0.0, 0.0, 1.0)).into_layer());
+    let mut bytes = vec![];
+    export(&mut OfflineRenderer::new(&pattern), &options, format, &mut bytes).unwrap();
+    bytes
+}
+
+fn rgb_at(rgb: &[u8], channels: usize, width: u32, (x, y): (u32, u32)) -> [u8; 3] {
+    let i = (y * width + x) as usize * channels;
+    [rgb[i], rgb[i + 1], rgb[i + 2]]
+}
+
+#[test]
+fn png_has_a_row_per_frame() {
+    let options = ExportOptions { num_frames: 3, pixel_size: 2, ..ExportOptions::default() };
+    let bytes = export_red(ExportFormat::Png, options);
+
+    let mut reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    let mut rgb = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgb).unwrap();
+    assert_eq!((info.width, info.height, info.color_type), (8, 6, png::ColorType::Rgb));
+    for corner in [(0, 0), (7, 0), (0, 5), (7, 5)] {
+        assert_eq!(rgb_at(&rgb, 3, info.width, corner), RED);
+    }
+}
+
+#[test]
+fn gif_draws_the_projected_layout() {
+    let options = ExportOptions { num_frames: 2, layout: ExportLayout::Flat, pixel_size: 2, image_size: 10, ..ExportOptions::default() };
+    let bytes = export_red(ExportFormat::Gif, options);
+
+    let mut decode_options = gif::DecodeOptions::new();
+    decode_options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = decode_options.read_info(Cursor::new(bytes)).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (10, 10));
+    let mut num_frames = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        num_frames += 1;
+        let rgba = &frame.buffer;
+        // +Y is up, so the pixel at (0, 1) is drawn in the top-left corner.
+        assert_eq!(rgb_at(rgba, 4, 10, (0, 0)), RED);
+        assert_eq!(rgb_at(rgba, 4, 10, (0, 9)), RED);
+        assert_eq!(rgb_at(rgba, 4, 10, (9, 9)), RED);
+        assert_eq!(rgb_at(rgba, 4, 10, (9, 0)), BLACK);
+        assert_eq!(rgb_at(rgba, 4, 10, (5, 5)), BLACK);
+    }
+    assert_eq!(num_frames, 2);
+}
+
+#[test]
+fn csv_has_a_row_per_pixel_per_frame() {
+    let options = ExportOptions { num_frames: 2, fps: 10.0, start_t: 1.0, ..ExportOptions::default() };
+    let csv = String::from_utf8(export_red(ExportFormat::Csv, options)).unwrap();
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "frame,t,pixel,r,g,b");
+    assert_eq!(lines.len(), 1 + 2 * 4);
+    assert_eq!(lines[1], "0,1,0,255,0,0");
+    assert_eq!(lines[8], "1,1.1,3,255,0,0");
+}
+
+#[test]
+fn empty_exports_are_rejected() {
+    let pattern_builder = PatternBuilder::new(4, Arc::new(NullEventSink));
+    let pattern = Pattern::new("Empty", pattern_builder.pattern_context(), 30.0);
+    let options = ExportOptions { num_frames: 0, ..ExportOptions::default() };
+    assert!(export(&mut OfflineRenderer::new(&pattern), &options, ExportFormat::Png, vec![]).is_err());
+    for fps in [0.0, f64::NAN, f64::INFINITY] {
+        let options = ExportOptions { fps, ..ExportOptions::default() };
+        assert!(export(&mut OfflineRenderer::new(&pattern), &options, ExportFormat::Png, vec![]).is_err());
+    }
+    assert_eq!(ExportFormat::from_path("out.GIF"), Some(ExportFormat::Gif));
+    assert_eq!(ExportFormat::from_path("out.mp4"), None);
+}
@@ -0,0 +1,62 @@
+use dazzlefruit_lib::pattern_builder::component::frame::{with_buffer_pool, Frame, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::layer_stack::LayerStack;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::simplex_noise::SimplexNoise;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+const NUM_PIXELS: usize = 5000;
+
+fn render(ctx: &PatternContext, t: f64) -> Frame<ScalarPixel> {
+    let mut stack = LayerStack::new();
+    stack.push(SimplexNoise::new(1.0).with_seed(3).into_layer());
+    stack.next((), t, ctx).unwrap()
+}
+
+#[test]
+fn parallel_evaluation_matches_sequential_slices() {
+    let ctx = PatternContext::new(NUM_PIXELS, standard_type_mapper());
+    let whole = render(&ctx, 1.5);
+
+    // Each slice is small enough to be evaluated on a single thread.
+    let sliced: Vec<ScalarPixel> = (0..NUM_PIXELS).step_by(500)
+        .flat_map(|start| Vec::from(render(&ctx.slice(start..start + 500), 1.5)))
+        .collect();
+    assert_eq!(*whole, sliced);
+}
+
+#[test]
+fn map_pixels_visits_every_pixel_once() {
+    let ctx = PatternContext::new(NUM_PIXELS, standard_type_mapper());
+    let frame: Frame<ScalarPixel> = ctx.map_pixels(|i, pos| {
+        assert_eq!(pos.unwrap().x, i as f64);
+        i as f64
+    });
+    assert!(frame.iter().enumerate().all(|(i, value)| *value == i as f64));
+
+    let mut reused = frame;
+    ctx.slice(0..10).fill_pixels(&mut reused, |_, _| 1.0);
+    assert_eq!(*reused, vec![1.0; 10]);
+}
+
+#[test]
+fn dropped_frames_are_reused() {
+    let frame: Frame<ScalarPixel> = Frame::empty(NUM_PIXELS);
+    let buffer = frame.as_ptr();
+    drop(frame);
+
+    let next: Frame<ScalarPixel> = Frame::empty(NUM_PIXELS);
+    assert_eq!(next.as_ptr(), buffer);
+}
+
+#[test]
+fn buffer_pool_reuses_buffers_that_are_big_enough() {
+    with_buffer_pool(|| {
+        let frame: Frame<ScalarPixel> = Frame::with_capacity(100);
+        let buffer = frame.as_ptr();
+        drop(frame);
+        assert_eq!(Frame::<ScalarPixel>::with_capacity(50).as_ptr(), buffer);
+
+        drop(Frame::<ScalarPixel>::with_capacity(10));
+        assert!(Frame::<ScalarPixel>::with_capacity(1000).capacity() >= 1000);
+    });
+}
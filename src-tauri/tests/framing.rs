@@ -0,0 +1,42 @@
+use std::sync::RwLock;
+
+use dazzlefruit_lib::pico_connection::framing::{encode_tcp_packet, read_tcp_packet, TcpHeader};
+use dazzlefruit_lib::pico_connection::packet_types::TcpPacketType;
+
+#[test]
+fn headers_round_trip() {
+    let header = TcpHeader { packet_type: 7, request_id: 0x1234, payload_len: 0xABCD };
+    assert_eq!(header.encode(), [7, 0x12, 0x34, 0xAB, 0xCD]);
+    assert_eq!(TcpHeader::decode(header.encode()), header);
+}
+
+#[test]
+fn oversized_payloads_are_rejected() {
+    assert!(encode_tcp_packet(TcpPacketType::Neopixel_Show, 1, &vec![0; u16::MAX as usize]).is_ok());
+    assert!(encode_tcp_packet(TcpPacketType::Neopixel_Show, 1, &vec![0; u16::MAX as usize + 1]).is_err());
+}
+
+#[tokio::test]
+async fn packets_are_read_back_in_order() {
+    let bytes = [
+        encode_tcp_packet(TcpPacketType::Err, 3, b"Nope").unwrap(),
+        encode_tcp_packet(TcpPacketType::Ok, 4, &[]).unwrap(),
+    ].concat();
+    let stream = RwLock::new(&bytes[..]);
+
+    let first = read_tcp_packet(&stream).await.unwrap().unwrap();
+    assert_eq!((first.packet_type(), first.request_id, first.payload.as_slice()), (Some(TcpPacketType::Err), 3, &b"Nope"[..]));
+    let second = read_tcp_packet(&stream).await.unwrap().unwrap();
+    assert_eq!((second.packet_type(), second.request_id, second.payload.len()), (Some(TcpPacketType::Ok), 4, 0));
+    assert!(read_tcp_packet(&stream).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn truncated_packets_are_errors() {
+    let packet = encode_tcp_packet(TcpPacketType::Err, 3, b"Nope").unwrap();
+    let stream = RwLock::new(&packet[..packet.len() - 1]);
+    assert!(read_tcp_packet(&stream).await.is_err());
+
+    let stream = RwLock::new(&packet[..2]);
+    assert!(read_tcp_packet(&stream).await.is_err());
+}
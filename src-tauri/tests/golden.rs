@@ -0,0 +1,176 @@
+//! Golden-frame regression tests for the library layers.
+//!
+//! Each case renders a small stack at fixed `t` values against a fixed `PatternContext` and compares
+//! the frames with those stored in `tests/goldens`. After an intentional visual change, regenerate
+//! the stored frames with:
+//!
+//! ```sh
+//! UPDATE_GOLDENS=1 cargo test --test golden
+//! ```
+//!
+//! and review the diff of the golden files before committing them.
+
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use palette::rgb::Rgb;
+use serde::{Deserialize, Serialize};
+
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame, Pixel, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::component::layer::io_type::DynType;
+use dazzlefruit_lib::pattern_builder::component::layer::layer_stack::LayerStack;
+use dazzlefruit_lib::pattern_builder::library::color::filters::cycle::Cycle;
+use dazzlefruit_lib::pattern_builder::library::color::filters::map_hsl_component::MapHslComponent;
+use dazzlefruit_lib::pattern_builder::library::color::textures::solid_color::SolidColor;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::dual_waves::DualWaves;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::pulse::Pulse;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::simplex_noise::SimplexNoise;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::sparkles::Sparkles;
+use dazzlefruit_lib::pattern_builder::library::transformers::scalar_to_texture::ScalarToTexture;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+use dazzlefruit_lib::test_patterns::AddValue;
+
+const NUM_PIXELS: usize = 24;
+const SEED: u64 = 0;
+const TOLERANCE: f64 = 1e-6;
+const TS: [f64; 8] = [0.0, 0.1, 0.25, 0.5, 1.0, 1.75, 2.5, 7.3];
+
+#[derive(Serialize, Deserialize)]
+struct Golden {
+    num_pixels: usize,
+    seed: u64,
+    ts: Vec<f64>,
+    /// One entry per `t`, holding each pixel's channels.
+    frames: Vec<Vec<Vec<f64>>>,
+}
+
+trait GoldenPixel: Pixel {
+    fn channels(&self) -> Vec<f64>;
+}
+
+impl GoldenPixel for ScalarPixel {
+    fn channels(&self) -> Vec<f64> {
+        vec![*self]
+    }
+}
+
+impl GoldenPixel for ColorPixel {
+    fn channels(&self) -> Vec<f64> {
+        vec![self.red, self.green, self.blue, self.alpha]
+    }
+}
+
+fn pattern_context() -> PatternContext<'static> {
+    let mut ctx = PatternContext::new(NUM_PIXELS, standard_type_mapper());
+    ctx.set_seed(SEED);
+    ctx
+}
+
+fn render<P: GoldenPixel>(mut stack: LayerStack) -> Golden where Frame<P>: DynType {
+    let ctx = pattern_context();
+    stack.reset();
+    let frames = TS.iter()
+        .map(|t| {
+            let frame: Frame<P> = stack.next((), *t, &ctx)
+                .unwrap_or_else(|err| panic!("Failed to evaluate stack: {:?}", err));
+            frame.iter().map(|pixel| pixel.channels()).collect()
+        })
+        .collect();
+    Golden {
+        num_pixels: NUM_PIXELS,
+        seed: SEED,
+        ts: TS.to_vec(),
+        frames,
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("goldens").join(format!("{}.json", name))
+}
+
+fn assert_golden(name: &str, actual: Golden) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDENS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        println!("Updated {}", path.display());
+        return;
+    }
+
+    let contents = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Cannot read {} ({}). Run with UPDATE_GOLDENS=1 to create it.", path.display(), err));
+    let expected: Golden = serde_json::from_str(&contents)
+        .unwrap_or_else(|err| panic!("Cannot parse {}: {}", path.display(), err));
+
+    assert_eq!(expected.num_pixels, actual.num_pixels, "{}: pixel count changed", name);
+    assert_eq!(expected.seed, actual.seed, "{}: seed changed", name);
+    assert_eq!(expected.ts, actual.ts, "{}: t values changed", name);
+    for (frame_index, (expected_frame, actual_frame)) in expected.frames.iter().zip(actual.frames.iter()).enumerate() {
+        let t = actual.ts[frame_index];
+        assert_eq!(expected_frame.len(), actual_frame.len(), "{}: frame length differs at t={}", name, t);
+        for (pixel, (expected_pixel, actual_pixel)) in expected_frame.iter().zip(actual_frame.iter()).enumerate() {
+            let matches = expected_pixel.len() == actual_pixel.len()
+                && expected_pixel.iter().zip(actual_pixel.iter()).all(|(e, a)| (e - a).abs() <= TOLERANCE);
+            assert!(
+                matches,
+                "{}: pixel {} differs at t={}: expected {:?}, got {:?}. If this change is intended, run with UPDATE_GOLDENS=1.",
+                name, pixel, t, expected_pixel, actual_pixel,
+            );
+        }
+    }
+    assert_eq!(expected.frames.len(), actual.frames.len(), "{}: frame count differs", name);
+}
+
+fn stack_of(layers: impl IntoIterator<Item=Layer>) -> LayerStack {
+    let mut stack = LayerStack::new();
+    for layer in layers {
+        stack.push(layer);
+    }
+    stack
+}
+
+/// A red pulse travelling along the strip, as input for the colour filters.
+fn red_pulse() -> LayerStack {
+    let texture = ScalarToTexture::new();
+    texture.texture().write().push(SolidColor::new(Rgb::from_str("#FF0000").unwrap().into()).into_layer());
+    stack_of([Pulse::new(3.0, 6.0, 2.0).into_layer(), texture.into_layer()])
+}
+
+#[test]
+fn pulse() {
+    assert_golden("pulse", render::<ScalarPixel>(stack_of([Pulse::new(2.0, 5.0, 1.5).into_layer()])));
+}
+
+#[test]
+fn dual_waves() {
+    assert_golden("dual_waves", render::<ScalarPixel>(stack_of([DualWaves::new().into_layer()])));
+}
+
+#[test]
+fn simplex_noise() {
+    assert_golden("simplex_noise", render::<ScalarPixel>(stack_of([SimplexNoise::new(1.0).with_seed(7).into_layer()])));
+}
+
+#[test]
+fn sparkles() {
+    assert_golden("sparkles", render::<ScalarPixel>(stack_of([Sparkles::new(0.2, 4.0).with_seed(7).into_layer()])));
+}
+
+#[test]
+fn cycle() {
+    let mut stack = red_pulse();
+    stack.push(Cycle::new(3.0, 2.5, true).into_layer());
+    assert_golden("cycle", render::<ColorPixel>(stack));
+}
+
+#[test]
+fn map_hue() {
+    let map_hue = MapHslComponent::new_hue();
+    map_hue.map().write().push(AddValue::new(90.0).into_layer());
+    let mut stack = red_pulse();
+    stack.push(map_hue.into_layer());
+    assert_golden("map_hue", render::<ColorPixel>(stack));
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use palette::rgb::Rgb;
+use tokio::time::{sleep, timeout};
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use dazzlefruit_lib::pattern_builder::component::layer::layer_stack::LayerStack;
+use dazzlefruit_lib::pattern_builder::component::property::PropView;
+use dazzlefruit_lib::pattern_builder::library::color::textures::solid_color::SolidColor;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::pulse::Pulse;
+use dazzlefruit_lib::pattern_builder::pattern::Pattern;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::{standard_type_mapper, PatternBuilder};
+
+#[derive(Clone)]
+struct Slow;
+
+impl LayerCore for Slow {
+    type Input = ();
+    type Output = Frame<ColorPixel>;
+
+    fn next(&mut self, _input: (), _t: f64, ctx: &PatternContext) -> Frame<ColorPixel> {
+        thread::sleep(Duration::from_millis(3));
+        Frame::empty(ctx.num_pixels())
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        vec![]
+    }
+
+    fn detach(&mut self) {}
+}
+
+#[test]
+fn stack_and_layers_count_their_frames() {
+    let ctx = PatternContext::new(10, standard_type_mapper());
+    let pulse = Pulse::new(2.0, 5.0, 1.0).into_layer();
+    let mut stack = LayerStack::new();
+    stack.push(pulse);
+    for i in 0..5 {
+        let _: Frame<ScalarPixel> = stack.next((), i as f64 / 30.0, &ctx).unwrap();
+    }
+
+    assert_eq!(stack.stats().total_frames(), 5);
+    let view = serde_json::to_value(stack.layer_views().remove(0)).unwrap();
+    assert_eq!(view["stats"]["total_frames"], 5);
+    assert!(view["stats"]["max_ms"].as_f64().unwrap() >= view["stats"]["average_ms"].as_f64().unwrap());
+
+    stack.detach();
+    assert_eq!(stack.stats().total_frames(), 0);
+}
+
+#[tokio::test]
+async fn pattern_stats_put_the_slowest_layer_first() {
+    let pattern_builder = PatternBuilder::new(10, Arc::new(NullEventSink));
+    let pattern = Pattern::new("Slow", pattern_builder.pattern_context(), 30.0);
+    let slow = Layer::new_texture(Slow, LayerTypeInfo::new("Slow"));
+    let slow_id = slow.info().id();
+    pattern.stack().write().push(SolidColor::new(Rgb::from_str("#FF0000").unwrap().into()).into_layer());
+    pattern.stack().write().push(slow);
+
+    let _receiver = pattern.get_frame_receiver();
+    let result = timeout(Duration::from_secs(2), async {
+        while pattern.stats().layers().iter().any(|layer| layer.stats().total_frames < 3) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    }).await;
+    assert!(result.is_ok(), "Pattern did not render");
+
+    let stats = pattern.stats();
+    assert_eq!(stats.layers().len(), 2);
+    assert_eq!(stats.layers()[0].id(), slow_id);
+    assert!(stats.layers()[0].stats().average_ms.unwrap() >= 3.0);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::pattern_builder::component::frame::ScalarPixel;
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::cuboid::BoxMask;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::cylinder::CylinderMask;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::distance::DistanceFalloff;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::plane::PlaneMask;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::sphere::SphereMask;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+/// Renders on a line of 5 pixels, which sit at -1, -0.5, 0, 0.5 and 1 once centered.
+fn render(layer: Layer, t: f64) -> Vec<ScalarPixel> {
+    common::render([layer], t, &PatternContext::new(5, standard_type_mapper()))
+}
+
+#[test]
+fn hard_edged_shapes_cover_their_pixels() {
+    let x = DVec3::new(1.0, 0.0, 0.0);
+    assert_eq!(render(PlaneMask::new(DVec3::zeros(), x).into_layer(), 0.0), vec![0.0, 0.0, 1.0, 1.0, 1.0]);
+    assert_eq!(render(PlaneMask::new(DVec3::zeros(), -x).into_layer(), 0.0), vec![1.0, 1.0, 1.0, 0.0, 0.0]);
+    assert_eq!(render(SphereMask::new(DVec3::zeros(), 0.6).into_layer(), 0.0), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+    assert_eq!(render(BoxMask::new(DVec3::new(0.5, 0.0, 0.0), DVec3::repeat(1.2)).into_layer(), 0.0), vec![0.0, 0.0, 1.0, 1.0, 1.0]);
+
+    let around_y = CylinderMask::new(DVec3::zeros(), DVec3::new(0.0, 1.0, 0.0), 0.5);
+    assert_eq!(render(around_y.into_layer(), 0.0), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+    // Along the line, a short cylinder is cut off by its ends rather than its radius.
+    let along_x = CylinderMask::new(DVec3::zeros(), x, 0.5);
+    *along_x.length().write() = 1.2;
+    assert_eq!(render(along_x.into_layer(), 0.0), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn shapes_move_at_their_velocity() {
+    let plane = PlaneMask::new(DVec3::zeros(), DVec3::new(1.0, 0.0, 0.0));
+    *plane.velocity().write() = DVec3::new(0.25, 0.0, 0.0);
+    assert_eq!(render(plane.into_layer(), 2.0), vec![0.0, 0.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn soft_edges_and_falloff_fade_out() {
+    let sphere = SphereMask::new(DVec3::zeros(), 0.5);
+    *sphere.softness().write() = 0.5;
+    let soft = render(sphere.into_layer(), 0.0);
+    assert_eq!(soft[2], 1.0);
+    assert!((soft[1] - 0.5).abs() < 1e-12);
+    assert_eq!(soft[0], 0.0);
+
+    let falloff = render(DistanceFalloff::new(DVec3::zeros(), 1.0).into_layer(), 0.0);
+    assert_eq!(falloff, vec![0.0, 0.5, 1.0, 0.5, 0.0]);
+
+    // Without falloff, pixels in range are fully on and those beyond stay off.
+    let hard = DistanceFalloff::new(DVec3::zeros(), 1.0);
+    *hard.falloff().write() = 0.0;
+    assert_eq!(render(hard.into_layer(), 0.0), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+}
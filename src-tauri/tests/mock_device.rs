@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use dazzlefruit_lib::event_sink::{AppEvent, EventSink, MemoryEventSink, NullEventSink};
+use dazzlefruit_lib::neopixel_controller::NeopixelController;
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame};
+use dazzlefruit_lib::pattern_builder::component::RandId;
+use dazzlefruit_lib::pico_connection::mock_device::MockPicoDevice;
+use dazzlefruit_lib::pico_connection::packet_types::TcpPacketType;
+use dazzlefruit_lib::pico_connection::{PicoConnection, PicoConnectionHandle};
+
+async fn connect(device: &MockPicoDevice) -> PicoConnectionHandle {
+    connect_with_events(device, Arc::new(NullEventSink)).await
+}
+
+async fn connect_with_events(device: &MockPicoDevice, event_sink: Arc<dyn EventSink>) -> PicoConnectionHandle {
+    PicoConnection::new("127.0.0.1".to_string(), device.tcp_port(), device.udp_port(), event_sink).await
+        .expect("Failed to connect to mock device.")
+}
+
+#[tokio::test]
+async fn hello_is_acknowledged() {
+    let device = MockPicoDevice::start().await.unwrap();
+    let connection = connect(&device).await;
+
+    let response = connection.send_tcp_await_response(TcpPacketType::Hello, &[]).await.unwrap();
+
+    assert_eq!(response, Ok(()));
+    assert_eq!(device.log().tcp_packets.len(), 1);
+    assert_eq!(device.log().tcp_packets[0].0, TcpPacketType::Hello);
+}
+
+#[tokio::test]
+async fn err_response_carries_message() {
+    let device = MockPicoDevice::start().await.unwrap();
+    device.fail_packet_type(TcpPacketType::Hello, "Not today");
+    let connection = connect(&device).await;
+
+    let response = connection.send_tcp_await_response(TcpPacketType::Hello, &[]).await.unwrap();
+
+    assert_eq!(response, Err("Server returned ERR: Not today".to_string()));
+}
+
+#[tokio::test]
+async fn concurrent_responses_go_to_their_requests() {
+    let device = MockPicoDevice::start().await.unwrap();
+    let connection = connect(&device).await;
+    let num_pixels = 10u16.to_be_bytes();
+
+    let (bad_init, hello, good_init) = tokio::join!(
+        connection.send_tcp_await_response(TcpPacketType::Neopixel_Init, &[1]),
+        connection.send_tcp_await_response(TcpPacketType::Hello, &[]),
+        connection.send_tcp_await_response(TcpPacketType::Neopixel_Init, &num_pixels),
+    );
+
+    assert!(bad_init.unwrap().unwrap_err().contains("2 byte pixel count"));
+    assert_eq!(hello.unwrap(), Ok(()));
+    assert_eq!(good_init.unwrap(), Ok(()));
+}
+
+#[tokio::test]
+async fn ping_is_answered() {
+    let device = MockPicoDevice::start().await.unwrap();
+    let connection = connect(&device).await;
+    connection.send_tcp_await_response(TcpPacketType::Hello, &[]).await.unwrap().unwrap();
+
+    device.ping().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert!(device.log().tcp_packets.iter().any(|(packet_type, _)| *packet_type == TcpPacketType::Ping));
+}
+
+#[tokio::test]
+async fn selected_pattern_frames_are_streamed() {
+    let device = MockPicoDevice::start().await.unwrap();
+    let connection = connect(&device).await;
+    let (frame_sender, frame_receiver) = broadcast::channel(10);
+
+    let controller = NeopixelController::new(connection, 3, frame_receiver).await.unwrap();
+    assert_eq!(device.log().neopixel_num_pixels, Some(3));
+
+    let shown: RandId = rand::random();
+    let hidden: RandId = rand::random();
+    controller.show_pattern_id(shown).await;
+    let red = ColorPixel::new(1.0, 0.0, 0.0, 1.0);
+    frame_sender.send((hidden, Frame::from(vec![red; 3]))).unwrap();
+    frame_sender.send((shown, Frame::from(vec![red, ColorPixel::new(0.0, 0.0, 0.0, 1.0)]))).unwrap();
+
+    let frames = device.wait_for_frames(1, Duration::from_secs(2)).await.unwrap();
+    assert_eq!(frames, vec![vec![255, 0, 0, 0, 0, 0, 0, 0, 0]]);
+}
+
+#[tokio::test]
+async fn hang_up_fails_pending_requests() {
+    let device = MockPicoDevice::start().await.unwrap();
+    device.ignore_packet_type(TcpPacketType::Hello);
+    let events = Arc::new(MemoryEventSink::new());
+    let connection = connect_with_events(&device, events.clone()).await;
+
+    let (response, _) = tokio::join!(
+        connection.send_tcp_await_response(TcpPacketType::Hello, &[]),
+        async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            device.hang_up().await.unwrap();
+        },
+    );
+
+    // Fails straight away rather than waiting out the response timeout.
+    assert_eq!(response.unwrap(), Err("Connection closed by the pico.".to_string()));
+    assert!(connection.is_closed());
+    // The close event is emitted just after the pending requests are failed.
+    for _ in 0..50 {
+        if !events.events().is_empty() { break; }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(events.events(), vec![AppEvent::ConnectionClose]);
+    assert!(connection.send_tcp_await_response(TcpPacketType::Hello, &[]).await.unwrap().is_err());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::sync::Arc;
+
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::{fork_properties, view_properties};
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame, Pixel, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use dazzlefruit_lib::pattern_builder::component::layer::io_type::DynType;
+use dazzlefruit_lib::pattern_builder::component::property::PropView;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::neighborhood::blur::Blur;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::neighborhood::edge_detect::EdgeDetect;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::neighborhood::glow::Glow;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::neighborhood::sharpen::Sharpen;
+use dazzlefruit_lib::pattern_builder::pattern_context::neighbors::Topology;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::pattern_context::zone::PixelSelection;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+use common::render;
+
+/// Draws the same pixels every frame.
+#[derive(Clone)]
+struct Fixed<T: Pixel>(Vec<T>);
+
+impl<T> LayerCore for Fixed<T> where T: Pixel, Frame<T>: DynType {
+    type Input = ();
+    type Output = Frame<T>;
+
+    fn next(&mut self, _: (), _t: f64, _ctx: &PatternContext) -> Frame<T> {
+        self.0.clone().into()
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!()
+    }
+
+    fn detach(&mut self) {
+        fork_properties!();
+    }
+}
+
+fn fixed<T>(pixels: Vec<T>) -> Layer where T: Pixel, Frame<T>: DynType {
+    Layer::new_texture(Fixed(pixels), LayerTypeInfo::new("Fixed"))
+}
+
+/// Three pixels where the strip order isn't the layout order: the last pixel sits beside the first.
+fn folded_context() -> PatternContext<'static> {
+    let mut ctx = PatternContext::new(3, standard_type_mapper());
+    ctx.set_position_map(PositionMap::new(vec![
+        Some(DVec3::new(0.0, 0.0, 0.0)),
+        Some(DVec3::new(10.0, 0.0, 0.0)),
+        Some(DVec3::new(1.0, 0.0, 0.0)),
+    ]));
+    ctx
+}
+
+#[test]
+fn neighbor_graphs_follow_the_topology() {
+    let ctx = folded_context();
+    let strip = ctx.neighbors(Topology::Strip);
+    assert_eq!((strip.neighbors(0), strip.neighbors(1), strip.neighbors(2)), (&[1][..], &[0, 2][..], &[1][..]));
+
+    let nearest = ctx.neighbors(Topology::Nearest(1));
+    assert_eq!((nearest.neighbors(0), nearest.neighbors(1), nearest.neighbors(2)), (&[2][..], &[2][..], &[0][..]));
+    assert_eq!(ctx.neighbors(Topology::Nearest(5)).neighbors(1), &[2, 0]);
+
+    // Graphs are built once, and rebuilt when the layout changes.
+    assert!(Arc::ptr_eq(&nearest, &ctx.neighbors(Topology::Nearest(1))));
+    assert!(Arc::ptr_eq(&nearest, &ctx.clone().neighbors(Topology::Nearest(1))));
+    let mut moved = ctx.clone();
+    moved.set_position_map(PositionMap::new_linear(3));
+    assert_eq!(moved.neighbors(Topology::Nearest(1)).neighbors(2), &[1]);
+}
+
+#[test]
+fn derived_contexts_keep_their_graphs_between_frames() {
+    let ctx = folded_context();
+    let nearest = Topology::Nearest(1);
+    let sliced = ctx.slice(0..2).neighbors(nearest);
+    assert_eq!(sliced.neighbors(0), &[1]);
+    assert!(Arc::ptr_eq(&sliced, &ctx.slice(0..2).neighbors(nearest)));
+    assert!(!Arc::ptr_eq(&sliced, &ctx.slice(1..3).neighbors(nearest)));
+
+    let selection = PixelSelection::Pixels(vec![2, 1]);
+    let selected = ctx.select(&selection).neighbors(nearest);
+    assert!(Arc::ptr_eq(&selected, &ctx.select(&selection).neighbors(nearest)));
+
+    // Moving the layout keeps the parent's graphs, while stretching it gets graphs of its own.
+    let moved = ctx.transform_positions(|pos| pos + DVec3::new(5.0, 0.0, 0.0), true).neighbors(nearest);
+    assert!(Arc::ptr_eq(&moved, &ctx.neighbors(nearest)));
+    let stretch = |pos: DVec3| DVec3::new(pos.x * 0.01, 0.0, 0.0);
+    let stretched = ctx.transform_positions(stretch, false).neighbors(nearest);
+    assert!(!Arc::ptr_eq(&stretched, &ctx.neighbors(nearest)));
+    assert!(Arc::ptr_eq(&stretched, &ctx.transform_positions(stretch, false).neighbors(nearest)));
+}
+
+#[test]
+fn filters_mix_with_neighbors() {
+    let strip = PatternContext::new(5, standard_type_mapper());
+    let blurred: Vec<ScalarPixel> = render([fixed(vec![0.0, 0.0, 3.0, 0.0, 0.0]), Blur::<ScalarPixel>::new(Topology::Strip, 1).into_layer()], 0.0, &strip);
+    assert_eq!(blurred, vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+
+    let blurred: Vec<ScalarPixel> = render([fixed(vec![2.0, 0.0, 0.0]), Blur::<ScalarPixel>::new(Topology::Nearest(1), 1).into_layer()], 0.0, &folded_context());
+    assert_eq!(blurred, vec![1.0, 0.0, 1.0]);
+
+    let sharpened: Vec<ScalarPixel> = render([fixed(vec![0.0, 0.0, 3.0, 0.0, 0.0]), Sharpen::<ScalarPixel>::new(Topology::Strip, 1.0).into_layer()], 0.0, &strip);
+    assert_eq!(sharpened, vec![0.0, -1.0, 5.0, -1.0, 0.0]);
+
+    let edges: Vec<ScalarPixel> = render([fixed(vec![0.0, 0.0, 0.0, 1.0, 1.0]), EdgeDetect::<ScalarPixel>::new(Topology::Strip, 3.0).into_layer()], 0.0, &strip);
+    assert_eq!(edges, vec![0.0, 0.0, 1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn filters_work_on_colors() {
+    let ctx = PatternContext::new(3, standard_type_mapper());
+    let black = ColorPixel::new(0.0, 0.0, 0.0, 1.0);
+    let pixels = vec![black, ColorPixel::new(0.9, 0.0, 0.0, 1.0), black];
+
+    let glowing: Vec<ColorPixel> = render([fixed(pixels.clone()), Glow::<ColorPixel>::new(Topology::Strip, 1, 1.0).into_layer()], 0.0, &ctx);
+    let reds: Vec<f64> = glowing.iter().map(|pixel| (pixel.red * 100.0).round() / 100.0).collect();
+    assert_eq!(reds, vec![0.45, 1.2, 0.45]);
+    assert!(glowing.iter().all(|pixel| pixel.alpha == 1.0));
+
+    let sharpened: Vec<ColorPixel> = render([fixed(pixels.clone()), Sharpen::<ColorPixel>::new(Topology::Strip, 2.0).into_layer()], 0.0, &ctx);
+    assert!(sharpened.iter().all(|pixel| pixel.alpha == 1.0));
+
+    let edges: Vec<ScalarPixel> = render([fixed(pixels), EdgeDetect::<ColorPixel>::new(Topology::Strip, 1.0).into_layer()], 0.0, &ctx);
+    let edges: Vec<f64> = edges.iter().map(|edge| (edge * 100.0).round() / 100.0).collect();
+    assert_eq!(edges, vec![0.45, 0.6, 0.45]);
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use dazzlefruit_lib::neopixel_controller::NeopixelController;
+use dazzlefruit_lib::neopixel_controller::output_format::{ChannelOrder, NeopixelOutputFormat};
+use dazzlefruit_lib::neopixel_controller::recording::{FrameRecorder, RecordedFrame, Recording};
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame};
+use dazzlefruit_lib::pattern_builder::component::RandId;
+
+#[test]
+fn format_applies_channel_order_and_full_scale() {
+    let format = NeopixelOutputFormat { channel_order: ChannelOrder::Grb, gamma: 1.0 };
+    let frame = Frame::from(vec![ColorPixel::new(1.0, 0.5, 0.0, 1.0), ColorPixel::new(0.0, 1.0, 1.0, 1.0)]);
+
+    let bytes = format.encode(&frame, 3);
+
+    assert_eq!(bytes, vec![128, 255, 0, 255, 0, 255, 0, 0, 0]);
+    assert_eq!(format.decode(&bytes), vec![(255, 128, 0), (0, 255, 255), (0, 0, 0)]);
+}
+
+#[tokio::test]
+async fn virtual_output_is_recorded_and_replayed() {
+    let format = NeopixelOutputFormat { channel_order: ChannelOrder::Bgr, gamma: 2.2 };
+    let (frame_sender, frame_receiver) = broadcast::channel(10);
+    let controller = NeopixelController::new_virtual(2, format, frame_receiver);
+    let mut output = controller.output_receiver();
+    let path = std::env::temp_dir().join(format!("dazzlefruit-recording-{}.dzfrec", rand::random::<u32>()));
+
+    let pattern_id: RandId = rand::random();
+    controller.show_pattern_id(pattern_id).await;
+    controller.start_recording(FrameRecorder::create(&path, 2, format).unwrap()).unwrap();
+    let colors = [ColorPixel::new(1.0, 0.0, 0.0, 1.0), ColorPixel::new(0.0, 0.5, 1.0, 1.0)];
+    let mut sent = vec![];
+    for color in colors {
+        frame_sender.send((pattern_id, Frame::from(vec![color; 2]))).unwrap();
+        sent.push(output.recv().await.unwrap());
+    }
+    controller.stop_recording().unwrap();
+
+    let recording = Recording::load(&path).unwrap();
+    assert_eq!(recording.num_pixels, 2);
+    assert_eq!(recording.format, format);
+    assert_eq!(recording.frames.iter().map(|frame| frame.bytes.clone()).collect::<Vec<_>>(), sent);
+
+    controller.play_recording(recording).await.unwrap();
+    for bytes in sent {
+        let replayed = tokio::time::timeout(Duration::from_secs(2), output.recv()).await.unwrap().unwrap();
+        assert_eq!(replayed, bytes);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn playback_rejects_mismatched_recording() {
+    let (_, frame_receiver) = broadcast::channel(10);
+    let controller = NeopixelController::new_virtual(4, NeopixelOutputFormat::default(), frame_receiver);
+    let recording = Recording { num_pixels: 5, format: NeopixelOutputFormat::default(), frames: vec![] };
+
+    assert!(controller.play_recording(recording).await.is_err());
+}
+
+#[tokio::test]
+async fn stopping_playback_early_resumes_the_pattern() {
+    let format = NeopixelOutputFormat { channel_order: ChannelOrder::Rgb, gamma: 1.0 };
+    let (frame_sender, frame_receiver) = broadcast::channel(10);
+    let controller = NeopixelController::new_virtual(1, format, frame_receiver);
+    let mut output = controller.output_receiver();
+    let pattern_id: RandId = rand::random();
+    controller.show_pattern_id(pattern_id).await;
+
+    let recording = Recording {
+        num_pixels: 1,
+        format,
+        frames: vec![
+            RecordedFrame { timestamp: Duration::ZERO, bytes: vec![1, 2, 3] },
+            RecordedFrame { timestamp: Duration::from_secs(60), bytes: vec![4, 5, 6] },
+        ],
+    };
+    controller.play_recording(recording).await.unwrap();
+    assert_eq!(output.recv().await.unwrap(), vec![1, 2, 3]);
+    // The interrupted pattern still belongs to the strip, so it can be released if nothing resumes it.
+    assert_eq!(controller.selected_pattern_id().await, Some(pattern_id));
+
+    controller.stop_playback().await;
+    assert_eq!(controller.selected_pattern_id().await, Some(pattern_id));
+    frame_sender.send((pattern_id, Frame::from(vec![ColorPixel::new(1.0, 0.0, 0.0, 1.0)]))).unwrap();
+    let resumed = tokio::time::timeout(Duration::from_secs(2), output.recv()).await.unwrap().unwrap();
+    assert_eq!(resumed, vec![255, 0, 0]);
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame};
+use dazzlefruit_lib::pattern_builder::offline_render::OfflineRenderer;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+use dazzlefruit_lib::test_patterns::test_patterns;
+
+const NUM_PIXELS: usize = 40;
+
+fn render_all(ts: &[f64], seed: Option<u64>) -> Vec<(String, Vec<Frame<ColorPixel>>)> {
+    let pattern_builder = PatternBuilder::new(NUM_PIXELS, Arc::new(NullEventSink));
+    test_patterns(pattern_builder.pattern_context()).iter()
+        .map(|pattern| {
+            let mut renderer = OfflineRenderer::new(pattern);
+            if let Some(seed) = seed {
+                renderer = renderer.with_seed(seed);
+            }
+            (pattern.name(), renderer.render_sequence(ts.iter().copied()).unwrap())
+        })
+        .collect()
+}
+
+fn uneven_ts() -> Vec<f64> {
+    (0..90).map(|i| i as f64 / 30.0 + if i % 7 == 0 { 0.013 } else { 0.0 }).collect()
+}
+
+#[test]
+fn renders_are_identical_across_runs() {
+    let ts = uneven_ts();
+    let first = render_all(&ts, None);
+    let second = render_all(&ts, None);
+
+    for ((name, a), (_, b)) in first.iter().zip(second.iter()) {
+        assert_eq!(a.len(), ts.len());
+        for (i, (frame_a, frame_b)) in a.iter().zip(b.iter()).enumerate() {
+            assert_eq!(**frame_a, **frame_b, "{} differs at frame {}", name, i);
+        }
+    }
+}
+
+#[test]
+fn renderer_starts_from_a_reset_state() {
+    let ts = uneven_ts();
+    let pattern_builder = PatternBuilder::new(NUM_PIXELS, Arc::new(NullEventSink));
+    for pattern in test_patterns(pattern_builder.pattern_context()) {
+        let mut warmed_up = OfflineRenderer::new(&pattern);
+        warmed_up.render_sequence(ts.iter().copied()).unwrap();
+        warmed_up.reset();
+
+        let expected = OfflineRenderer::new(&pattern).render_sequence(ts.iter().copied()).unwrap();
+        let actual = warmed_up.render_sequence(ts.iter().copied()).unwrap();
+        for (i, (a, b)) in expected.iter().zip(actual.iter()).enumerate() {
+            assert_eq!(**a, **b, "{} differs at frame {} after reset", pattern.name(), i);
+        }
+    }
+}
+
+#[test]
+fn seed_changes_stochastic_patterns() {
+    let ts = uneven_ts();
+    let default_seed = render_all(&ts, None);
+    let other_seed = render_all(&ts, Some(12345));
+
+    let (_, a) = default_seed.iter().find(|(name, _)| name == "Pretty Lights").unwrap();
+    let (_, b) = other_seed.iter().find(|(name, _)| name == "Pretty Lights").unwrap();
+    assert!(a.iter().zip(b.iter()).any(|(frame_a, frame_b)| **frame_a != **frame_b));
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::{sleep, timeout};
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame};
+use dazzlefruit_lib::pattern_builder::offline_render::OfflineRenderer;
+use dazzlefruit_lib::pattern_builder::pattern::{LoopRange, Pattern};
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+use dazzlefruit_lib::test_patterns::pretty_light_pattern;
+
+const NUM_PIXELS: usize = 40;
+
+async fn wait_for_t(pattern: &Pattern, t: f64) {
+    let result = timeout(Duration::from_secs(2), async {
+        while (pattern.get_t() - t).abs() > 1e-9 {
+            sleep(Duration::from_millis(5)).await;
+        }
+    }).await;
+    assert!(result.is_ok(), "Pattern did not reach t={}, stuck at t={}", t, pattern.get_t());
+}
+
+/// A pattern paused at t=0. Patterns start out running, so the runner may tick before the pause.
+async fn paused_pattern() -> Pattern {
+    let pattern_builder = PatternBuilder::new(NUM_PIXELS, Arc::new(NullEventSink));
+    let pattern = pretty_light_pattern(pattern_builder.pattern_context());
+    pattern.set_running(false);
+    pattern.seek(0.0).unwrap();
+    wait_for_t(&pattern, 0.0).await;
+    pattern
+}
+
+async fn wait_for_frame(pattern: &Pattern, expected: &Frame<ColorPixel>) {
+    let receiver = pattern.get_frame_receiver();
+    let result = timeout(Duration::from_secs(2), async {
+        while **receiver.borrow() != **expected {
+            sleep(Duration::from_millis(5)).await;
+        }
+    }).await;
+    assert!(result.is_ok(), "Pattern did not render the expected frame at t={}", pattern.get_t());
+}
+
+#[test]
+fn loop_range_wraps_past_its_end() {
+    let loop_range = LoopRange::new(1.0, 3.0).unwrap();
+    assert_eq!(loop_range.wrap(0.5), 0.5);
+    assert_eq!(loop_range.wrap(2.0), 2.0);
+    assert_eq!(loop_range.wrap(3.0), 1.0);
+    assert_eq!(loop_range.wrap(6.5), 2.5);
+    assert!(LoopRange::new(2.0, 2.0).is_err());
+    assert!(LoopRange::new(0.0, f64::INFINITY).is_err());
+}
+
+#[tokio::test]
+async fn seek_resets_stateful_layers() {
+    let pattern = paused_pattern().await;
+    let _receiver = pattern.get_frame_receiver();
+    pattern.set_running(true);
+    sleep(Duration::from_millis(200)).await;
+    pattern.set_running(false);
+
+    pattern.seek(2.0).unwrap();
+    wait_for_t(&pattern, 2.0).await;
+    pattern.step(3).unwrap();
+
+    let stepped_t = 2.0 + 3.0 * (1.0 / 30.0);
+    let expected = OfflineRenderer::new(&pattern).render_sequence([2.0, stepped_t]).unwrap().pop().unwrap();
+    assert!(expected.iter().any(|pixel| pixel.alpha > 0.0));
+    wait_for_t(&pattern, stepped_t).await;
+    wait_for_frame(&pattern, &expected).await;
+}
+
+#[tokio::test]
+async fn step_only_works_while_paused() {
+    let pattern = paused_pattern().await;
+    pattern.step(-3).unwrap();
+    wait_for_t(&pattern, -0.1).await;
+
+    pattern.set_running(true);
+    assert!(pattern.step(1).is_err());
+}
+
+#[tokio::test]
+async fn playback_stays_inside_the_loop_range() {
+    let pattern = paused_pattern().await;
+    pattern.set_loop_range(Some(LoopRange::new(0.0, 0.1).unwrap())).unwrap();
+    assert!(pattern.set_loop_range(Some(LoopRange { start: 1.0, end: 0.0 })).is_err());
+    pattern.set_running(true);
+
+    for _ in 0..10 {
+        sleep(Duration::from_millis(30)).await;
+        let t = pattern.get_t();
+        assert!((0.0..0.1).contains(&t), "t={} is outside the loop", t);
+    }
+
+    pattern.reset_time().unwrap();
+    pattern.set_loop_range(None).unwrap();
+    assert_eq!(pattern.loop_range(), None);
+}
+
+#[tokio::test]
+async fn patterns_only_render_while_subscribed() {
+    let pattern_builder = PatternBuilder::new(NUM_PIXELS, Arc::new(NullEventSink));
+    let pattern = pretty_light_pattern(pattern_builder.pattern_context());
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(pattern.render_time(), None);
+
+    let _receiver = pattern.get_frame_receiver();
+    let result = timeout(Duration::from_secs(2), async {
+        while pattern.render_time().is_none() {
+            sleep(Duration::from_millis(5)).await;
+        }
+    }).await;
+    assert!(result.is_ok(), "Pattern did not render once subscribed");
+}
+
+#[tokio::test]
+async fn paused_pattern_rerenders_after_a_property_change() {
+    let mut pattern = paused_pattern().await;
+    let mut receiver = pattern.get_frame_receiver();
+    pattern.seek(1.0).unwrap();
+    wait_for_t(&pattern, 1.0).await;
+    timeout(Duration::from_secs(2), receiver.changed()).await.unwrap().unwrap();
+    receiver.borrow_and_update();
+
+    let seed_prop_id = pattern.view().generate_property_map().into_values()
+        .find(|prop| prop.prop_type_id() == "seed")
+        .unwrap()
+        .info().id();
+    pattern.try_update_prop(seed_prop_id, "5".to_string()).unwrap();
+
+    assert!(timeout(Duration::from_secs(2), receiver.changed()).await.is_ok(), "No frame after the property change");
+    assert_eq!(pattern.get_t(), 1.0);
+}
+
+#[tokio::test]
+async fn fps_can_change_while_running() {
+    let pattern = paused_pattern().await;
+    assert!(pattern.set_fps(0.0).is_err());
+    pattern.set_fps(10.0).unwrap();
+    assert_eq!(pattern.fps(), 10.0);
+    pattern.set_running(true);
+
+    let mut receiver = pattern.get_frame_receiver();
+    // Let the runner pick up the new rate before counting.
+    sleep(Duration::from_millis(150)).await;
+    receiver.borrow_and_update();
+    let mut frames = 0;
+    let _ = timeout(Duration::from_millis(500), async {
+        while receiver.changed().await.is_ok() {
+            frames += 1;
+        }
+    }).await;
+    assert!((2..=8).contains(&frames), "Rendered {} frames in 500ms at 10fps", frames);
+}
@@ -0,0 +1,79 @@
+use chrono::NaiveTime;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use dazzlefruit_lib::neopixel_controller::transition::{Transition, TransitionKind};
+use dazzlefruit_lib::pattern_builder::component::RandId;
+use dazzlefruit_lib::playlist::{ActiveHours, Playlist, PlaylistEntry};
+
+fn time(hour: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+}
+
+/// Two daytime entries and a calm one that only plays from 22:00 to 06:00.
+fn day_and_night() -> Playlist {
+    Playlist {
+        entries: vec![
+            PlaylistEntry::new(rand::random(), 60.0).with_active_hours(time(6), time(22)),
+            PlaylistEntry::new(rand::random(), 30.0).with_active_hours(time(6), time(22)),
+            PlaylistEntry::new(rand::random(), 600.0).with_active_hours(time(22), time(6)),
+        ],
+        shuffle: false,
+        transition: Transition::new(TransitionKind::Crossfade, 2.0).unwrap(),
+    }
+}
+
+#[test]
+fn active_hours_can_run_past_midnight() {
+    let night = ActiveHours { start: time(22), end: time(6) };
+    assert!(night.contains(time(23)));
+    assert!(night.contains(time(2)));
+    assert!(!night.contains(time(6)));
+    assert!(!night.contains(time(12)));
+}
+
+#[test]
+fn entries_play_in_order_within_their_hours() {
+    let playlist = day_and_night();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    assert_eq!(playlist.next_entry(None, time(12), &mut rng), Some(0));
+    assert_eq!(playlist.next_entry(Some(0), time(12), &mut rng), Some(1));
+    assert_eq!(playlist.next_entry(Some(1), time(12), &mut rng), Some(0));
+    assert_eq!(playlist.next_entry(Some(1), time(23), &mut rng), Some(2));
+    assert_eq!(playlist.next_entry(Some(2), time(23), &mut rng), Some(2));
+
+    assert_eq!(Playlist::default().next_entry(None, time(12), &mut rng), None);
+}
+
+#[test]
+fn shuffle_avoids_repeating_an_entry() {
+    let mut playlist = day_and_night();
+    playlist.shuffle = true;
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let mut current = Some(0);
+    for _ in 0..20 {
+        let next = playlist.next_entry(current, time(12), &mut rng);
+        assert!(next.is_some() && next != current && next != Some(2));
+        current = next;
+    }
+}
+
+#[test]
+fn saved_playlists_refer_to_patterns_by_name() {
+    let playlist = day_and_night();
+    let names = ["Waves", "Sparkles", "Calm"];
+    let path = std::env::temp_dir().join(format!("dazzlefruit-playlist-{}.json", rand::random::<u32>()));
+    let name_of = |id: RandId| playlist.entries.iter().position(|entry| entry.pattern_id == id).map(|i| names[i].to_string());
+    playlist.save(&path, name_of).unwrap();
+
+    let new_ids: Vec<RandId> = names.iter().map(|_| rand::random()).collect();
+    let loaded = Playlist::load(&path, |name| names.iter().position(|n| *n == name).map(|i| new_ids[i])).unwrap();
+    assert_eq!(loaded.entries.iter().map(|entry| entry.pattern_id).collect::<Vec<_>>(), new_ids);
+    assert_eq!(loaded.entries[2].active_hours, playlist.entries[2].active_hours);
+    assert_eq!(loaded.transition, playlist.transition);
+
+    assert!(Playlist::load(&path, |_| None).is_err());
+    std::fs::remove_file(path).unwrap();
+}
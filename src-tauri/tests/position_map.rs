@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::generator::LayoutGenerator;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+
+fn positions(generator: LayoutGenerator) -> Vec<DVec3> {
+    generator.generate().unwrap().iter().map(|pos| pos.unwrap()).collect()
+}
+
+#[test]
+fn serpentine_matrix_reverses_every_other_row() {
+    let matrix = positions(LayoutGenerator::Matrix { width: 3, height: 2, spacing: 2.0, serpentine: true, column_major: false });
+    let xy: Vec<(f64, f64)> = matrix.iter().map(|pos| (pos.x, pos.y)).collect();
+    assert_eq!(xy, vec![(0.0, 0.0), (2.0, 0.0), (4.0, 0.0), (4.0, 2.0), (2.0, 2.0), (0.0, 2.0)]);
+
+    let columns = positions(LayoutGenerator::Matrix { width: 2, height: 2, spacing: 1.0, serpentine: true, column_major: true });
+    assert_eq!(columns[2], DVec3::new(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn round_layouts_keep_their_radius() {
+    for pos in positions(LayoutGenerator::Ring { num_pixels: 12, radius: 5.0 }) {
+        assert!((pos.magnitude() - 5.0).abs() < 1e-9);
+    }
+
+    let spiral = positions(LayoutGenerator::Spiral { num_pixels: 20, turns: 3.0, inner_radius: 1.0, outer_radius: 4.0 });
+    assert!((spiral[0].magnitude() - 1.0).abs() < 1e-9);
+    assert!((spiral[19].magnitude() - 4.0).abs() < 1e-9);
+
+    let tree = positions(LayoutGenerator::Helix { num_pixels: 50, turns: 5.0, height: 2.0, bottom_radius: 1.0, top_radius: 0.0 });
+    assert_eq!(tree[0].y, 0.0);
+    assert_eq!(tree[49].y, 2.0);
+    assert!(tree[49].xz().magnitude() < 1e-9);
+}
+
+#[test]
+fn polyline_places_pixels_along_each_segment() {
+    let line = positions(LayoutGenerator::Polyline {
+        points: vec![[0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [4.0, 2.0, 0.0]],
+        segment_pixels: vec![4, 2],
+    });
+    assert_eq!(line.len(), 6);
+    assert_eq!(line[0], DVec3::new(0.5, 0.0, 0.0));
+    assert_eq!(line[5], DVec3::new(4.0, 1.5, 0.0));
+
+    let mismatched = LayoutGenerator::Polyline { points: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]], segment_pixels: vec![1, 2] };
+    assert!(mismatched.generate().is_err());
+}
+
+#[test]
+fn edited_maps_save_and_load() {
+    let pattern_builder = PatternBuilder::new(4, Arc::new(NullEventSink));
+    pattern_builder.generate_position_map(&LayoutGenerator::Ring { num_pixels: 4, radius: 1.0 }).unwrap();
+    pattern_builder.set_pixel_position(1, Some(DVec3::new(7.0, 8.0, 9.0))).unwrap();
+    pattern_builder.set_pixel_position(2, None).unwrap();
+    assert!(pattern_builder.set_pixel_position(4, None).is_err());
+
+    let path = std::env::temp_dir().join(format!("dazzlefruit-positions-{}.json", rand::random::<u32>()));
+    pattern_builder.save_position_map(&path).unwrap();
+    let reloaded = PatternBuilder::new(4, Arc::new(NullEventSink));
+    reloaded.load_position_map(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let ctx = reloaded.pattern_context().borrow().clone();
+    assert_eq!(ctx.position_map().pos(1), Some(DVec3::new(7.0, 8.0, 9.0)));
+    assert_eq!(ctx.position_map().pos(2), None);
+    assert_eq!(ctx.position_map().len(), 4);
+}
+
+#[test]
+fn pixel_count_follows_the_layout() {
+    let pattern_builder = PatternBuilder::new(10, Arc::new(NullEventSink));
+    let mut pattern_context = pattern_builder.pattern_context();
+
+    // The default line of pixels stretches to whatever the strip needs.
+    pattern_builder.set_num_pixels(20).unwrap();
+    assert!(pattern_context.has_changed().unwrap());
+    let ctx = pattern_context.borrow_and_update().clone();
+    assert_eq!(ctx.num_pixels(), 20);
+    assert_eq!(ctx.position_map().pos(19), Some(DVec3::new(19.0, 0.0, 0.0)));
+
+    pattern_builder.generate_position_map(&LayoutGenerator::Ring { num_pixels: 12, radius: 1.0 }).unwrap();
+    assert_eq!(pattern_context.borrow_and_update().num_pixels(), 12);
+
+    // A loaded layout can't be stretched.
+    assert!(pattern_builder.set_num_pixels(20).is_err());
+    pattern_builder.set_num_pixels(12).unwrap();
+    assert!(!pattern_context.has_changed().unwrap());
+}
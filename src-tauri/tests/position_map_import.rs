@@ -0,0 +1,86 @@
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::import::{import_position_map, ImportFormat, Normalization};
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+
+fn positions(position_map: &PositionMap) -> Vec<Option<DVec3>> {
+    position_map.iter().cloned().collect()
+}
+
+#[test]
+fn csv_rows_are_placed_by_index() {
+    let csv = "index,x,y,z\n2,1.5,2,3\n0,0,0,0\n# pixel 1 was never mapped\n3,4,5,\n";
+    let position_map = ImportFormat::Csv.parse(csv).unwrap();
+    assert_eq!(positions(&position_map), vec![
+        Some(DVec3::new(0.0, 0.0, 0.0)),
+        None,
+        Some(DVec3::new(1.5, 2.0, 3.0)),
+        Some(DVec3::new(4.0, 5.0, 0.0)),
+    ]);
+
+    let headerless = ImportFormat::Csv.parse("1,2\n3,4\n").unwrap();
+    assert_eq!(headerless.pos(1), Some(DVec3::new(3.0, 4.0, 0.0)));
+
+    assert!(ImportFormat::Csv.parse("index,x,y\n0,a,1\n").is_err());
+    assert!(ImportFormat::Csv.parse("index,x,y\n4000000000,0,1\n").is_err());
+}
+
+#[test]
+fn xlights_models_use_node_numbers() {
+    let grid = r#"<?xml version="1.0"?><custommodel name="Star" parm1="3" parm2="2" CustomModel="1,,2;,3,|,4,"/>"#;
+    let position_map = ImportFormat::XLights.parse(grid).unwrap();
+    assert_eq!(positions(&position_map), vec![
+        Some(DVec3::new(0.0, 0.0, 0.0)),
+        Some(DVec3::new(2.0, 0.0, 0.0)),
+        Some(DVec3::new(1.0, -1.0, 0.0)),
+        Some(DVec3::new(1.0, 0.0, 1.0)),
+    ]);
+
+    let compressed = r#"<custommodel CustomModelCompressed="2,0,0;1,1,3;1,3,3"/>"#;
+    let position_map = ImportFormat::XLights.parse(compressed).unwrap();
+    assert_eq!(position_map.pos(0), Some(DVec3::new(3.0, -2.0, 0.0)));
+    assert_eq!(position_map.pos(1), Some(DVec3::new(0.0, 0.0, 0.0)));
+}
+
+#[test]
+fn json_accepts_mapping_tool_exports() {
+    let pairs = ImportFormat::Json.parse(r#"[[1, 2], {"x": 3, "y": 4, "z": 5}, null]"#).unwrap();
+    assert_eq!(positions(&pairs), vec![Some(DVec3::new(1.0, 2.0, 0.0)), Some(DVec3::new(3.0, 4.0, 5.0)), None]);
+
+    let ledmap = ImportFormat::Json.parse(r#"{"width": 2, "height": 2, "map": [1, -1, 0, 2]}"#).unwrap();
+    assert_eq!(positions(&ledmap), vec![
+        Some(DVec3::new(0.0, -1.0, 0.0)),
+        Some(DVec3::new(0.0, 0.0, 0.0)),
+        Some(DVec3::new(1.0, -1.0, 0.0)),
+    ]);
+
+    assert!(ImportFormat::Json.parse(r#"{"map": [0, 4000000000]}"#).is_err());
+}
+
+#[test]
+fn unknown_extensions_are_read_as_json() {
+    let path = std::env::temp_dir().join(format!("dazzlefruit-import-{}.map", rand::random::<u32>()));
+    std::fs::write(&path, "[[1, 2], null]").unwrap();
+    let position_map = import_position_map(&path, None, &Normalization::default());
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(positions(&position_map.unwrap()), vec![Some(DVec3::new(1.0, 2.0, 0.0)), None]);
+}
+
+#[test]
+fn normalization_centres_and_fits_the_layout() {
+    let path = std::env::temp_dir().join(format!("dazzlefruit-import-{}.csv", rand::random::<u32>()));
+    std::fs::write(&path, "x,y,z\n100,0,0\n300,100,0\n,,\n").unwrap();
+    let normalization = Normalization { center: true, fit_size: Some(2.0), unit_scale: 1.0 };
+    let position_map = import_position_map(&path, None, &normalization).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(positions(&position_map), vec![
+        Some(DVec3::new(-1.0, -0.5, 0.0)),
+        Some(DVec3::new(1.0, 0.5, 0.0)),
+        None,
+    ]);
+
+    let millimetres = Normalization { unit_scale: 0.001, ..Default::default() }.apply(&position_map);
+    assert_eq!(millimetres.pos(1), Some(DVec3::new(0.001, 0.0005, 0.0)));
+}
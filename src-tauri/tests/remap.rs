@@ -0,0 +1,87 @@
+mod common;
+
+use dazzlefruit_lib::{fork_properties, view_properties};
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame, ScalarPixel};
+use dazzlefruit_lib::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use dazzlefruit_lib::pattern_builder::component::property::PropView;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::remap::kaleidoscope::Kaleidoscope;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::remap::mirror::Mirror;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::remap::offset::Offset;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::remap::reverse::Reverse;
+use dazzlefruit_lib::pattern_builder::library::generic::filters::remap::stretch::Stretch;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+use common::render;
+
+/// Gives each pixel its own index, so a remapped frame shows where every pixel was read from.
+#[derive(Clone)]
+struct IndexRamp;
+
+impl LayerCore for IndexRamp {
+    type Input = ();
+    type Output = Frame<ScalarPixel>;
+
+    fn next(&mut self, _: (), _t: f64, ctx: &PatternContext) -> Frame<ScalarPixel> {
+        ctx.map_pixels(|i, _| i as f64)
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!()
+    }
+
+    fn detach(&mut self) {
+        fork_properties!();
+    }
+}
+
+/// Lights only the first pixel, in red.
+#[derive(Clone)]
+struct FirstPixel;
+
+impl LayerCore for FirstPixel {
+    type Input = ();
+    type Output = Frame<ColorPixel>;
+
+    fn next(&mut self, _: (), _t: f64, ctx: &PatternContext) -> Frame<ColorPixel> {
+        ctx.map_pixels(|i, _| if i == 0 { ColorPixel::new(1.0, 0.0, 0.0, 1.0) } else { ColorPixel::new(0.0, 0.0, 0.0, 1.0) })
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!()
+    }
+
+    fn detach(&mut self) {
+        fork_properties!();
+    }
+}
+
+fn render_scalar(num_pixels: usize, filter: Layer, t: f64) -> Vec<ScalarPixel> {
+    render([Layer::new_texture(IndexRamp, LayerTypeInfo::new("Index Ramp")), filter], t, &PatternContext::new(num_pixels, standard_type_mapper()))
+}
+
+#[test]
+fn remaps_rearrange_pixels() {
+    assert_eq!(render_scalar(4, Reverse::<ScalarPixel>::new().into_layer(), 0.0), vec![3.0, 2.0, 1.0, 0.0]);
+    assert_eq!(render_scalar(5, Mirror::<ScalarPixel>::new(0.5).into_layer(), 0.0), vec![0.0, 1.0, 2.0, 1.0, 0.0]);
+    assert_eq!(render_scalar(8, Kaleidoscope::<ScalarPixel>::new(2.0).into_layer(), 0.0), vec![0.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, 0.0]);
+    assert_eq!(render_scalar(8, Kaleidoscope::<ScalarPixel>::new(4.0).into_layer(), 0.0), vec![0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn fractional_remaps_mix_neighbouring_pixels() {
+    assert_eq!(render_scalar(4, Offset::<ScalarPixel>::new(1.5, 0.0, false).into_layer(), 0.0), vec![0.0, 0.0, 0.5, 1.5]);
+    assert_eq!(render_scalar(4, Offset::<ScalarPixel>::new(1.5, 0.0, true).into_layer(), 0.0), vec![2.5, 1.5, 0.5, 1.5]);
+    assert_eq!(render_scalar(4, Offset::<ScalarPixel>::new(0.0, 1.0, true).into_layer(), 1.0), vec![3.0, 0.0, 1.0, 2.0]);
+    assert_eq!(render_scalar(4, Stretch::<ScalarPixel>::new(2.0, 0.0).into_layer(), 0.0), vec![0.0, 0.5, 1.0, 1.5]);
+    assert_eq!(render_scalar(4, Stretch::<ScalarPixel>::new(0.5, 0.0).into_layer(), 0.0), vec![0.0, 2.0, 0.0, 0.0]);
+}
+
+#[test]
+fn remaps_work_on_colors() {
+    let ctx = PatternContext::new(4, standard_type_mapper());
+    let layers = [Layer::new_texture(FirstPixel, LayerTypeInfo::new("First Pixel")), Offset::<ColorPixel>::new(0.5, 0.0, true).into_layer(), Reverse::<ColorPixel>::new().into_layer()];
+    let frame: Vec<ColorPixel> = render(layers, 0.0, &ctx);
+    let reds: Vec<f64> = frame.iter().map(|pixel| pixel.red).collect();
+    assert_eq!(reds, vec![0.0, 0.0, 0.5, 0.5]);
+}
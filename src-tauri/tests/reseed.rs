@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::pattern_builder::component::frame::ColorPixel;
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::library::color::textures::solid_color::SolidColor;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::simplex_noise::SimplexNoise;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::sparkles::Sparkles;
+use dazzlefruit_lib::pattern_builder::library::transformers::scalar_to_texture::ScalarToTexture;
+use dazzlefruit_lib::pattern_builder::offline_render::OfflineRenderer;
+use dazzlefruit_lib::pattern_builder::pattern::Pattern;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+
+const NUM_PIXELS: usize = 32;
+
+/// A pattern that shows `layer` in white.
+fn white_pattern(layer: Layer) -> Pattern {
+    let pattern_builder = PatternBuilder::new(NUM_PIXELS, Arc::new(NullEventSink));
+    let pattern = Pattern::new("Seeded", pattern_builder.pattern_context(), 30.0);
+    let texture = ScalarToTexture::new();
+    texture.texture().write().push(SolidColor::new(ColorPixel::new(1.0, 1.0, 1.0, 1.0)).into_layer());
+    pattern.stack().write().push(layer);
+    pattern.stack().write().push(texture.into_layer());
+    pattern
+}
+
+fn render(pattern: &Pattern) -> Vec<Vec<ColorPixel>> {
+    OfflineRenderer::new(pattern).render_frames(0.0, 0.25, 8).unwrap()
+        .into_iter()
+        .map(Vec::from)
+        .collect()
+}
+
+fn assert_reseed_changes_output(layer: impl Fn() -> Layer) {
+    let mut pattern = white_pattern(layer());
+    let before = render(&pattern);
+    // A fixed seed reproduces the same frames.
+    assert_eq!(before, render(&white_pattern(layer())));
+
+    pattern.reseed();
+    assert_ne!(before, render(&pattern));
+}
+
+#[test]
+fn reseeding_changes_sparkles() {
+    assert_reseed_changes_output(|| Sparkles::new(2.0, 4.0).with_seed(7).into_layer());
+}
+
+#[test]
+fn reseeding_changes_simplex_noise() {
+    assert_reseed_changes_output(|| SimplexNoise::new(1.0).with_seed(7).into_layer());
+}
+
+#[test]
+fn random_seed_update_picks_a_new_seed() {
+    let layer = SimplexNoise::new(1.0).with_seed(7);
+    let seed = layer.seed().clone();
+    let mut pattern = white_pattern(layer.into_layer());
+    let before = render(&pattern);
+
+    let seed_id = pattern.view().generate_property_map().into_iter()
+        .find(|(_, property)| property.prop_type_id() == "seed")
+        .map(|(id, _)| id)
+        .unwrap();
+    pattern.try_update_prop(seed_id, "\"random\"".to_string()).unwrap();
+    assert_ne!(*seed.read(), 7);
+    assert_ne!(before, render(&pattern));
+
+    pattern.try_update_prop(seed_id, "7".to_string()).unwrap();
+    assert_eq!(before, render(&pattern));
+}
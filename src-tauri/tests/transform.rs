@@ -0,0 +1,56 @@
+mod common;
+
+use nalgebra_glm::DVec3;
+
+use dazzlefruit_lib::pattern_builder::component::frame::ScalarPixel;
+use dazzlefruit_lib::pattern_builder::component::layer::Layer;
+use dazzlefruit_lib::pattern_builder::library::core::transform::Transform;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::plane::PlaneMask;
+use dazzlefruit_lib::pattern_builder::library::scalar::textures::masks::sphere::SphereMask;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::standard_type_mapper;
+
+/// Renders on a line of 5 pixels, like the mask tests, rounding so soft edges compare exactly.
+fn render(layer: Layer, t: f64) -> Vec<ScalarPixel> {
+    let frame: Vec<ScalarPixel> = common::render([layer], t, &PatternContext::new(5, standard_type_mapper()));
+    frame.into_iter().map(f64::round).collect()
+}
+
+fn transformed(layer: Layer) -> Transform<ScalarPixel> {
+    let transform = Transform::new();
+    transform.stack().write().push(layer);
+    transform
+}
+
+#[test]
+fn translation_moves_the_content() {
+    let dot = || SphereMask::new(DVec3::zeros(), 0.3).into_layer();
+    assert_eq!(render(transformed(dot()).into_layer(), 0.0), vec![0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    let moved = transformed(dot());
+    *moved.translation().write() = DVec3::new(0.5, 0.0, 0.0);
+    assert_eq!(render(moved.into_layer(), 0.0), vec![0.0, 0.0, 0.0, 1.0, 0.0]);
+
+    let moving = transformed(dot());
+    *moving.velocity().write() = DVec3::new(-0.25, 0.0, 0.0);
+    assert_eq!(render(moving.into_layer(), 4.0), vec![1.0, 0.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn rotation_spins_around_the_middle_of_the_layout() {
+    let right_half = || PlaneMask::new(DVec3::new(0.25, 0.0, 0.0), DVec3::new(1.0, 0.0, 0.0)).into_layer();
+    let turned = transformed(right_half());
+    *turned.rotation().write() = DVec3::new(0.0, 0.0, 180.0);
+    assert_eq!(render(turned.into_layer(), 0.0), vec![1.0, 1.0, 0.0, 0.0, 0.0]);
+
+    let spinning = transformed(right_half());
+    *spinning.rotation_speed().write() = DVec3::new(0.0, 90.0, 0.0);
+    assert_eq!(render(spinning.into_layer(), 2.0), vec![1.0, 1.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn scale_stretches_the_content() {
+    let stretched = transformed(SphereMask::new(DVec3::zeros(), 0.3).into_layer());
+    *stretched.scale().write() = DVec3::new(2.0, 1.0, 1.0);
+    assert_eq!(render(stretched.into_layer(), 0.0), vec![0.0, 1.0, 1.0, 1.0, 0.0]);
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::neopixel_controller::NeopixelController;
+use dazzlefruit_lib::neopixel_controller::output_format::{ChannelOrder, NeopixelOutputFormat};
+use dazzlefruit_lib::neopixel_controller::transition::{Axis, Transition, TransitionKind};
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame};
+use dazzlefruit_lib::pattern_builder::component::RandId;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+use dazzlefruit_lib::test_patterns::test_patterns;
+
+const RED: ColorPixel = ColorPixel::new(1.0, 0.0, 0.0, 1.0);
+const BLUE: ColorPixel = ColorPixel::new(0.0, 0.0, 1.0, 1.0);
+
+fn solid(color: ColorPixel, num_pixels: usize) -> Frame<ColorPixel> {
+    Frame::from(vec![color; num_pixels])
+}
+
+#[test]
+fn crossfade_and_fade_through_black_mix_evenly() {
+    let crossfade = Transition::new(TransitionKind::Crossfade, 1.0).unwrap();
+    let mixed = crossfade.mix(solid(RED, 2), solid(BLUE, 2), 0.5, &[]);
+    assert_eq!(*mixed, vec![ColorPixel::new(0.5, 0.0, 0.5, 1.0); 2]);
+
+    let fade = Transition::new(TransitionKind::FadeThroughBlack, 1.0).unwrap();
+    assert_eq!(*fade.mix(solid(RED, 2), solid(BLUE, 2), 0.5, &[]), vec![ColorPixel::new(0.0, 0.0, 0.0, 1.0); 2]);
+    assert_eq!(*fade.mix(solid(RED, 2), solid(BLUE, 2), 0.25, &[]), vec![ColorPixel::new(0.5, 0.0, 0.0, 1.0); 2]);
+
+    assert!(Transition::new(TransitionKind::Crossfade, -1.0).is_err());
+    assert!(Transition::new(TransitionKind::Crossfade, f64::NAN).is_err());
+    assert!(Transition::new(TransitionKind::Crossfade, 1e300).is_err());
+}
+
+#[test]
+fn wipe_sweeps_along_the_axis() {
+    let wipe = Transition::new(TransitionKind::Wipe { axis: Axis::X }, 1.0).unwrap();
+    let coords: Vec<Option<f64>> = (0..10).map(|i| Some(i as f64 / 9.0)).collect();
+
+    let mixed = wipe.mix(solid(RED, 10), solid(BLUE, 10), 0.5, &coords);
+    assert_eq!(mixed[0], BLUE);
+    assert_eq!(mixed[9], RED);
+    assert_eq!(*wipe.mix(solid(RED, 10), solid(BLUE, 10), 1.0, &coords), vec![BLUE; 10]);
+}
+
+#[tokio::test]
+async fn controller_mixes_both_patterns_until_the_transition_ends() {
+    let format = NeopixelOutputFormat { channel_order: ChannelOrder::Rgb, gamma: 1.0 };
+    let (frame_sender, frame_receiver) = broadcast::channel(10);
+    let controller = NeopixelController::new_virtual(2, format, frame_receiver);
+    let mut output = controller.output_receiver();
+    let (old_id, new_id): (RandId, RandId) = (rand::random(), rand::random());
+
+    controller.show_pattern_id(old_id).await;
+    frame_sender.send((old_id, solid(RED, 2))).unwrap();
+    assert_eq!(output.recv().await.unwrap(), vec![255, 0, 0, 255, 0, 0]);
+
+    let transition = Transition::new(TransitionKind::Crossfade, 0.3).unwrap();
+    controller.transition_to(Some(new_id), transition, &PositionMap::new_linear(2)).await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    frame_sender.send((new_id, solid(BLUE, 2))).unwrap();
+    let mixed = output.recv().await.unwrap();
+    assert!(mixed[0] > 0 && mixed[2] > 0, "Expected both patterns in {:?}", mixed);
+
+    // Neither pattern sends another frame, so the controller must finish the transition itself.
+    let last = timeout(Duration::from_secs(2), output.recv()).await.unwrap().unwrap();
+    assert_eq!(last, vec![0, 0, 255, 0, 0, 255]);
+    frame_sender.send((old_id, solid(RED, 2))).unwrap();
+    frame_sender.send((new_id, solid(BLUE, 2))).unwrap();
+    assert_eq!(output.recv().await.unwrap(), vec![0, 0, 255, 0, 0, 255]);
+}
+
+#[tokio::test]
+async fn releasing_the_controller_unwatches_its_pattern() {
+    let mut pattern_builder = PatternBuilder::new(2, Arc::new(NullEventSink));
+    let pattern = test_patterns(pattern_builder.pattern_context()).into_iter().next().unwrap();
+    let pattern_id = pattern.id();
+    pattern_builder.load_pattern(pattern);
+    let controller = NeopixelController::new_virtual(2, NeopixelOutputFormat::default(), pattern_builder.pattern_update_receiver());
+
+    pattern_builder.watch_pattern(pattern_id).unwrap();
+    controller.show_pattern_id(pattern_id).await;
+    assert_eq!(pattern_builder.watcher_count(pattern_id), Some(1));
+
+    controller.release(&mut pattern_builder).await;
+    assert_eq!(pattern_builder.watcher_count(pattern_id), Some(0));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::sync::Arc;
+
+use dazzlefruit_lib::event_sink::NullEventSink;
+use dazzlefruit_lib::{fork_properties, view_properties};
+use dazzlefruit_lib::pattern_builder::component::frame::{ColorPixel, Frame, Pixel};
+use dazzlefruit_lib::pattern_builder::component::layer::{Layer, LayerCore, LayerTypeInfo};
+use dazzlefruit_lib::pattern_builder::component::property::PropView;
+use dazzlefruit_lib::pattern_builder::library::core::zone::Zone;
+use dazzlefruit_lib::pattern_builder::pattern_context::position_map::PositionMap;
+use dazzlefruit_lib::pattern_builder::pattern_context::zone::PixelSelection;
+use dazzlefruit_lib::pattern_builder::pattern_context::PatternContext;
+use dazzlefruit_lib::pattern_builder::PatternBuilder;
+
+use common::render;
+
+/// Fades from black to red across whatever pixels it's given, to show what each layer sees.
+#[derive(Clone)]
+struct IndexGradient;
+
+impl LayerCore for IndexGradient {
+    type Input = ();
+    type Output = Frame<ColorPixel>;
+
+    fn next(&mut self, _: (), _t: f64, ctx: &PatternContext) -> Frame<ColorPixel> {
+        let num_pixels = ctx.num_pixels();
+        ctx.map_pixels(|i, _| ColorPixel::new(i as f64 / num_pixels as f64, 0.0, 0.0, 1.0))
+    }
+
+    fn view_properties(&self) -> Vec<PropView> {
+        view_properties!()
+    }
+
+    fn detach(&mut self) {
+        fork_properties!();
+    }
+}
+
+fn zone_layer(name: &str) -> Layer {
+    let zone = Zone::new(name);
+    zone.stack().write().push(Layer::new_texture(IndexGradient, LayerTypeInfo::new("Index Gradient")));
+    zone.into_layer()
+}
+
+fn reds(frame: &[ColorPixel]) -> Vec<Option<f64>> {
+    frame.iter().map(|pixel| (pixel.alpha > 0.0).then_some(pixel.red)).collect()
+}
+
+fn render_zone(pattern_builder: &PatternBuilder, name: &str) -> Vec<ColorPixel> {
+    render([zone_layer(name)], 0.0, &pattern_builder.pattern_context().borrow())
+}
+
+#[test]
+fn zone_layers_draw_only_into_their_zone() {
+    let pattern_builder = PatternBuilder::new(8, Arc::new(NullEventSink));
+    pattern_builder.set_zone("roofline", PixelSelection::Range { start: 2, end: 6 }).unwrap();
+    pattern_builder.set_zone("left window", PixelSelection::Pixels(vec![7, 0])).unwrap();
+
+    assert_eq!(reds(&render_zone(&pattern_builder, "roofline")), vec![None, None, Some(0.0), Some(0.25), Some(0.5), Some(0.75), None, None]);
+    assert_eq!(reds(&render_zone(&pattern_builder, "left window")), vec![Some(0.5), None, None, None, None, None, None, Some(0.0)]);
+
+    let missing = render_zone(&pattern_builder, "porch");
+    assert_eq!(missing.len(), 8);
+    assert!(missing.iter().all(|pixel| *pixel == ColorPixel::empty()));
+}
+
+#[test]
+fn zones_must_fit_the_layout() {
+    let pattern_builder = PatternBuilder::new(8, Arc::new(NullEventSink));
+    assert!(pattern_builder.set_zone("too long", PixelSelection::Range { start: 4, end: 9 }).is_err());
+    assert!(pattern_builder.set_zone("backwards", PixelSelection::Range { start: 4, end: 4 }).is_err());
+    assert!(pattern_builder.set_zone("stray", PixelSelection::Pixels(vec![1, 8])).is_err());
+    assert!(pattern_builder.set_zone(" ", PixelSelection::Pixels(vec![1])).is_err());
+    assert!(pattern_builder.remove_zone("never added").is_err());
+    assert!(pattern_builder.zones().is_empty());
+}
+
+#[test]
+fn zones_are_saved_with_the_layout() {
+    let pattern_builder = PatternBuilder::new(8, Arc::new(NullEventSink));
+    pattern_builder.set_zone("roofline", PixelSelection::Range { start: 0, end: 4 }).unwrap();
+    pattern_builder.set_zone("door", PixelSelection::Pixels(vec![5, 6])).unwrap();
+    pattern_builder.remove_zone("door").unwrap();
+
+    let path = std::env::temp_dir().join(format!("dazzlefruit-zones-{}.json", rand::random::<u32>()));
+    pattern_builder.save_position_map(&path).unwrap();
+    let reloaded = PatternBuilder::new(8, Arc::new(NullEventSink));
+    reloaded.load_position_map(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(reloaded.zones(), pattern_builder.zones());
+    assert_eq!(reloaded.zones().len(), 1);
+}
+
+#[test]
+fn zones_follow_layout_changes() {
+    let pattern_builder = PatternBuilder::new(8, Arc::new(NullEventSink));
+    pattern_builder.set_zone("start", PixelSelection::Range { start: 0, end: 4 }).unwrap();
+    pattern_builder.set_zone("end", PixelSelection::Pixels(vec![6, 7])).unwrap();
+
+    // Shrinking the default layout drops the zones that no longer fit.
+    pattern_builder.set_num_pixels(5).unwrap();
+    assert_eq!(pattern_builder.zones().keys().collect::<Vec<_>>(), vec!["start"]);
+
+    pattern_builder.set_position_map(PositionMap::new_linear(20));
+    assert!(pattern_builder.zones().is_empty());
+}
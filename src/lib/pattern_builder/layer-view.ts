@@ -25,6 +25,13 @@ type Layer<T extends LayerMetadata> = {
     description: PropView<UnsupportedPropMetadata>,
     data: T,
     properties: [AnyPropView],
+    stats: LayerStats,
+}
+type LayerStats = {
+    total_frames: number,
+    last_ms: number|null,
+    average_ms: number|null,
+    max_ms: number|null,
 }
 type LayerMetadata = Object;
 type TextureLayerMetadata = {
@@ -39,6 +46,7 @@ type DisplayPane = 'Tree'|'Config'|'TreeAndConfig';
 type AnyPropView =
     PropView<NumPropMetadata> |
     PropView<NumVecPropMetadata> |
+    PropView<SeedPropMetadata> |
     PropView<StringPropMetadata> |
     PropView<OptionStringPropMetadata> |
     PropView<ColorPropMetadata> |
@@ -74,6 +82,11 @@ type NumVecPropMetadata = {
         sliders: [null | { range: NumRange, step: number }],
     }
 };
+type SeedPropMetadata = {
+    type: 'seed',
+    value: number,
+    data: {},
+};
 type StringPropMetadata = {
     type: 'string',
     value: string,
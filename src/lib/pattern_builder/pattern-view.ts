@@ -6,6 +6,8 @@ export class PatternView {
     #layerConfigs: Map<RandId, AnyLayer>;
     preview_colors: string[];
     selectedLayerId: RandId|null;
+    layerStats: Map<RandId, LayerStats>;
+    stackAverageMs: number|null;
 
     constructor(info: PatternInfo, data) {
         this.info = info;
@@ -13,6 +15,20 @@ export class PatternView {
         this.#layerConfigs = new Map<RandId, AnyLayer>(Object.entries(data.components));
         this.selectedLayerId = null;
         this.preview_colors = [];
+        this.layerStats = new Map();
+        this.stackAverageMs = null;
+    }
+
+    updateStats(stats: {stack: LayerStats, layers: {id: RandId, stats: LayerStats}[]}) {
+        this.stackAverageMs = stats.stack.average_ms;
+        this.layerStats = new Map(stats.layers.map((layer) => [layer.id, layer.stats]));
+    }
+
+    // Layers taking a large share of the frame are flagged in the tree.
+    isExpensive(layerId: RandId): boolean {
+        let averageMs = this.layerStats.get(layerId)?.average_ms ?? null;
+        return averageMs !== null && this.stackAverageMs !== null
+            && averageMs >= 0.5 && averageMs >= 0.25 * this.stackAverageMs;
     }
 
     getRootStack(): PropView<LayerStackPropMetadata> {